@@ -1,153 +1,981 @@
-use chrono::{Datelike, NaiveDate, Weekday};
+//! Parsing the arguments into a command.
+//!
+//! The first argument selects the command, and the other arguments are
+//! parsed by their position, so that e.g. a project can be named "week".
+//! Errors point at the argument that could not be parsed, and suggest the
+//! closest keyword or project if the argument looks misspelled.
 
-pub fn consume_bool(target: &str, args: Vec<String>) -> (bool, Vec<String>) {
-    // Check if the target string exists in the vector
-    let exists = args.iter().any(|s| s == target);
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
 
-    // Create a new vector with the target string removed
-    let filtered_args: Vec<String> = args.into_iter().filter(|s| *s != target).collect();
+use chrono::{Datelike, Duration, NaiveDate, NaiveTime, TimeDelta, Weekday};
 
-    // Return the tuple
-    (exists, filtered_args)
+use crate::day::Field;
+use crate::parse_time;
+use crate::traits::Parsable;
+
+pub const WEEKDAYS: &[&str] = &[
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+pub const MONTHS: &[&str] = &[
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+/// The commands selected by the first argument. Without one of them, the
+/// arguments report times, like `start 8`.
+const COMMANDS: &[&str] = &[
+    "add",
+    "undo",
+    "redo",
+    "history",
+    "compact",
+    "archive",
+    "check",
+    "compliance",
+    "convert",
+    "encrypt",
+    "decrypt",
+    "where",
+    "tui",
+    "shell",
+    "batch",
+    "config",
+    "suggest",
+    "import",
+    "completions",
+];
+
+/// The commands that the binary runs before the data is loaded
+const STANDALONE_COMMANDS: &[&str] = &["encrypt", "decrypt", "where", "shell", "batch"];
+
+/// The keywords of a command that reports times
+const ENTRY_KEYWORDS: &[&str] = &[
+    "start",
+    "stop",
+    "lunch",
+    "overtime",
+    "project",
+    "clear",
+    "show",
+    "yesterday",
+    "last",
+    "--weekend",
+];
+
+/// Options that belong to one command only
+const COMMAND_OPTIONS: &[(&str, &str)] = &[
+    ("--date", "undo"),
+    ("--record", "suggest"),
+    ("--format", "import"),
+    ("--add-projects", "import"),
+    ("--commit", "import"),
+];
+
+/// How similar a misspelled word must be to a keyword or a project to be
+/// suggested, from 0 to 1.
+const SIMILARITY: f64 = 0.8;
+
+/// The years that are taken as years, so that a mistyped number is not
+/// used as one, e.g. to archive or show the wrong year.
+const YEARS: RangeInclusive<i32> = 1900..=2100;
+
+const DAYS_HELP: &str = "DAY is a date like 2025-04-15, a weekday like monday, last monday or \
+yesterday. Without a day, today is used.";
+
+/// The usage and the description of each command and keyword, for `--help`
+const HELP: &[(&str, &str, &str)] = &[
+    (
+        "start",
+        "t [DAY...] start TIME",
+        "Report when work started, e.g. 8, 8:30 or 08:30.",
+    ),
+    (
+        "stop",
+        "t [DAY...] stop TIME",
+        "Report when work stopped, e.g. 17 or 17:30.",
+    ),
+    (
+        "lunch",
+        "t [DAY...] lunch DURATION",
+        "Report the length of the lunch, e.g. 45m or 0:45.",
+    ),
+    (
+        "overtime",
+        "t [DAY...] overtime DURATION",
+        "Report overtime, instead of the overtime from the rules in the settings.",
+    ),
+    (
+        "project",
+        "t [DAY...] project PROJECT DURATION",
+        "Report time on a project, given by its name or by its index in the table. Quote \
+names with spaces, e.g. \"Project A\".",
+    ),
+    (
+        "clear",
+        "t [DAY...] clear [start|stop|lunch|overtime|project PROJECT]",
+        "Remove one reported value, or all values of the days.",
+    ),
+    (
+        "show",
+        "t [DATE] show {[last] week|MONTH [YEAR]} [html|ics] [--weekend]",
+        "Show the week of DATE, by default the current week, or a month. html shows it in \
+the browser and ics prints it as a calendar.",
+    ),
+    ("add", "t add PROJECT", "Add a project to report time on."),
+    (
+        "undo",
+        "t undo [STEPS|--date DAY]",
+        "Undo the last command, the last STEPS commands, or the last command that changed \
+DAY.",
+    ),
+    ("redo", "t redo", "Redo the last undone command."),
+    (
+        "history",
+        "t history [COUNT]",
+        "Show the last COUNT commands that changed the data, by default 10.",
+    ),
+    (
+        "compact",
+        "t compact [DATE]",
+        "Combine the entries for the days before DATE into one entry per day. Changes to \
+these days can no longer be undone.",
+    ),
+    (
+        "archive",
+        "t archive YEAR",
+        "Move the days of a past year to an archive file next to the data file.",
+    ),
+    (
+        "check",
+        "t check [[last] week|MONTH [YEAR]|YEAR]",
+        "Report problems in the data, by default for the current month.",
+    ),
+    (
+        "compliance",
+        "t compliance [[last] week|MONTH [YEAR]|YEAR]",
+        "Check the working time regulations, by default for the current month.",
+    ),
+    (
+        "convert",
+        "t convert FILE",
+        "Copy all data to a new data file, e.g. a .sqlite file.",
+    ),
+    (
+        "encrypt",
+        "t encrypt",
+        "Encrypt the data file with a passphrase.",
+    ),
+    ("decrypt", "t decrypt", "Decrypt the data file."),
+    (
+        "where",
+        "t where",
+        "Print the paths of the data file and the settings file.",
+    ),
+    (
+        "tui",
+        "t tui",
+        "Browse and edit the weeks in a full-screen terminal UI.",
+    ),
+    (
+        "shell",
+        "t shell",
+        "Enter commands at a prompt, and save all changes together at the end.",
+    ),
+    (
+        "batch",
+        "t batch < FILE",
+        "Run the commands in FILE, one per line, and save the changes only if all of them \
+succeed.",
+    ),
+    (
+        "config",
+        "t config [get [KEY]|set KEY VALUE]",
+        "Show all settings, or get or set one of them.",
+    ),
+    (
+        "suggest",
+        "t suggest [DATE] [--record]",
+        "Suggest the project times of a day from git commits, and record them with --record.",
+    ),
+    (
+        "import",
+        "t import [--format {ics|toggl|clockify|generic-csv|FORMAT}] FILE [DATE [DATE]] \
+[--add-projects] [--commit]",
+        "Import days from a calendar or a CSV export, between the dates if given. Nothing is \
+saved without --commit.",
+    ),
+    (
+        "completions",
+        "t completions {bash|zsh|fish}",
+        "Print a completion script for the shell.",
+    ),
+];
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// Report times for days, and show them
+    Entry(Entry),
+    Add(String),
+    Undo(Undo),
+    Redo,
+    History(usize),
+    Compact(Option<NaiveDate>),
+    Archive(i32),
+    Check(Option<Period>),
+    Compliance(Option<Period>),
+    Convert(String),
+    Tui,
+    Config(ConfigCommand),
+    Suggest {
+        date: Option<NaiveDate>,
+        record: bool,
+    },
+    Import(Import),
+    Completions(Option<String>),
+    /// A command that the binary runs before the data is loaded, like
+    /// `encrypt`
+    Standalone(String),
 }
 
-pub fn consume_after_target(
-    target: &str,
-    args: Vec<String>,
-) -> (Result<Option<String>, String>, Vec<String>) {
-    args.iter().position(|s| s == target).map_or_else(
-        || (Ok(None), args.to_vec()),
-        |i| {
-            if i >= args.len() - 1 {
-                (Err(format!("No argument after {}", target)), args.to_vec())
-            } else {
-                let modified = args
-                    .iter()
-                    .enumerate()
-                    .filter_map(|(idx, s)| {
-                        if idx != i && idx != i + 1 {
-                            Some(s.clone())
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-
-                (Ok(Some(args[i + 1].clone())), modified)
+/// The values to report for the days, e.g. `monday tuesday start 8`.
+#[derive(Debug, Default, PartialEq)]
+pub struct Entry {
+    /// Empty for today
+    pub dates: Vec<NaiveDate>,
+    pub start: Option<NaiveTime>,
+    pub stop: Option<NaiveTime>,
+    pub lunch: Option<TimeDelta>,
+    pub overtime: Option<TimeDelta>,
+    pub projects: HashMap<String, TimeDelta>,
+    pub cleared: Vec<Field>,
+    pub show: Option<Show>,
+    pub weekend: bool,
+}
+
+impl Entry {
+    /// Whether anything is reported, rather than only shown.
+    pub fn has_content(&self) -> bool {
+        self.start.is_some()
+            || self.stop.is_some()
+            || self.lunch.is_some()
+            || self.overtime.is_some()
+            || !self.projects.is_empty()
+            || !self.cleared.is_empty()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Show {
+    pub period: Period,
+    pub format: Format,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Format {
+    Terminal,
+    Html,
+    Ics,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Period {
+    Week {
+        last: bool,
+    },
+    /// The month from 1 to 12. Without a year, the last such month up to
+    /// today.
+    Month {
+        month: u32,
+        year: Option<i32>,
+    },
+    Year(i32),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Undo {
+    Steps(usize),
+    Date(NaiveDate),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ConfigCommand {
+    Get(Option<String>),
+    Set(String, String),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Import {
+    pub file: String,
+    pub format: Option<String>,
+    pub dates: Vec<NaiveDate>,
+    pub add_projects: bool,
+    pub commit: bool,
+}
+
+pub fn parse(
+    args: &[String],
+    project_names: &[String],
+    today: NaiveDate,
+) -> Result<Command, String> {
+    Parser {
+        args,
+        index: 0,
+        project_names,
+        today,
+    }
+    .command()
+}
+
+/// The help for the first command or keyword in the arguments, or None if
+/// there is none.
+pub fn help(args: &[String]) -> Option<String> {
+    let (_, usage, description) = args
+        .iter()
+        .find_map(|arg| HELP.iter().find(|(name, _, _)| name == arg))?;
+    let text = format!("Usage: {}\n\n{}", usage, description);
+    if usage.contains("[DAY...]") {
+        return Some(format!("{} {}", text, DAYS_HELP));
+    }
+    Some(text)
+}
+
+/// Find the project name from a name or an index.
+pub fn project_name(project: &str, project_names: &[String]) -> Result<String, String> {
+    if project_names.iter().any(|name| name == project) {
+        return Ok(project.to_string());
+    }
+    let project_index: usize = match project.parse() {
+        Ok(project_index) => project_index,
+        Err(_) => {
+            return Err(format!(
+                "Unknown project '{}'{}",
+                project,
+                did_you_mean(project, project_names.iter().map(|name| name.as_str()))
+            ))
+        }
+    };
+    if project_index == 0 {
+        return Err("No project with index 0".to_string());
+    }
+    if project_index == 1 {
+        return Err("Cannot report time on default project".to_string());
+    }
+    // -2 here since the first non-default project has index 2
+    match project_names.get(project_index - 2) {
+        Some(project_name) => Ok(project_name.to_string()),
+        None => Err(format!("No project with index {}", project_index)),
+    }
+}
+
+/// The candidate most similar to the word, if any is similar enough.
+pub fn closest<'a>(word: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let word = word.to_lowercase();
+    candidates
+        .into_iter()
+        .map(|candidate| {
+            let similarity = strsim::jaro_winkler(&word, &candidate.to_lowercase());
+            (similarity, candidate)
+        })
+        .filter(|(similarity, _)| *similarity >= SIMILARITY)
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, candidate)| candidate)
+}
+
+/// ". Did you mean 'start'?" for the closest candidate, or nothing.
+fn did_you_mean<'a>(word: &str, candidates: impl IntoIterator<Item = &'a str>) -> String {
+    closest(word, candidates).map_or(String::new(), |candidate| {
+        format!(". Did you mean '{}'?", candidate)
+    })
+}
+
+struct Parser<'a> {
+    args: &'a [String],
+    /// The next argument to parse
+    index: usize,
+    project_names: &'a [String],
+    today: NaiveDate,
+}
+
+impl<'a> Parser<'a> {
+    fn command(&mut self) -> Result<Command, String> {
+        let Some(name) = self.peek() else {
+            return Ok(Command::Entry(Entry::default()));
+        };
+        if !COMMANDS.contains(&name) {
+            return Ok(Command::Entry(self.entry()?));
+        }
+        self.index += 1;
+        let command = match name {
+            "add" => Command::Add(self.value("add")?.to_string()),
+            "undo" => Command::Undo(self.undo()?),
+            "redo" => Command::Redo,
+            "history" => Command::History(match self.peek() {
+                Some(_) => self.count([])?,
+                None => 10,
+            }),
+            "compact" => Command::Compact(match self.peek() {
+                Some(_) => Some(self.date_value()?),
+                None => None,
+            }),
+            "archive" => Command::Archive(self.archive_year()?),
+            "check" => Command::Check(self.period("Unknown period", true)?),
+            "compliance" => Command::Compliance(self.period("Unknown period", true)?),
+            "convert" => match self.next() {
+                Some(file) => Command::Convert(file.to_string()),
+                None => return Err("A file to convert to must be given".to_string()),
+            },
+            "tui" => Command::Tui,
+            "config" => Command::Config(self.config()?),
+            "suggest" => self.suggest()?,
+            "import" => Command::Import(self.import()?),
+            "completions" => Command::Completions(self.next().map(|shell| shell.to_string())),
+            _ => {
+                debug_assert!(STANDALONE_COMMANDS.contains(&name));
+                Command::Standalone(name.to_string())
             }
-        },
-    )
-}
-pub fn consume_two_after_target(
-    target: &str,
-    args: Vec<String>,
-) -> (Result<Option<(String, String)>, String>, Vec<String>) {
-    // First, find the position of the target without holding a borrow
-    let pos = args.iter().position(|s| s == target);
-
-    match pos {
-        None => (Ok(None), args),
-        Some(i) => {
-            if i + 2 >= args.len() {
-                (Err(format!("Not enough arguments after {}", target)), args)
-            } else {
-                // Extract the two values after the target
-                let s1 = args[i + 1].clone();
-                let s2 = args[i + 2].clone();
-
-                // Create a new vector excluding the target and the next two elements
-                let modified: Vec<String> = args
-                    .into_iter() // Take ownership of args
-                    .enumerate()
-                    .filter_map(|(idx, s)| {
-                        if idx != i && idx != i + 1 && idx != i + 2 {
-                            Some(s)
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-
-                (Ok(Some((s1, s2))), modified)
+        };
+        self.end()?;
+        Ok(command)
+    }
+
+    fn entry(&mut self) -> Result<Entry, String> {
+        let mut entry = Entry::default();
+        let mut given: Vec<&str> = Vec::new();
+        while let Some(arg) = self.peek() {
+            let index = self.index;
+            if let Some(date) = self.date() {
+                entry.dates.push(date);
+                continue;
+            }
+            self.index += 1;
+            if ["start", "stop", "lunch", "overtime", "show"].contains(&arg) {
+                if given.contains(&arg) {
+                    return Err(self.error_at(index, format!("{} is given twice", arg)));
+                }
+                given.push(arg);
+            }
+            match arg {
+                "start" => entry.start = Some(self.time("start")?),
+                "stop" => entry.stop = Some(self.time("stop")?),
+                "lunch" => entry.lunch = Some(self.duration("lunch")?),
+                "overtime" => entry.overtime = Some(self.duration("overtime")?),
+                "project" => {
+                    let project = self.project("project")?;
+                    let timedelta = self.duration(&format!("project {}", project))?;
+                    entry.projects.insert(project, timedelta);
+                }
+                "clear" => entry.cleared.extend(self.clear()?),
+                "show" => entry.show = Some(self.show()?),
+                "--weekend" => entry.weekend = true,
+                "last" => {
+                    return Err(
+                        self.error_at(index, "last must be followed by a weekday".to_string())
+                    )
+                }
+                _ => {
+                    let option = COMMAND_OPTIONS.iter().find(|(option, _)| *option == arg);
+                    if let Some((option, command)) = option {
+                        return Err(self.error_at(
+                            index,
+                            format!("{} can only be used with {}", option, command),
+                        ));
+                    }
+                    let mut candidates = [ENTRY_KEYWORDS, WEEKDAYS].concat();
+                    // Commands are only recognized first
+                    if index == 0 {
+                        candidates.extend(COMMANDS);
+                    }
+                    return Err(self.unknown(index, candidates));
+                }
             }
         }
+        Ok(entry)
     }
-}
-pub fn consume_dates(args: Vec<String>, today: NaiveDate) -> (Vec<NaiveDate>, Vec<String>) {
-    let mut dates = Vec::new(); // To store the collected dates
-    let mut remaining_args = args; // Start with the full input arguments
 
-    loop {
-        // Call consume_date with the remaining arguments
-        let (date, new_remaining_args) = consume_date(remaining_args, today);
+    /// A day, if the next argument is one: a date, a weekday in the current
+    /// week, `last WEEKDAY` or `yesterday`.
+    fn date(&mut self) -> Option<NaiveDate> {
+        let arg = self.peek()?.to_lowercase();
+        let date = if arg == "yesterday" {
+            self.today
+                .pred_opt()
+                .expect("the day is not the first day in history")
+        } else if arg == "last" {
+            let weekday = self.args.get(self.index + 1).and_then(|arg| weekday(arg))?;
+            self.index += 1;
+            self.weekday_date(weekday) - Duration::try_weeks(1).expect("hardcoded int")
+        } else if let Some(weekday) = weekday(&arg) {
+            self.weekday_date(weekday)
+        } else {
+            NaiveDate::parse_from_str(&arg, "%Y-%m-%d").ok()?
+        };
+        self.index += 1;
+        Some(date)
+    }
 
-        match date {
-            Some(d) => {
-                // If a date was found, add it to the dates vector
-                dates.push(d);
-                // Update the remaining arguments for the next iteration
-                remaining_args = new_remaining_args;
+    /// The date of the weekday in the current week.
+    fn weekday_date(&self, weekday: Weekday) -> NaiveDate {
+        let days_since_monday = self.today.weekday().num_days_from_monday();
+        self.today - Duration::try_days(days_since_monday as i64).expect("must be 0-6")
+            + Duration::try_days(weekday.num_days_from_monday() as i64).expect("must be 0-6")
+    }
+
+    fn date_value(&mut self) -> Result<NaiveDate, String> {
+        let index = self.index;
+        match self.date() {
+            Some(date) => Ok(date),
+            None => Err(self.error_at(
+                index,
+                format!("Could not parse date '{}'", self.args[index]),
+            )),
+        }
+    }
+
+    fn time(&mut self, keyword: &str) -> Result<NaiveTime, String> {
+        let text = self.value(keyword)?;
+        parse_time(text).map_err(|message| self.error_at(self.index - 1, message))
+    }
+
+    fn duration(&mut self, keyword: &str) -> Result<TimeDelta, String> {
+        let text = self.value(keyword)?;
+        TimeDelta::from_str(text).map_err(|message| self.error_at(self.index - 1, message))
+    }
+
+    fn project(&mut self, keyword: &str) -> Result<String, String> {
+        let text = self.value(keyword)?;
+        project_name(text, self.project_names)
+            .map_err(|message| self.error_at(self.index - 1, message))
+    }
+
+    /// The fields after `clear`: one field, or else all of them.
+    fn clear(&mut self) -> Result<Vec<Field>, String> {
+        let field = match self.peek() {
+            Some("start") => Field::Start,
+            Some("stop") => Field::Stop,
+            Some("lunch") => Field::Lunch,
+            Some("overtime") => Field::Overtime,
+            Some("project") => {
+                self.index += 1;
+                return Ok(vec![Field::Project(self.project("clear project")?)]);
             }
-            None => {
-                // If no date was found, return the collected dates and remaining arguments
-                return (dates, new_remaining_args);
+            _ => {
+                return Ok(vec![
+                    Field::Start,
+                    Field::Stop,
+                    Field::Lunch,
+                    Field::Overtime,
+                    Field::Projects,
+                ])
             }
+        };
+        self.index += 1;
+        Ok(vec![field])
+    }
+
+    fn show(&mut self) -> Result<Show, String> {
+        if self.peek().is_none() {
+            return Err(self.error_at(self.index - 1, "No argument after show".to_string()));
+        }
+        let period = self
+            .period("Unknown show command", false)?
+            .expect("there is an argument");
+        let format = match self.peek() {
+            Some("html") => Format::Html,
+            Some("ics") => Format::Ics,
+            _ => Format::Terminal,
+        };
+        if format != Format::Terminal {
+            self.index += 1;
         }
+        Ok(Show { period, format })
     }
-}
 
-fn consume_date(args: Vec<String>, today: NaiveDate) -> (Option<NaiveDate>, Vec<String>) {
-    for (index, arg) in args.iter().enumerate() {
-        match date_from_arg(arg, today) {
-            Some(date) => {
-                // Create new vector without the weekday string
-                let modified = args
-                    .into_iter()
-                    .enumerate()
-                    .filter_map(|(i, s)| (i != index).then_some(s))
-                    .collect();
+    /// `[last] week`, `MONTH [YEAR]` or, if `allow_year`, `YEAR`. None if
+    /// there are no more arguments.
+    fn period(&mut self, unknown: &str, allow_year: bool) -> Result<Option<Period>, String> {
+        let index = self.index;
+        let Some(arg) = self.next() else {
+            return Ok(None);
+        };
+        if arg == "week" {
+            return Ok(Some(Period::Week { last: false }));
+        }
+        if arg == "last" {
+            return match self.next() {
+                Some("week") => Ok(Some(Period::Week { last: true })),
+                _ => Err(self.error_at(index, "last must be followed by week".to_string())),
+            };
+        }
+        if let Some(position) = MONTHS.iter().position(|month| *month == arg) {
+            let year = self.peek().and_then(year);
+            if year.is_some() {
+                self.index += 1;
+            }
+            return Ok(Some(Period::Month {
+                month: position as u32 + 1,
+                year,
+            }));
+        }
+        if let (true, Some(year)) = (allow_year, year(arg)) {
+            return Ok(Some(Period::Year(year)));
+        }
+        let candidates = [&["week", "last"], MONTHS].concat();
+        Err(self.error_at(
+            index,
+            format!("{}: {}{}", unknown, arg, did_you_mean(arg, candidates)),
+        ))
+    }
 
-                return (Some(date), modified);
+    fn undo(&mut self) -> Result<Undo, String> {
+        match self.peek() {
+            None => Ok(Undo::Steps(1)),
+            Some("--date") => {
+                self.index += 1;
+                if self.peek().is_none() {
+                    return Err(
+                        self.error_at(self.index - 1, "No argument after --date".to_string())
+                    );
+                }
+                Ok(Undo::Date(self.date_value()?))
             }
-            None => (),
-        }
-    }
-    (None, args)
-}
-
-fn date_from_arg(arg: &String, today: NaiveDate) -> Option<NaiveDate> {
-    if arg.to_lowercase() == "yesterday" {
-        let yesterday = today
-            .pred_opt()
-            .expect("the day is not the first day in history");
-        return Some(yesterday);
-    }
-
-    let weekdays = [
-        "monday",
-        "tuesday",
-        "wednesday",
-        "thursday",
-        "friday",
-        "saturday",
-        "sunday",
-    ];
-    match weekdays.iter().position(|&x| x == arg.to_lowercase()) {
-        Some(position) => {
-            let weekday = Weekday::try_from(position as u8).unwrap();
-            let days_since_monday = today.weekday().num_days_from_monday();
-            let target_days_since_monday = weekday.num_days_from_monday();
-            let date = today
-                - chrono::Duration::try_days(days_since_monday as i64).expect("must be 0-6")
-                + chrono::Duration::try_days(target_days_since_monday as i64).expect("must be 0-6");
-            return Some(date);
-        }
-        None => (),
-    }
-    // If no weekday found, try parsing from date
-    match NaiveDate::parse_from_str(arg, "%Y-%m-%d") {
-        Ok(date) => return Some(date),
-        Err(_) => (),
-    }
-    return None;
+            Some(_) => Ok(Undo::Steps(self.count(["--date"])?)),
+        }
+    }
+
+    /// A positive number, e.g. of steps to undo, or else an error suggesting
+    /// the candidates.
+    fn count<'b>(
+        &mut self,
+        candidates: impl IntoIterator<Item = &'b str>,
+    ) -> Result<usize, String> {
+        let index = self.index;
+        match self.next().map(|arg| arg.parse::<usize>()) {
+            Some(Ok(count)) if count > 0 => Ok(count),
+            _ => Err(self.unknown(index, candidates)),
+        }
+    }
+
+    fn archive_year(&mut self) -> Result<i32, String> {
+        let index = self.index;
+        match self.next() {
+            None => Err("A year must be given to archive".to_string()),
+            Some(text) => match year(text) {
+                Some(year) => Ok(year),
+                None => Err(self.error_at(index, format!("Invalid year '{}'", text))),
+            },
+        }
+    }
+
+    fn config(&mut self) -> Result<ConfigCommand, String> {
+        let index = self.index;
+        match self.next() {
+            None => Ok(ConfigCommand::Get(None)),
+            Some("get") => Ok(ConfigCommand::Get(self.next().map(|key| key.to_string()))),
+            Some("set") if self.args.len() > self.index + 1 => {
+                let key = self.args[self.index].clone();
+                // The rest is the value, e.g. "6:00 30m, 9:00 45m"
+                let value = self.args[self.index + 1..].join(" ");
+                self.index = self.args.len();
+                Ok(ConfigCommand::Set(key, value))
+            }
+            Some(arg) => Err(self.error_at(
+                index,
+                format!(
+                    "Usage: t config [get [KEY]|set KEY VALUE]{}",
+                    did_you_mean(arg, ["get", "set"])
+                ),
+            )),
+        }
+    }
+
+    fn suggest(&mut self) -> Result<Command, String> {
+        let mut date = None;
+        let mut record = false;
+        while let Some(arg) = self.peek() {
+            let index = self.index;
+            if arg == "--record" {
+                record = true;
+                self.index += 1;
+            } else if let Some(day) = self.date() {
+                if date.is_some() {
+                    return Err(
+                        self.error_at(index, "Only one date can be given to suggest".to_string())
+                    );
+                }
+                date = Some(day);
+            } else {
+                return Err(self.unknown(index, ["--record"]));
+            }
+        }
+        Ok(Command::Suggest { date, record })
+    }
+
+    fn import(&mut self) -> Result<Import, String> {
+        let mut file = None;
+        let mut import = Import {
+            file: String::new(),
+            format: None,
+            dates: Vec::new(),
+            add_projects: false,
+            commit: false,
+        };
+        while let Some(arg) = self.peek() {
+            match arg {
+                "--format" => {
+                    self.index += 1;
+                    import.format = Some(self.value("--format")?.to_string());
+                }
+                "--add-projects" | "--commit" => {
+                    self.index += 1;
+                    import.add_projects |= arg == "--add-projects";
+                    import.commit |= arg == "--commit";
+                }
+                _ if file.is_none() => {
+                    self.index += 1;
+                    file = Some(arg.to_string());
+                }
+                _ if import.dates.len() < 2 => import.dates.push(self.date_value()?),
+                _ => return Err(self.unknown(self.index, ["--add-projects", "--commit"])),
+            }
+        }
+        match file {
+            Some(file) => Ok(Import { file, ..import }),
+            None => Err(self.error_at(self.index - 1, "No file to import".to_string())),
+        }
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.args.get(self.index).map(|arg| arg.as_str())
+    }
+
+    fn next(&mut self) -> Option<&'a str> {
+        let arg = self.peek();
+        if arg.is_some() {
+            self.index += 1;
+        }
+        arg
+    }
+
+    /// The argument after a keyword, e.g. the time after `start`.
+    fn value(&mut self, keyword: &str) -> Result<&'a str, String> {
+        match self.next() {
+            Some(arg) => Ok(arg),
+            None => Err(self.error_at(
+                self.args.len() - 1,
+                format!("No argument after {}", keyword),
+            )),
+        }
+    }
+
+    /// Fail if there are arguments left.
+    fn end(&self) -> Result<(), String> {
+        match self.peek() {
+            Some(_) => Err(self.unknown(self.index, [])),
+            None => Ok(()),
+        }
+    }
+
+    fn unknown<'b>(&self, index: usize, candidates: impl IntoIterator<Item = &'b str>) -> String {
+        let arg = &self.args[index];
+        self.error_at(
+            index,
+            format!(
+                "Unknown or extra argument '{}'{}",
+                arg,
+                did_you_mean(arg, candidates)
+            ),
+        )
+    }
+
+    /// The message followed by the command with the argument at the index
+    /// marked, e.g.
+    ///
+    /// ```text
+    /// Unknown or extra argument 'strat'. Did you mean 'start'?
+    ///   t 2025-04-15 strat 8
+    ///                ^^^^^
+    /// ```
+    fn error_at(&self, index: usize, message: String) -> String {
+        let args: Vec<String> = self
+            .args
+            .iter()
+            .map(|arg| {
+                if arg.is_empty() || arg.contains(char::is_whitespace) {
+                    format!("\"{}\"", arg)
+                } else {
+                    arg.clone()
+                }
+            })
+            .collect();
+        let offset: usize = args[..index]
+            .iter()
+            .map(|arg| arg.chars().count() + 1)
+            .sum();
+        format!(
+            "{}\n  t {}\n    {}{}",
+            message,
+            args.join(" "),
+            " ".repeat(offset),
+            "^".repeat(args[index].chars().count())
+        )
+    }
+}
+
+fn weekday(text: &str) -> Option<Weekday> {
+    let text = text.to_lowercase();
+    let position = WEEKDAYS.iter().position(|weekday| *weekday == text)?;
+    Weekday::try_from(position as u8).ok()
+}
+
+/// A four digit year, like 2025.
+fn year(text: &str) -> Option<i32> {
+    if text.len() != 4 || !text.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok().filter(|year| YEARS.contains(year))
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{NaiveDate, NaiveTime, TimeDelta};
+    use rstest::rstest;
+
+    use crate::argparse::{
+        closest, help, parse, Command, ConfigCommand, Entry, Format, Period, Show, Undo,
+    };
+
+    /// Wednesday
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 4, 16).expect("")
+    }
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").expect("")
+    }
+
+    fn parse_str(text: &str) -> Result<Command, String> {
+        let args: Vec<String> = text.split_whitespace().map(String::from).collect();
+        parse(
+            &args,
+            &["week".to_string(), "Sickness".to_string()],
+            today(),
+        )
+    }
+
+    #[rstest]
+    fn days_and_times() {
+        assert_eq!(
+            parse_str("last monday 2025-04-15 start 8 stop 17:30 lunch 45m"),
+            Ok(Command::Entry(Entry {
+                dates: vec![date("2025-04-07"), date("2025-04-15")],
+                start: NaiveTime::from_hms_opt(8, 0, 0),
+                stop: NaiveTime::from_hms_opt(17, 30, 0),
+                lunch: TimeDelta::try_minutes(45),
+                ..Entry::default()
+            }))
+        );
+    }
+
+    #[rstest]
+    #[case::name("project week 1")]
+    #[case::index("project 2 1")]
+    fn project_named_like_a_keyword(#[case] text: &str) {
+        let Ok(Command::Entry(entry)) = parse_str(text) else {
+            panic!("not an entry");
+        };
+
+        assert_eq!(entry.projects["week"], TimeDelta::try_hours(1).expect(""));
+    }
+
+    #[rstest]
+    #[case("show week", Period::Week { last: false }, Format::Terminal)]
+    #[case("show last week html", Period::Week { last: true }, Format::Html)]
+    #[case("show december 2024 ics", Period::Month { month: 12, year: Some(2024) }, Format::Ics)]
+    fn show(#[case] text: &str, #[case] period: Period, #[case] format: Format) {
+        assert_eq!(
+            parse_str(text),
+            Ok(Command::Entry(Entry {
+                show: Some(Show { period, format }),
+                ..Entry::default()
+            }))
+        );
+    }
+
+    #[rstest]
+    #[case("undo", Command::Undo(Undo::Steps(1)))]
+    #[case("undo 3", Command::Undo(Undo::Steps(3)))]
+    #[case("undo --date yesterday", Command::Undo(Undo::Date(date("2025-04-15"))))]
+    #[case("check 2024", Command::Check(Some(Period::Year(2024))))]
+    #[case("check", Command::Check(None))]
+    #[case(
+        "config set lunch.breaks 6:00 30m",
+        Command::Config(ConfigCommand::Set("lunch.breaks".to_string(), "6:00 30m".to_string()))
+    )]
+    #[case("add last", Command::Add("last".to_string()))]
+    fn commands(#[case] text: &str, #[case] expected: Command) {
+        assert_eq!(parse_str(text), Ok(expected));
+    }
+
+    #[rstest]
+    fn error_points_at_the_argument() {
+        assert_eq!(
+            parse_str("2025-04-15 strat 8"),
+            Err([
+                "Unknown or extra argument 'strat'. Did you mean 'start'?",
+                "  t 2025-04-15 strat 8",
+                "               ^^^^^",
+            ]
+            .join("\n"))
+        );
+    }
+
+    #[rstest]
+    #[case("undoo", "Unknown or extra argument 'undoo'. Did you mean 'undo'?")]
+    #[case("show wek", "Unknown show command: wek. Did you mean 'week'?")]
+    #[case(
+        "project Sickess 1",
+        "Unknown project 'Sickess'. Did you mean 'Sickness'?"
+    )]
+    #[case("start 8 start 9", "start is given twice")]
+    #[case("start", "No argument after start")]
+    #[case("--commit", "--commit can only be used with import")]
+    #[case("history 3 4", "Unknown or extra argument '4'")]
+    #[case("archive 0201", "Invalid year '0201'")]
+    #[case("show 3024", "Unknown show command: 3024")]
+    fn errors(#[case] text: &str, #[case] expected: &str) {
+        let error = parse_str(text).expect_err("should fail");
+
+        assert!(error.starts_with(expected), "{}", error);
+    }
+
+    #[rstest]
+    fn no_suggestion_for_unrelated_word() {
+        assert_eq!(closest("blargh", ["start", "stop"]), None);
+    }
+
+    #[rstest]
+    fn help_for_keyword_explains_days() {
+        let text = help(&["lunch".to_string(), "--help".to_string()]).expect("has help");
+
+        assert!(text.starts_with("Usage: t [DAY...] lunch DURATION"));
+        assert!(text.contains("DAY is a date"));
+    }
 }
@@ -0,0 +1,376 @@
+//! Finding days that are missing data or have values that are probably wrong.
+
+use std::collections::HashMap;
+
+use chrono::{Datelike, NaiveDate, TimeDelta, Weekday};
+
+use crate::day::Day;
+use crate::lunch::LunchRules;
+use crate::table::{default_project_time, worked_time};
+use crate::traits::Parsable;
+
+/// More worked time than this in a day is probably a mistake
+const MAX_WORKED_HOURS: i64 = 12;
+/// A longer lunch than this is probably a mistake
+const MAX_LUNCH_HOURS: i64 = 3;
+
+/// A problem with the data of a date, and a command that fixes it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Finding {
+    pub date: NaiveDate,
+    pub problem: String,
+    pub fix: String,
+}
+
+impl Finding {
+    fn new(date: NaiveDate, problem: String, fix: &str) -> Self {
+        Self {
+            date,
+            problem,
+            fix: format!("t {} {}", date, fix),
+        }
+    }
+}
+
+/// Check the days from `first_date` to `last_date`, up to and including
+/// today. `entries` are the entries of the log, for finding the ones that
+/// were added more than once.
+///
+/// Today is not expected to have a stop time or lunch yet, and days with an
+/// implied lunch from `lunch_rules` are not missing a lunch.
+pub fn check(
+    first_date: NaiveDate,
+    last_date: NaiveDate,
+    day_from_date: &HashMap<NaiveDate, Day>,
+    entries: &[Day],
+    lunch_rules: &LunchRules,
+    today: NaiveDate,
+) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for date in first_date
+        .iter_days()
+        .take_while(|date| *date <= last_date.min(today))
+    {
+        match day_from_date.get(&date).filter(|day| day.has_content()) {
+            Some(day) => findings.extend(check_day(&lunch_rules.fill(day), date == today)),
+            None if !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && date < today => {
+                findings.push(Finding::new(
+                    date,
+                    "No data for a weekday".to_string(),
+                    "start TIME stop TIME lunch TIME",
+                ))
+            }
+            None => (),
+        }
+        findings.extend(check_duplicates(date, entries));
+    }
+    findings
+}
+
+fn check_day(day: &Day, is_today: bool) -> Vec<Finding> {
+    let date = day.date;
+    let mut findings = Vec::new();
+    match (day.start, day.stop) {
+        (Some(_), None) if !is_today => findings.push(Finding::new(
+            date,
+            "Start but no stop".to_string(),
+            "stop TIME",
+        )),
+        (None, Some(_)) => findings.push(Finding::new(
+            date,
+            "Stop but no start".to_string(),
+            "start TIME",
+        )),
+        (None, None) if !day.projects.is_empty() => findings.push(Finding::new(
+            date,
+            "Project time but no start or stop".to_string(),
+            "start TIME stop TIME",
+        )),
+        (Some(start), Some(stop)) if stop <= start => findings.push(Finding::new(
+            date,
+            format!(
+                "Stop {} is not after start {}",
+                stop.format("%H:%M"),
+                start.format("%H:%M")
+            ),
+            "start TIME stop TIME",
+        )),
+        (Some(_), Some(_)) if day.lunch.is_none() => {
+            findings.push(Finding::new(date, "No lunch".to_string(), "lunch TIME"))
+        }
+        _ => (),
+    }
+    if let Some(lunch) = day.lunch {
+        if lunch < TimeDelta::zero() || lunch > TimeDelta::try_hours(MAX_LUNCH_HOURS).expect("") {
+            findings.push(Finding::new(
+                date,
+                format!("Lunch of {} is implausible", lunch.to_hhmm()),
+                "lunch TIME",
+            ));
+        }
+    }
+    match worked_time(day) {
+        Some(worked) if worked > TimeDelta::try_hours(MAX_WORKED_HOURS).expect("") => findings
+            .push(Finding::new(
+                date,
+                format!(
+                    "Worked {}, which is more than {} hours",
+                    worked.to_hhmm(),
+                    MAX_WORKED_HOURS
+                ),
+                "start TIME stop TIME",
+            )),
+        _ => (),
+    }
+    match default_project_time(day) {
+        Some(timedelta)
+            if timedelta < TimeDelta::zero() && worked_time(day) > Some(TimeDelta::zero()) =>
+        {
+            // The project with the most time is the most likely to be wrong
+            let project = day
+                .projects
+                .iter()
+                .max_by_key(|(name, timedelta)| (**timedelta, (*name).clone()))
+                .map(|(name, _)| name.clone())
+                .expect("negative default project time needs a project");
+            findings.push(Finding::new(
+                date,
+                format!(
+                    "Project time {} is more than the worked time {}",
+                    day.projects.values().sum::<TimeDelta>().to_hhmm(),
+                    worked_time(day).expect("has start and stop").to_hhmm()
+                ),
+                &format!("project {} TIME", project),
+            ));
+        }
+        _ => (),
+    }
+    findings
+}
+
+/// Entries for the date that are identical to the entry before them, e.g.
+/// when the same command was run twice.
+fn check_duplicates(date: NaiveDate, entries: &[Day]) -> Vec<Finding> {
+    let entries: Vec<&Day> = entries.iter().filter(|day| day.date == date).collect();
+    let duplicate_count = entries
+        .windows(2)
+        .filter(|pair| is_same_entry(pair[0], pair[1]))
+        .count();
+    if duplicate_count == 0 {
+        return Vec::new();
+    }
+    // Undoing removes the last entry for the date, which only helps if that
+    // is a duplicate. Compacting combines all entries before a date.
+    let fix = match entries.as_slice() {
+        [.., before_last, last] if is_same_entry(before_last, last) => {
+            format!("t undo --date {}", date)
+        }
+        _ => format!("t compact {}", date + TimeDelta::try_days(1).expect("")),
+    };
+    vec![Finding {
+        date,
+        problem: format!(
+            "The same entry was added {} times in a row",
+            duplicate_count + 1
+        ),
+        fix,
+    }]
+}
+
+fn is_same_entry(first: &Day, second: &Day) -> bool {
+    first.start == second.start
+        && first.stop == second.stop
+        && first.lunch == second.lunch
+        && first.projects == second.projects
+        && first.cleared == second.cleared
+}
+
+/// The findings as text, one finding per line followed by the fix.
+pub fn format_findings(
+    findings: &[Finding],
+    first_date: NaiveDate,
+    last_date: NaiveDate,
+) -> String {
+    if findings.is_empty() {
+        return format!("No problems found from {} to {}", first_date, last_date);
+    }
+    findings
+        .iter()
+        .map(|finding| {
+            format!(
+                "{} {}: {}\n  Fix: {}",
+                finding.date,
+                finding.date.format("%a"),
+                finding.problem,
+                finding.fix
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use chrono::{NaiveDate, NaiveTime, TimeDelta};
+    use rstest::rstest;
+
+    use crate::check::{check, Finding};
+    use crate::day::Day;
+    use crate::lunch::LunchRules;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 4, day).expect("")
+    }
+
+    fn day(
+        day_of_month: u32,
+        start: Option<&str>,
+        stop: Option<&str>,
+        lunch_minutes: Option<i64>,
+        projects: &[(&str, i64)],
+    ) -> Day {
+        let time = |text: &str| NaiveTime::parse_from_str(text, "%H:%M").expect("");
+        Day {
+            date: date(day_of_month),
+            start: start.map(time),
+            stop: stop.map(time),
+            lunch: lunch_minutes.map(|minutes| TimeDelta::try_minutes(minutes).expect("")),
+            projects: projects
+                .iter()
+                .map(|(name, hours)| (name.to_string(), TimeDelta::try_hours(*hours).expect("")))
+                .collect(),
+            overtime: None,
+            cleared: Vec::new(),
+        }
+    }
+
+    /// Check Tuesday 2025-04-15, with today being the day after.
+    fn check_one(day: Day) -> Vec<Finding> {
+        let entries = vec![day.clone()];
+        let day_from_date = HashMap::from([(day.date, day)]);
+        check(
+            date(15),
+            date(15),
+            &day_from_date,
+            &entries,
+            &LunchRules::default(),
+            date(16),
+        )
+    }
+
+    fn problems(findings: Vec<Finding>) -> Vec<String> {
+        findings
+            .into_iter()
+            .map(|finding| finding.problem)
+            .collect()
+    }
+
+    #[rstest]
+    fn complete_day_has_no_findings() {
+        assert_eq!(
+            check_one(day(15, Some("08:00"), Some("16:30"), Some(45), &[("A", 2)])),
+            vec![]
+        );
+    }
+
+    #[rstest]
+    #[case(day(15, Some("08:00"), None, Some(45), &[]), "Start but no stop", "t 2025-04-15 stop TIME")]
+    #[case(day(15, None, Some("16:00"), Some(45), &[]), "Stop but no start", "t 2025-04-15 start TIME")]
+    #[case(day(15, Some("16:00"), Some("08:00"), Some(45), &[]), "Stop 08:00 is not after start 16:00", "t 2025-04-15 start TIME stop TIME")]
+    #[case(day(15, Some("08:00"), Some("16:00"), None, &[]), "No lunch", "t 2025-04-15 lunch TIME")]
+    #[case(day(15, Some("06:00"), Some("21:00"), Some(60), &[]), "Worked 14:00, which is more than 12 hours", "t 2025-04-15 start TIME stop TIME")]
+    #[case(day(15, Some("08:00"), Some("16:00"), Some(240), &[]), "Lunch of 04:00 is implausible", "t 2025-04-15 lunch TIME")]
+    #[case(day(15, Some("08:00"), Some("12:00"), Some(0), &[("A", 1), ("B", 5)]), "Project time 06:00 is more than the worked time 04:00", "t 2025-04-15 project B TIME")]
+    fn finding(#[case] day: Day, #[case] problem: &str, #[case] fix: &str) {
+        assert_eq!(
+            check_one(day),
+            vec![Finding {
+                date: date(15),
+                problem: problem.to_string(),
+                fix: fix.to_string()
+            }]
+        );
+    }
+
+    #[rstest]
+    fn missing_weekdays_before_today() {
+        let findings = check(
+            date(11),
+            date(20),
+            &HashMap::new(),
+            &[],
+            &LunchRules::default(),
+            date(16),
+        );
+
+        let dates: Vec<NaiveDate> = findings.iter().map(|finding| finding.date).collect();
+        // Friday, Monday and Tuesday, but not the weekend, today or later
+        assert_eq!(dates, vec![date(11), date(14), date(15)]);
+    }
+
+    #[rstest]
+    fn today_without_stop_is_fine() {
+        let today = day(16, Some("08:00"), None, None, &[]);
+        let day_from_date = HashMap::from([(today.date, today.clone())]);
+
+        assert_eq!(
+            check(
+                date(16),
+                date(16),
+                &day_from_date,
+                &[today],
+                &LunchRules::default(),
+                date(16)
+            ),
+            vec![]
+        );
+    }
+
+    #[rstest]
+    fn same_entry_twice() {
+        let entry = day(15, None, None, None, &[("A", 2)]);
+        let complete = day(15, Some("08:00"), Some("16:30"), Some(45), &[("A", 2)]);
+        let entries = vec![entry.clone(), entry];
+        let day_from_date = HashMap::from([(complete.date, complete)]);
+
+        let findings = check(
+            date(15),
+            date(15),
+            &day_from_date,
+            &entries,
+            &LunchRules::default(),
+            date(16),
+        );
+
+        assert_eq!(
+            findings,
+            vec![Finding {
+                date: date(15),
+                problem: "The same entry was added 2 times in a row".to_string(),
+                fix: "t undo --date 2025-04-15".to_string()
+            }]
+        );
+    }
+
+    #[rstest]
+    fn same_entry_twice_before_other_entry() {
+        let entry = day(15, None, None, None, &[("A", 2)]);
+        let complete = day(15, Some("08:00"), Some("16:30"), Some(45), &[("A", 2)]);
+        let entries = vec![entry.clone(), entry, complete.clone()];
+        let day_from_date = HashMap::from([(complete.date, complete)]);
+
+        let findings = check(
+            date(15),
+            date(15),
+            &day_from_date,
+            &entries,
+            &LunchRules::default(),
+            date(16),
+        );
+
+        assert_eq!(problems(findings.clone()).len(), 1);
+        assert_eq!(findings[0].fix, "t compact 2025-04-16");
+    }
+}
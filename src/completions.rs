@@ -0,0 +1,187 @@
+//! Shell completion scripts for bash, zsh and fish, `t completions SHELL`.
+//!
+//! The keywords are part of the scripts, while the projects are read when
+//! completing, by calling `timereport completions projects` with the same
+//! `--file` and `--profile` as the command being completed.
+
+use crate::argparse::{MONTHS, WEEKDAYS};
+
+/// The words that are completed anywhere in a command
+const KEYWORDS: &[&str] = &[
+    "start",
+    "stop",
+    "lunch",
+    "overtime",
+    "project",
+    "add",
+    "clear",
+    "show",
+    "last",
+    "week",
+    "yesterday",
+    "html",
+    "ics",
+    "undo",
+    "redo",
+    "history",
+    "compact",
+    "archive",
+    "check",
+    "compliance",
+    "convert",
+    "encrypt",
+    "decrypt",
+    "where",
+    "tui",
+    "shell",
+    "batch",
+    "config",
+    "suggest",
+    "import",
+    "completions",
+];
+
+const OPTIONS: &[&str] = &[
+    "--file",
+    "--profile",
+    "--all-profiles",
+    "--weekend",
+    "--help",
+    "--version",
+];
+
+const BASH: &str = r#"# bash completion for timereport, from `timereport completions bash`
+_timereport() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    local prev="${COMP_WORDS[COMP_CWORD-1]}"
+    local options=() i
+    # The projects of the same data file as the command being completed
+    for ((i = 1; i < COMP_CWORD - 1; i++)); do
+        case "${COMP_WORDS[i]}" in
+            --file|--profile) options+=("${COMP_WORDS[i]}" "${COMP_WORDS[i+1]}") ;;
+        esac
+    done
+    COMPREPLY=()
+    case "$prev" in
+        --file)
+            COMPREPLY=($(compgen -f -- "$cur"))
+            return
+            ;;
+        project)
+            local index name candidate
+            while IFS=$'\t' read -r index name; do
+                for candidate in "$index" "$name"; do
+                    if [[ "$candidate" == "$cur"* ]]; then
+                        COMPREPLY+=("$(printf '%q' "$candidate")")
+                    fi
+                done
+            done < <(timereport "${options[@]}" completions projects 2>/dev/null </dev/null)
+            return
+            ;;
+        completions)
+            COMPREPLY=($(compgen -W "bash zsh fish" -- "$cur"))
+            return
+            ;;
+    esac
+    COMPREPLY=($(compgen -W "{WORDS}" -- "$cur"))
+}
+complete -F _timereport timereport t
+"#;
+
+const ZSH: &str = r#"#compdef timereport t
+# zsh completion for timereport, from `timereport completions zsh`
+_timereport() {
+    local -a options indices names
+    local i line
+    # The projects of the same data file as the command being completed
+    for ((i = 2; i < CURRENT - 1; i++)); do
+        case "${words[i]}" in
+            --file|--profile) options+=("${words[i]}" "${words[i+1]}") ;;
+        esac
+    done
+    case "${words[CURRENT-1]}" in
+        --file)
+            _files
+            ;;
+        project)
+            for line in "${(@f)$(timereport "${options[@]}" completions projects 2>/dev/null </dev/null)}"; do
+                [[ -z "$line" ]] && continue
+                indices+=("${line%%$'\t'*}:${${line#*$'\t'}//:/\\:}")
+                names+=("${line#*$'\t'}")
+            done
+            _describe -t project-indices 'project index' indices
+            compadd -a names
+            ;;
+        completions)
+            compadd bash zsh fish
+            ;;
+        *)
+            compadd -- {WORDS}
+            ;;
+    esac
+}
+if [[ "$funcstack[1]" == "_timereport" ]]; then
+    _timereport "$@"
+else
+    compdef _timereport timereport t
+fi
+"#;
+
+const FISH: &str = r#"# fish completion for timereport, from `timereport completions fish`
+function __timereport_previous_is
+    set -l tokens (commandline -opc)
+    test "$tokens[-1]" = "$argv[1]"
+end
+
+function __timereport_projects
+    # The projects of the same data file as the command being completed
+    set -l tokens (commandline -opc)
+    set -l options
+    for i in (seq 2 (math (count $tokens) - 1))
+        if contains -- $tokens[$i] --file --profile
+            set -a options $tokens[$i] $tokens[(math $i + 1)]
+        end
+    end
+    timereport $options completions projects 2>/dev/null </dev/null | while read -l -d \t index name
+        echo $index\t$name
+        echo $name
+    end
+end
+
+for command in timereport t
+    complete -c $command -f
+    complete -c $command -n 'not __timereport_previous_is project; and not __timereport_previous_is completions; and not __timereport_previous_is --file' -a '{WORDS}'
+    complete -c $command -n '__timereport_previous_is project' -a '(__timereport_projects)'
+    complete -c $command -n '__timereport_previous_is completions' -a 'bash zsh fish'
+    complete -c $command -n '__timereport_previous_is --file' -F
+end
+"#;
+
+/// `t completions SHELL` prints the completion script for the shell, and
+/// `t completions projects` the projects for the scripts.
+pub fn completions(shell: Option<&str>, project_names: &[String]) -> Result<String, String> {
+    let script = match shell {
+        Some("bash") => BASH,
+        Some("zsh") => ZSH,
+        Some("fish") => FISH,
+        Some("projects") => return Ok(projects(project_names)),
+        _ => return Err("Usage: t completions {bash|zsh|fish}".to_string()),
+    };
+    Ok(script.replace("{WORDS}", &words().join(" ")))
+}
+
+fn words() -> Vec<&'static str> {
+    [KEYWORDS, WEEKDAYS, MONTHS, OPTIONS].concat()
+}
+
+/// The projects that time can be reported on, one per line with the index
+/// and the name separated by a tab.
+fn projects(project_names: &[String]) -> String {
+    project_names
+        .iter()
+        .enumerate()
+        // The first project after the default project has index 2
+        .map(|(index, name)| format!("{}\t{}", index + 2, name))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,531 @@
+//! Checking the recorded times against working time regulations: daily
+//! rest, the average weekly working time and the overtime per month and
+//! year.
+
+use std::collections::HashMap;
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime, TimeDelta, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::day::Day;
+use crate::naive_date::last_day_of_month;
+use crate::timedelta::{deserialize_seconds_or_hhmm_timedelta, serialize_hhmm_timedelta};
+use crate::traits::Parsable;
+
+/// Overtime is a near miss when it is more than this part of the limit
+const OVERTIME_NEAR_MISS_FRACTION: f64 = 0.9;
+
+/// How the overtime is counted, which differs from the overtime in the tables
+const OVERTIME_NOTE: &str = "Overtime is the time worked beyond working_time_per_day on weekdays \
+and all time worked on weekends. The overtime rules and marked overtime are not used.";
+
+/// The limits used by `t compliance`.
+///
+/// Stored under `compliance` in the settings file. The defaults are the
+/// limits of the Swedish Working Hours Act, which are also within the EU
+/// Working Time Directive.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Limits {
+    /// The least rest between two working days
+    #[serde(serialize_with = "serialize_hhmm_timedelta")]
+    #[serde(deserialize_with = "deserialize_seconds_or_hhmm_timedelta")]
+    pub daily_rest: TimeDelta,
+    /// The most working time per week, on average over `averaging_weeks`
+    #[serde(serialize_with = "serialize_hhmm_timedelta")]
+    #[serde(deserialize_with = "deserialize_seconds_or_hhmm_timedelta")]
+    pub weekly_hours: TimeDelta,
+    pub averaging_weeks: usize,
+    #[serde(serialize_with = "serialize_hhmm_timedelta")]
+    #[serde(deserialize_with = "deserialize_seconds_or_hhmm_timedelta")]
+    pub overtime_per_month: TimeDelta,
+    #[serde(serialize_with = "serialize_hhmm_timedelta")]
+    #[serde(deserialize_with = "deserialize_seconds_or_hhmm_timedelta")]
+    pub overtime_per_year: TimeDelta,
+    /// Rest and weekly working time this close to the limit are near misses
+    #[serde(serialize_with = "serialize_hhmm_timedelta")]
+    #[serde(deserialize_with = "deserialize_seconds_or_hhmm_timedelta")]
+    pub near_miss_margin: TimeDelta,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            daily_rest: TimeDelta::try_hours(11).expect("hardcoded hours"),
+            weekly_hours: TimeDelta::try_hours(48).expect("hardcoded hours"),
+            // About four months
+            averaging_weeks: 17,
+            overtime_per_month: TimeDelta::try_hours(50).expect("hardcoded hours"),
+            overtime_per_year: TimeDelta::try_hours(200).expect("hardcoded hours"),
+            near_miss_margin: TimeDelta::try_hours(1).expect("hardcoded hours"),
+        }
+    }
+}
+
+/// Violations and near misses of the limits, and the overtime totals.
+#[derive(Debug, Default, PartialEq)]
+pub struct Report {
+    /// Days that are left out of the checks because their times are invalid
+    pub not_checked: Vec<String>,
+    pub violations: Vec<String>,
+    pub near_misses: Vec<String>,
+    /// The month or year, the overtime and the limit
+    pub overtime: Vec<(String, TimeDelta, TimeDelta)>,
+}
+
+/// Check the days from `first_date` to `last_date`. The weeks and the
+/// months and years with overtime are checked in full, also when only part
+/// of them is in the period.
+///
+/// A stop before the start is on the next day. Days where the stop is the
+/// same as the start are left out, as it is not clear whether no time or a
+/// whole day was worked.
+pub fn compliance(
+    first_date: NaiveDate,
+    last_date: NaiveDate,
+    day_from_date: &HashMap<NaiveDate, Day>,
+    working_time_per_day: TimeDelta,
+    limits: &Limits,
+) -> Report {
+    let mut report = Report::default();
+    let (invalid_days, day_from_date): (HashMap<NaiveDate, Day>, HashMap<NaiveDate, Day>) =
+        day_from_date
+            .iter()
+            .map(|(date, day)| (*date, day.clone()))
+            .partition(|(_, day)| is_zero_length(day));
+    report.not_checked = first_date
+        .iter_days()
+        .take_while(|date| *date <= last_date)
+        .filter_map(|date| invalid_days.get(&date))
+        .map(|day| {
+            format!(
+                "{}: Stop is the same as start {}",
+                day.date,
+                day.start
+                    .expect("zero-length days have a start")
+                    .format("%H:%M")
+            )
+        })
+        .collect();
+    let day_from_date = &day_from_date;
+    check_daily_rest(first_date, last_date, day_from_date, limits, &mut report);
+    check_weekly_hours(first_date, last_date, day_from_date, limits, &mut report);
+    check_overtime(
+        first_date,
+        last_date,
+        day_from_date,
+        working_time_per_day,
+        limits,
+        &mut report,
+    );
+    report
+}
+
+fn is_zero_length(day: &Day) -> bool {
+    matches!((day.start, day.stop), (Some(start), Some(stop)) if stop == start)
+}
+
+/// When the work of a day started and ended. A stop before the start is on
+/// the next day, i.e. the work went on past midnight.
+pub(crate) fn shift(day: &Day) -> Option<(NaiveDateTime, NaiveDateTime)> {
+    let start = day.date.and_time(day.start?);
+    let mut stop = day.date.and_time(day.stop?);
+    if stop <= start {
+        stop += TimeDelta::try_days(1).expect("one day");
+    }
+    Some((start, stop))
+}
+
+/// The time worked on a day, including work past midnight.
+fn worked_time(day: &Day) -> Option<TimeDelta> {
+    let (start, stop) = shift(day)?;
+    Some(stop - start - day.lunch.unwrap_or(TimeDelta::zero()))
+}
+
+fn worked_time_on(date: NaiveDate, day_from_date: &HashMap<NaiveDate, Day>) -> TimeDelta {
+    day_from_date
+        .get(&date)
+        .and_then(worked_time)
+        .unwrap_or(TimeDelta::zero())
+}
+
+fn check_daily_rest(
+    first_date: NaiveDate,
+    last_date: NaiveDate,
+    day_from_date: &HashMap<NaiveDate, Day>,
+    limits: &Limits,
+    report: &mut Report,
+) {
+    for date in first_date.iter_days().take_while(|date| *date <= last_date) {
+        let previous_date = date.pred_opt().expect("dates are in range");
+        let (Some((_, previous_stop)), Some((start, _))) = (
+            day_from_date.get(&previous_date).and_then(shift),
+            day_from_date.get(&date).and_then(shift),
+        ) else {
+            continue;
+        };
+        let rest = start - previous_stop;
+        let text = format!(
+            "{}: {} of rest after {}, the limit is at least {}",
+            date,
+            rest.to_hhmm(),
+            previous_stop.format("%Y-%m-%d %H:%M"),
+            limits.daily_rest.to_hhmm()
+        );
+        if rest < limits.daily_rest {
+            report.violations.push(text);
+        } else if rest < limits.daily_rest + limits.near_miss_margin {
+            report.near_misses.push(text);
+        }
+    }
+}
+
+fn check_weekly_hours(
+    first_date: NaiveDate,
+    last_date: NaiveDate,
+    day_from_date: &HashMap<NaiveDate, Day>,
+    limits: &Limits,
+    report: &mut Report,
+) {
+    let week_count = limits.averaging_weeks.max(1);
+    let mut monday = first_date.week(Weekday::Mon).first_day();
+    while monday <= last_date {
+        let week_total = |monday: NaiveDate| -> TimeDelta {
+            monday
+                .iter_days()
+                .take(7)
+                .map(|date| worked_time_on(date, day_from_date))
+                .sum()
+        };
+        let total = week_total(monday);
+        let average = (0..week_count)
+            .map(|weeks_before| {
+                week_total(monday - TimeDelta::try_weeks(weeks_before as i64).expect("few weeks"))
+            })
+            .sum::<TimeDelta>()
+            / week_count as i32;
+        let text = format!(
+            "Week {}: {} worked, {} on average over {} weeks, the limit is at most {}",
+            monday.format("%G-W%V"),
+            total.to_hhmm(),
+            average.to_hhmm(),
+            week_count,
+            limits.weekly_hours.to_hhmm()
+        );
+        if average > limits.weekly_hours {
+            report.violations.push(text);
+        } else if average > limits.weekly_hours - limits.near_miss_margin {
+            report.near_misses.push(text);
+        }
+        monday += TimeDelta::try_weeks(1).expect("one week");
+    }
+}
+
+/// The time worked beyond the working time of a weekday, or all time worked
+/// on a weekend.
+fn overtime(
+    date: NaiveDate,
+    day_from_date: &HashMap<NaiveDate, Day>,
+    working_time_per_day: TimeDelta,
+) -> TimeDelta {
+    let ordinary_time = match date.weekday() {
+        Weekday::Sat | Weekday::Sun => TimeDelta::zero(),
+        _ => working_time_per_day,
+    };
+    (worked_time_on(date, day_from_date) - ordinary_time).max(TimeDelta::zero())
+}
+
+fn check_overtime(
+    first_date: NaiveDate,
+    last_date: NaiveDate,
+    day_from_date: &HashMap<NaiveDate, Day>,
+    working_time_per_day: TimeDelta,
+    limits: &Limits,
+    report: &mut Report,
+) {
+    let total = |first_date: NaiveDate, last_date: NaiveDate| -> TimeDelta {
+        first_date
+            .iter_days()
+            .take_while(|date| *date <= last_date)
+            .map(|date| overtime(date, day_from_date, working_time_per_day))
+            .sum()
+    };
+    let mut periods = Vec::new();
+    let mut month = first_date.with_day(1).expect("first day of month");
+    while month <= last_date {
+        let label = month.format("%Y-%m").to_string();
+        periods.push((
+            label,
+            month,
+            last_day_of_month(month),
+            limits.overtime_per_month,
+        ));
+        month = last_day_of_month(month)
+            .succ_opt()
+            .expect("dates are in range");
+    }
+    for year in first_date.year()..=last_date.year() {
+        let first_day = NaiveDate::from_ymd_opt(year, 1, 1).expect("first day of year");
+        let last_day = NaiveDate::from_ymd_opt(year, 12, 31).expect("last day of year");
+        periods.push((
+            year.to_string(),
+            first_day,
+            last_day,
+            limits.overtime_per_year,
+        ));
+    }
+    for (label, first_day, last_day, limit) in periods {
+        let overtime = total(first_day, last_day);
+        let text = format!(
+            "{}: {} of overtime, the limit is at most {}",
+            label,
+            overtime.to_hhmm(),
+            limit.to_hhmm()
+        );
+        if overtime > limit {
+            report.violations.push(text);
+        } else if overtime.num_seconds() as f64
+            > limit.num_seconds() as f64 * OVERTIME_NEAR_MISS_FRACTION
+        {
+            report.near_misses.push(text);
+        }
+        report.overtime.push((label, overtime, limit));
+    }
+}
+
+pub fn format_report(report: &Report, first_date: NaiveDate, last_date: NaiveDate) -> String {
+    let list = |lines: &[String]| {
+        if lines.is_empty() {
+            "  None".to_string()
+        } else {
+            lines
+                .iter()
+                .map(|line| format!("  {}", line))
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+    };
+    let overtime = report
+        .overtime
+        .iter()
+        .map(|(label, overtime, limit)| {
+            format!("  {}: {} of {}", label, overtime.to_hhmm(), limit.to_hhmm())
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let not_checked = if report.not_checked.is_empty() {
+        "".to_string()
+    } else {
+        format!(
+            "\n\nNot checked, see t check:\n{}",
+            list(&report.not_checked)
+        )
+    };
+    format!(
+        "Compliance from {} to {}{}\n\nViolations:\n{}\n\nNear misses:\n{}\n\nOvertime:\n{}\n\n{}",
+        first_date,
+        last_date,
+        not_checked,
+        list(&report.violations),
+        list(&report.near_misses),
+        overtime,
+        OVERTIME_NOTE
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use chrono::{NaiveDate, NaiveTime, TimeDelta};
+    use rstest::rstest;
+
+    use crate::compliance::{compliance, Limits, Report};
+    use crate::day::Day;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 4, day).expect("")
+    }
+
+    fn day(day_of_month: u32, start: &str, stop: &str) -> Day {
+        let time = |text: &str| NaiveTime::parse_from_str(text, "%H:%M").expect("");
+        Day {
+            date: date(day_of_month),
+            start: Some(time(start)),
+            stop: Some(time(stop)),
+            lunch: Some(TimeDelta::zero()),
+            projects: HashMap::new(),
+            overtime: None,
+            cleared: Vec::new(),
+        }
+    }
+
+    fn report(days: Vec<Day>, first_day: u32, last_day: u32) -> Report {
+        let day_from_date = days.into_iter().map(|day| (day.date, day)).collect();
+        compliance(
+            date(first_day),
+            date(last_day),
+            &day_from_date,
+            TimeDelta::try_hours(8).expect(""),
+            &Limits::default(),
+        )
+    }
+
+    #[rstest]
+    fn normal_days_comply() {
+        let report = report(
+            vec![day(14, "08:00", "16:00"), day(15, "08:00", "16:00")],
+            14,
+            15,
+        );
+
+        assert!(report.violations.is_empty());
+        assert!(report.near_misses.is_empty());
+    }
+
+    #[rstest]
+    fn short_rest_is_a_violation() {
+        let report = report(
+            vec![day(14, "08:00", "22:00"), day(15, "07:00", "16:00")],
+            14,
+            15,
+        );
+
+        assert_eq!(
+            report.violations,
+            vec!["2025-04-15: 09:00 of rest after 2025-04-14 22:00, the limit is at least 11:00"]
+        );
+    }
+
+    #[rstest]
+    fn rest_across_midnight() {
+        let report = report(
+            vec![day(14, "14:00", "01:00"), day(15, "11:30", "16:00")],
+            14,
+            15,
+        );
+
+        assert_eq!(
+            report.violations,
+            vec!["2025-04-15: 10:30 of rest after 2025-04-15 01:00, the limit is at least 11:00"]
+        );
+    }
+
+    #[rstest]
+    fn zero_length_day_is_not_checked() {
+        let report = report(
+            vec![day(14, "08:00", "08:00"), day(15, "11:30", "16:00")],
+            14,
+            15,
+        );
+
+        assert_eq!(
+            report.not_checked,
+            vec!["2025-04-14: Stop is the same as start 08:00"]
+        );
+        assert!(report.violations.is_empty());
+    }
+
+    #[rstest]
+    fn rest_just_above_limit_is_a_near_miss() {
+        let report = report(
+            vec![day(14, "08:00", "20:00"), day(15, "07:30", "16:00")],
+            14,
+            15,
+        );
+
+        assert!(report.violations.is_empty());
+        assert_eq!(
+            report.near_misses,
+            vec!["2025-04-15: 11:30 of rest after 2025-04-14 20:00, the limit is at least 11:00"]
+        );
+    }
+
+    #[rstest]
+    fn rest_after_the_period_is_not_checked() {
+        let report = report(
+            vec![day(14, "08:00", "22:00"), day(15, "07:00", "16:00")],
+            14,
+            14,
+        );
+
+        assert!(report.violations.is_empty());
+    }
+
+    #[rstest]
+    fn weekly_average_over_limit() {
+        let limits = Limits {
+            averaging_weeks: 1,
+            ..Limits::default()
+        };
+        let days: HashMap<NaiveDate, Day> = (14..=18)
+            .map(|day_of_month| (date(day_of_month), day(day_of_month, "06:00", "16:00")))
+            .collect();
+
+        let report = compliance(
+            date(14),
+            date(18),
+            &days,
+            TimeDelta::try_hours(8).expect(""),
+            &limits,
+        );
+
+        assert_eq!(
+            report.violations,
+            vec!["Week 2025-W16: 50:00 worked, 50:00 on average over 1 weeks, the limit is at most 48:00"]
+        );
+    }
+
+    #[rstest]
+    fn long_week_within_average() {
+        let days: Vec<Day> = (14..=18)
+            .map(|day_of_month| day(day_of_month, "06:00", "16:00"))
+            .collect();
+
+        let report = report(days, 14, 18);
+
+        assert!(report.violations.is_empty());
+    }
+
+    #[rstest]
+    fn overtime_of_month_and_year() {
+        // Two hours of overtime on a weekday and four on a Saturday
+        let report = report(
+            vec![day(14, "06:00", "16:00"), day(19, "08:00", "12:00")],
+            14,
+            19,
+        );
+
+        assert_eq!(
+            report.overtime,
+            vec![
+                (
+                    "2025-04".to_string(),
+                    TimeDelta::try_hours(6).expect(""),
+                    TimeDelta::try_hours(50).expect("")
+                ),
+                (
+                    "2025".to_string(),
+                    TimeDelta::try_hours(6).expect(""),
+                    TimeDelta::try_hours(200).expect("")
+                ),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn overtime_over_monthly_limit() {
+        // 10 hours of overtime on each of six Saturdays and Sundays
+        let days: Vec<Day> = [5, 6, 12, 13, 19, 20]
+            .into_iter()
+            .map(|day_of_month| day(day_of_month, "06:00", "16:00"))
+            .collect();
+
+        let report = report(days, 1, 30);
+
+        assert_eq!(
+            report.violations,
+            vec!["2025-04: 60:00 of overtime, the limit is at most 50:00"]
+        );
+    }
+}
@@ -1,72 +1,361 @@
-use crate::timedelta::{deserialize_timedelta, serialize_timedelta};
-use std::collections::HashMap;
-use std::fs::{self, File};
-use std::io::Read;
-use std::path::Path;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::Range;
 
 use crate::day::Day;
-use chrono::{NaiveDate, TimeDelta};
+use crate::rounding::Apply;
+use crate::settings::Settings;
+use crate::storage::Storage;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, TimeDelta};
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Config {
-    pub project_names: Vec<String>,
+    #[serde(flatten)]
+    pub settings: Settings,
     days: Vec<Day>,
     undone: Vec<Day>,
-    #[serde(deserialize_with = "deserialize_timedelta")]
-    #[serde(serialize_with = "serialize_timedelta")]
-    pub working_time_per_day: TimeDelta,
+    #[serde(default)]
+    journal: Vec<JournalEntry>,
+    #[serde(default)]
+    undone_journal: Vec<JournalEntry>,
+    /// The number of days at the start of the log that have been combined by
+    /// compact, and cannot be undone.
+    #[serde(default)]
+    compacted_day_count: usize,
+    /// The years whose days have been moved to archive files
+    #[serde(default)]
+    archived_years: Vec<i32>,
+    /// The days in the archive files, loaded together with the config
+    #[serde(skip)]
+    archived_days: Vec<Day>,
+    #[serde(skip)]
+    command: String,
+    /// When the current command was given
+    #[serde(skip)]
+    command_time: Option<NaiveDateTime>,
+    #[serde(skip)]
+    is_entry_open: bool,
+}
+
+/// One undoable step, i.e. the days and projects added by one command.
+///
+/// The days of an entry are the last `day_count` days in the log that are not
+/// part of a later entry. Days added before the journal existed have no
+/// entry, and are undone one at a time.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct JournalEntry {
+    pub day_count: usize,
+    #[serde(default)]
+    pub timestamp: Option<NaiveDateTime>,
+    #[serde(default)]
+    pub command: String,
+    #[serde(default)]
+    pub projects: Vec<String>,
 }
 
 impl Config {
-    fn new(days: Vec<Day>) -> Self {
+    pub(crate) fn new(days: Vec<Day>) -> Self {
         Self {
-            project_names: Vec::new(),
+            settings: Settings::default(),
             days,
             undone: Vec::new(),
-            // TODO: make customizable
-            working_time_per_day: TimeDelta::new(27900, 0).expect("hardcoded seconds"),
+            journal: Vec::new(),
+            undone_journal: Vec::new(),
+            compacted_day_count: 0,
+            archived_years: Vec::new(),
+            archived_days: Vec::new(),
+            command: String::new(),
+            command_time: None,
+            is_entry_open: false,
         }
     }
-    pub fn save(&self, path: &Path) {
-        let json_string = serde_json::to_string_pretty(&self).unwrap();
-        match fs::write(path, json_string) {
-            Ok(_) => {}
-            Err(_) => eprintln!("Error writing to file {}", path.to_string_lossy()),
+    pub fn save(&self, storage: &dyn Storage) -> Result<(), String> {
+        storage.save(self)
+    }
+
+    /// Save all data, including the archives, to another storage, e.g. in
+    /// another format.
+    pub fn convert(&self, from: &dyn Storage, to: &dyn Storage) -> Result<(), String> {
+        to.save(self)?;
+        for year in &self.archived_years {
+            to.save_archive(*year, &from.load_archive(*year)?)?;
         }
+        Ok(())
     }
+
+    /// Set the command line and time that are recorded in the journal for
+    /// the changes made from now on.
+    pub fn set_command(&mut self, command: String, now: NaiveDateTime) {
+        self.command = command;
+        self.command_time = Some(now);
+        self.is_entry_open = false;
+    }
+
+    /// The journal entry for the current command, so that all changes made
+    /// by one command are undone in one step.
+    fn current_entry(&mut self) -> &mut JournalEntry {
+        if !self.is_entry_open {
+            self.journal.push(JournalEntry {
+                day_count: 0,
+                timestamp: self.command_time,
+                command: self.command.clone(),
+                projects: Vec::new(),
+            });
+            self.undone.clear();
+            self.undone_journal.clear();
+            self.is_entry_open = true;
+        }
+        self.journal.last_mut().expect("just pushed")
+    }
+
     pub fn add_project(&mut self, name: String) -> () {
-        self.project_names.push(name);
+        self.current_entry().projects.push(name.clone());
+        self.settings.project_names.push(name);
+    }
+
+    /// Add days to the log, rounded if the rounding is applied on entry.
+    pub fn add_days(&mut self, days: Vec<Day>) {
+        if days.is_empty() {
+            return;
+        }
+        let days: Vec<Day> = match self.settings.rounding.apply {
+            Apply::Entry => days
+                .iter()
+                .map(|day| self.settings.rounding.round(day))
+                .collect(),
+            Apply::Report => days,
+        };
+        self.current_entry().day_count += days.len();
+        self.days.extend(days);
+    }
+
+    /// The range in the log of the days of each journal entry.
+    fn journal_ranges(&self) -> Vec<Range<usize>> {
+        let mut end = self.days.len();
+        let mut ranges: Vec<Range<usize>> = self
+            .journal
+            .iter()
+            .rev()
+            .map(|entry| {
+                let start = end.saturating_sub(entry.day_count);
+                let range = start..end;
+                end = start;
+                range
+            })
+            .collect();
+        ranges.reverse();
+        ranges
+    }
+
+    /// The journal entries and the dates they affected, most recent first.
+    pub fn history(&self) -> Vec<(&JournalEntry, Vec<NaiveDate>)> {
+        self.journal
+            .iter()
+            .zip(self.journal_ranges())
+            .map(|(entry, range)| (entry, self.days[range].iter().map(|day| day.date).collect()))
+            .rev()
+            .collect()
+    }
+
+    /// Undo the last step, returning the dates of the affected days.
+    pub fn undo(&mut self) -> Result<Vec<NaiveDate>, String> {
+        if !self.journal.is_empty() {
+            return Ok(self.undo_entry(self.journal.len() - 1));
+        }
+        if self.days.len() <= self.compacted_day_count {
+            return Err("Nothing to undo".to_string());
+        }
+        match self.days.pop() {
+            Some(day) => {
+                let date = day.date;
+                self.undone.push(day);
+                // Give the day an entry, so that it is redone before any
+                // earlier undone entries
+                self.undone_journal.push(JournalEntry {
+                    day_count: 1,
+                    timestamp: None,
+                    command: String::new(),
+                    projects: Vec::new(),
+                });
+                Ok(vec![date])
+            }
+            None => Err("Nothing to undo".to_string()),
+        }
+    }
+
+    /// Undo the last step that affected the date, even if later steps have
+    /// been made, returning the dates of the affected days.
+    pub fn undo_date(&mut self, date: NaiveDate) -> Result<Vec<NaiveDate>, String> {
+        let ranges = self.journal_ranges();
+        match ranges
+            .iter()
+            .rposition(|range| self.days[range.clone()].iter().any(|day| day.date == date))
+        {
+            Some(index) => Ok(self.undo_entry(index)),
+            None => Err(format!("Nothing to undo for {}", date)),
+        }
     }
 
-    pub fn add_day(&mut self, day: Day) -> () {
-        self.days.push(day);
-        self.undone.clear();
+    fn undo_entry(&mut self, index: usize) -> Vec<NaiveDate> {
+        let range = self.journal_ranges()[index].clone();
+        let entry = self.journal.remove(index);
+        let days: Vec<Day> = self.days.drain(range).collect();
+        for project in entry.projects.iter().rev() {
+            if let Some(position) = self
+                .settings
+                .project_names
+                .iter()
+                .rposition(|name| name == project)
+            {
+                self.settings.project_names.remove(position);
+            }
+        }
+        let dates = days.iter().map(|day| day.date).collect();
+        self.undone.extend(days);
+        self.undone_journal.push(entry);
+        self.is_entry_open = false;
+        dates
     }
 
-    pub fn undo(&mut self) -> Result<NaiveDate, String> {
-        let day = match self.days.pop() {
-            Some(day) => day,
-            None => return Err("Nothing to undo".to_string()),
+    /// Redo the last undone step, returning the dates of the affected days.
+    ///
+    /// A step that was undone with undo_date is redone as the latest step.
+    pub fn redo(&mut self) -> Result<Vec<NaiveDate>, String> {
+        let entry = match self.undone_journal.pop() {
+            Some(entry) => entry,
+            // Days undone before the journal existed are redone one at a time
+            None => JournalEntry {
+                day_count: 1,
+                timestamp: None,
+                command: String::new(),
+                projects: Vec::new(),
+            },
         };
-        let date = day.date;
-        self.undone.push(day);
-        Ok(date)
+        if self.undone.len() < entry.day_count {
+            return Err("Nothing to redo".to_string());
+        }
+        let days = self.undone.split_off(self.undone.len() - entry.day_count);
+        let dates = days.iter().map(|day| day.date).collect();
+        self.days.extend(days);
+        self.settings.project_names.extend(entry.projects.clone());
+        self.journal.push(entry);
+        self.is_entry_open = false;
+        Ok(dates)
     }
 
-    pub fn redo(&mut self) -> Result<NaiveDate, String> {
-        let day = match self.undone.pop() {
-            Some(day) => day,
-            None => return Err("Nothing to redo".to_string()),
+    /// Remove the days matching the predicate from the log, keeping the
+    /// journal consistent with the remaining days.
+    fn remove_days(&mut self, predicate: impl Fn(&Day) -> bool) -> Vec<Day> {
+        let ranges = self.journal_ranges();
+        let mut removed: Vec<(usize, Day)> = vec![];
+        let mut kept: Vec<Day> = vec![];
+        for (i, day) in self.days.drain(..).enumerate() {
+            if predicate(&day) {
+                removed.push((i, day));
+            } else {
+                kept.push(day);
+            }
+        }
+        for (entry, range) in self.journal.iter_mut().zip(ranges) {
+            entry.day_count -= removed.iter().filter(|(i, _)| range.contains(i)).count();
+        }
+        self.compacted_day_count -= removed
+            .iter()
+            .filter(|(i, _)| *i < self.compacted_day_count)
+            .count();
+        self.journal
+            .retain(|entry| entry.day_count > 0 || !entry.projects.is_empty());
+        self.days = kept;
+        removed.into_iter().map(|(_, day)| day).collect()
+    }
+
+    /// Combine all entries in the log for dates before the cutoff into one
+    /// day per date, returning the number of entries removed.
+    ///
+    /// The combined days can no longer be undone, but later changes can.
+    pub fn compact(&mut self, cutoff: NaiveDate) -> usize {
+        let old_days = self.remove_days(|day| day.date < cutoff);
+        let old_day_count = old_days.len();
+        let compacted_days = combine_days(old_days);
+        let removed_count = old_day_count - compacted_days.len();
+        self.compacted_day_count += compacted_days.len();
+        self.days.splice(0..0, compacted_days);
+        removed_count
+    }
+
+    /// Compact the log if it has grown past the threshold, so that the time
+    /// to combine the days does not grow without limit.
+    fn compact_if_needed(&mut self, today: NaiveDate) {
+        if self.days.len() <= self.settings.compact_threshold {
+            return;
+        }
+        let cutoff =
+            today - TimeDelta::try_days(self.settings.compact_keep_days).expect("few days");
+        let old_dates: Vec<NaiveDate> = self
+            .days
+            .iter()
+            .map(|day| day.date)
+            .filter(|date| *date < cutoff)
+            .collect();
+        // Compacting also removes the history of the days, so only do it if
+        // it removes entries
+        if old_dates.len() > old_dates.iter().collect::<BTreeSet<_>>().len() {
+            self.compact(cutoff);
+        }
+    }
+
+    /// Move all days of the year from the log to the archive file for the
+    /// year, returning the number of moved entries. The archive is written
+    /// here, so the config must be saved afterwards: if that fails, the days
+    /// are still in the data file.
+    ///
+    /// Days already in the archive are kept, so a year can be archived again
+    /// if days have been added to it afterwards.
+    pub fn archive(&mut self, year: i32, storage: &dyn Storage) -> Result<usize, String> {
+        let days = self.remove_days(|day| day.date.year() == year);
+        let moved_count = days.len();
+        if days.is_empty() {
+            return Err(format!("No days to archive for {}", year));
+        }
+        let mut archived_days = if self.archived_years.contains(&year) {
+            storage.load_archive(year)?
+        } else {
+            Vec::new()
         };
-        let date = day.date;
-        self.days.push(day);
-        Ok(date)
+        archived_days.extend(days);
+        let archived_days = combine_days(archived_days);
+        storage.save_archive(year, &archived_days)?;
+        if !self.archived_years.contains(&year) {
+            self.archived_years.push(year);
+            self.archived_years.sort();
+        }
+        self.archived_days = self
+            .archived_days
+            .drain(..)
+            .filter(|day| day.date.year() != year)
+            .chain(archived_days)
+            .collect();
+        self.archived_days.sort_by_key(|day| day.date);
+        Ok(moved_count)
+    }
+
+    /// A copy of the config as stored, without the days loaded from the
+    /// archives.
+    pub(crate) fn stored(&self) -> Config {
+        Config {
+            archived_days: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    /// The entries of the log, in the order they were added.
+    pub fn days(&self) -> &[Day] {
+        &self.days
     }
 
     pub fn day_from_date(&self) -> HashMap<NaiveDate, Day> {
         let mut day_from_date: HashMap<NaiveDate, Day> = HashMap::new();
-        for day in &self.days {
+        for day in self.archived_days.iter().chain(&self.days) {
             match day_from_date.get(&day.date) {
                 None => day_from_date.insert(day.date, day.clone()),
                 Some(old_day) => day_from_date.insert(day.date, old_day.combine(day)),
@@ -74,33 +363,54 @@ impl Config {
         }
         day_from_date
     }
+
+    /// The days as shown in tables and exports, rounded if the rounding is
+    /// applied in reports.
+    pub fn rounded_day_from_date(&self) -> HashMap<NaiveDate, Day> {
+        let day_from_date = self.day_from_date();
+        match self.settings.rounding.apply {
+            Apply::Entry => day_from_date,
+            Apply::Report => day_from_date
+                .into_iter()
+                .map(|(date, day)| (date, self.settings.rounding.round(&day)))
+                .collect(),
+        }
+    }
+}
+
+pub fn load(storage: &dyn Storage, today: NaiveDate) -> Result<Config, String> {
+    let mut config = load_without_compaction(storage)?;
+    config.compact_if_needed(today);
+    Ok(config)
 }
 
-pub fn load(path: &Path) -> Result<Config, String> {
-    if fs::metadata(path).is_err() {
-        create_empty_config_file(path);
-    }
-    let mut file = match File::open(path) {
-        Ok(file) => file,
-        Err(error) => {
-            return Err(format!(
-                "Error when trying to access {}: {}",
-                path.to_string_lossy(),
-                error.to_string()
-            ))
-        }
-    };
-    let mut contents = String::new();
-    file.read_to_string(&mut contents)
-        .expect(&format!("Failed to read {}", path.to_string_lossy()));
-    match serde_json::from_str(&contents) {
-        Ok(config) => Ok(config),
-        Err(_) => Err(format!("Failed to parse {}", path.to_string_lossy())),
+/// Load the config exactly as stored, e.g. for converting it to another
+/// format.
+pub fn load_without_compaction(storage: &dyn Storage) -> Result<Config, String> {
+    let mut config = storage.load()?;
+    for year in &config.archived_years {
+        config.archived_days.extend(storage.load_archive(*year)?);
     }
+    Ok(config)
 }
 
-fn create_empty_config_file(path: &Path) {
-    fs::File::create(path).expect(&format!("Failed to create file {}", path.to_string_lossy()));
-    let config = Config::new(Vec::new());
-    config.save(path);
+/// Combine the days into one day per date, in date order.
+fn combine_days(days: Vec<Day>) -> Vec<Day> {
+    let mut day_from_date: BTreeMap<NaiveDate, Day> = BTreeMap::new();
+    for day in days {
+        let combined = match day_from_date.get(&day.date) {
+            None => day,
+            Some(old_day) => old_day.combine(&day),
+        };
+        day_from_date.insert(combined.date, combined);
+    }
+    day_from_date
+        .into_values()
+        .map(|mut day| {
+            // Nothing before the combined day is left to clear
+            day.cleared.clear();
+            day
+        })
+        .filter(|day| day.has_content())
+        .collect()
 }
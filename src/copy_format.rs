@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+
+/// How times are written when copied from the HTML table.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeFormat {
+    /// Decimal hours, e.g. "7,25"
+    Decimal,
+    /// Hours and minutes, e.g. "07:15"
+    Hhmm,
+}
+
+/// Whether a copy button copies a row (one project, all days) or a column
+/// (one day, all projects).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Layout {
+    Rows,
+    Columns,
+}
+
+/// The format used by the copy buttons in `show ... html`.
+///
+/// Stored under `copy_format` in the config file. Missing fields get the
+/// default values, which match the format used before the format was
+/// configurable.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct CopyFormat {
+    pub time_format: TimeFormat,
+    pub decimal_separator: String,
+    pub decimals: usize,
+    pub layout: Layout,
+    pub include_weekend: bool,
+    /// Positive flex is not copied, and negative flex is copied without the
+    /// minus sign, i.e. as the amount of flex that needs to be taken out.
+    pub blank_positive_flex: bool,
+}
+
+impl Default for CopyFormat {
+    fn default() -> Self {
+        Self {
+            time_format: TimeFormat::Decimal,
+            decimal_separator: ",".to_string(),
+            decimals: 2,
+            layout: Layout::Rows,
+            include_weekend: true,
+            blank_positive_flex: true,
+        }
+    }
+}
+
+impl CopyFormat {
+    /// Format an "HH:MM" cell from the terminal table for copying.
+    ///
+    /// An empty string returns an empty string.
+    pub fn format(&self, time_str: &str, flex: bool) -> String {
+        if time_str.is_empty() {
+            return String::new();
+        }
+        let (sign, hours, minutes) = match parse_hhmm(time_str) {
+            Ok(parsed) => parsed,
+            Err(message) => return message,
+        };
+        let sign = if flex && self.blank_positive_flex {
+            if sign > 0 && hours + minutes > 0 {
+                return String::new();
+            }
+            1
+        } else {
+            sign
+        };
+        let minus = if sign < 0 && hours + minutes > 0 {
+            "-"
+        } else {
+            ""
+        };
+        match self.time_format {
+            TimeFormat::Hhmm => format!("{}{:02}:{:02}", minus, hours, minutes),
+            TimeFormat::Decimal => {
+                let decimal = hours as f64 + (minutes as f64 / 60.0);
+                format!("{}{:.*}", minus, self.decimals, decimal)
+                    .replace('.', &self.decimal_separator)
+            }
+        }
+    }
+}
+
+fn parse_hhmm(time_str: &str) -> Result<(i32, u32, u32), String> {
+    let sign = if time_str.starts_with('-') { -1 } else { 1 };
+    let parts: Vec<&str> = time_str.trim_start_matches('-').split(':').collect();
+
+    // Handle format validation
+    if parts.len() != 2 {
+        return Err(format!("Error: Invalid format '{}'. Use HH:MM", time_str));
+    }
+
+    // Parse hours with error handling
+    let hours = match parts[0].parse::<u32>() {
+        Ok(h) if h < 24 => h,
+        Ok(_) => return Err(format!("Error: Hours in '{}' must be < 24", time_str)),
+        Err(_) => return Err(format!("Error: Invalid hours '{}'", parts[0])),
+    };
+
+    // Parse minutes with error handling
+    let minutes = match parts[1].parse::<u32>() {
+        Ok(m) if m < 60 => m,
+        Ok(_) => return Err(format!("Error: Minutes in '{}' must be < 60", time_str)),
+        Err(_) => return Err(format!("Error: Invalid minutes '{}'", parts[1])),
+    };
+    Ok((sign, hours, minutes))
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use crate::copy_format::{CopyFormat, TimeFormat};
+
+    #[rstest]
+    #[case("07:15", false, "7,25")]
+    #[case("", false, "")]
+    #[case("-00:45", true, "0,75")]
+    #[case("00:45", true, "")]
+    #[case("00:00", true, "0,00")]
+    #[case("-00:45", false, "-0,75")]
+    fn default_format(#[case] time_str: &str, #[case] flex: bool, #[case] expected: &str) {
+        assert_eq!(CopyFormat::default().format(time_str, flex), expected);
+    }
+
+    #[rstest]
+    fn dot_and_one_decimal() {
+        let copy_format = CopyFormat {
+            decimal_separator: ".".to_string(),
+            decimals: 1,
+            ..CopyFormat::default()
+        };
+
+        assert_eq!(copy_format.format("07:30", false), "7.5");
+    }
+
+    #[rstest]
+    fn hhmm_keeps_positive_flex_when_not_blanked() {
+        let copy_format = CopyFormat {
+            time_format: TimeFormat::Hhmm,
+            blank_positive_flex: false,
+            ..CopyFormat::default()
+        };
+
+        assert_eq!(copy_format.format("00:30", true), "00:30");
+        assert_eq!(copy_format.format("-01:30", true), "-01:30");
+    }
+
+    #[rstest]
+    fn invalid_hours() {
+        assert_eq!(
+            CopyFormat::default().format("25:00", false),
+            "Error: Hours in '25:00' must be < 24"
+        );
+    }
+}
@@ -8,7 +8,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt::Debug;
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub struct Day {
     pub date: NaiveDate,
     pub start: Option<NaiveTime>,
@@ -19,6 +19,29 @@ pub struct Day {
     #[serde(deserialize_with = "deserialize_hashmap_timedelta")]
     #[serde(serialize_with = "serialize_hashmap_timedelta")]
     pub projects: HashMap<String, TimeDelta>,
+    /// Time marked as overtime, instead of overtime from the rules in the
+    /// settings
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(deserialize_with = "deserialize_option_timedelta")]
+    #[serde(serialize_with = "serialize_option_timedelta")]
+    pub overtime: Option<TimeDelta>,
+    /// Fields removed by this entry, overriding the values of earlier entries
+    /// for the same date.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cleared: Vec<Field>,
+}
+
+/// A field of a day that can be cleared.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Field {
+    Start,
+    Stop,
+    Lunch,
+    Overtime,
+    Project(String),
+    /// All projects
+    Projects,
 }
 
 impl Debug for Day {
@@ -29,6 +52,8 @@ impl Debug for Day {
             .field("stop", &self.stop)
             .field("lunch", &self.lunch)
             .field("projects", &self.projects)
+            .field("overtime", &self.overtime)
+            .field("cleared", &self.cleared)
             .finish()
     }
 }
@@ -36,20 +61,38 @@ impl Debug for Day {
 impl Day {
     /// Combines two Days into a third.
     ///
-    /// The "other" variable overwrites the first.
+    /// The "other" variable overwrites the first, and the fields cleared in
+    /// "other" are removed from the first.
     pub fn combine(&self, other: &Day) -> Day {
         assert!(self.date == other.date);
+        let is_cleared = |field: &Field| other.cleared.contains(field);
+        let projects_of_self = self.projects.clone().into_iter().filter(|(name, _)| {
+            !is_cleared(&Field::Projects) && !is_cleared(&Field::Project(name.clone()))
+        });
         Day {
             date: self.date,
-            start: other.start.or(self.start),
-            stop: other.stop.or(self.stop),
-            lunch: other.lunch.or(self.lunch),
-            projects: self
-                .projects
-                .clone()
-                .into_iter()
-                .chain(other.projects.clone())
-                .collect(),
+            start: if is_cleared(&Field::Start) {
+                other.start
+            } else {
+                other.start.or(self.start)
+            },
+            stop: if is_cleared(&Field::Stop) {
+                other.stop
+            } else {
+                other.stop.or(self.stop)
+            },
+            lunch: if is_cleared(&Field::Lunch) {
+                other.lunch
+            } else {
+                other.lunch.or(self.lunch)
+            },
+            projects: projects_of_self.chain(other.projects.clone()).collect(),
+            overtime: if is_cleared(&Field::Overtime) {
+                other.overtime
+            } else {
+                other.overtime.or(self.overtime)
+            },
+            cleared: Vec::new(),
         }
     }
 
@@ -58,6 +101,8 @@ impl Day {
             || self.stop.is_some()
             || self.lunch.is_some()
             || !self.projects.is_empty()
+            || self.overtime.is_some()
+            || !self.cleared.is_empty()
     }
 }
 
@@ -68,7 +113,58 @@ mod test {
     use chrono::{NaiveDate, NaiveTime, TimeDelta};
     use rstest::rstest;
 
-    use crate::day::Day;
+    use crate::day::{Day, Field};
+
+    fn day(start: Option<&str>, projects: &[(&str, i64)], cleared: Vec<Field>) -> Day {
+        Day {
+            date: NaiveDate::from_ymd_opt(2025, 2, 17).expect(""),
+            start: start.map(|start| NaiveTime::parse_from_str(start, "%H:%M").expect("")),
+            stop: None,
+            lunch: None,
+            projects: projects
+                .iter()
+                .map(|(name, hours)| (name.to_string(), TimeDelta::try_hours(*hours).expect("")))
+                .collect(),
+            overtime: None,
+            cleared,
+        }
+    }
+
+    #[rstest]
+    fn combine_overwrites() {
+        let combined = day(Some("08:00"), &[], vec![]).combine(&day(Some("09:00"), &[], vec![]));
+
+        assert_eq!(combined.start, NaiveTime::from_hms_opt(9, 0, 0));
+    }
+
+    #[rstest]
+    fn combine_clears_field() {
+        let combined = day(Some("08:00"), &[], vec![]).combine(&day(None, &[], vec![Field::Start]));
+
+        assert_eq!(combined.start, None);
+    }
+
+    #[rstest]
+    fn combine_clears_one_project() {
+        let combined = day(None, &[("a", 1), ("b", 2)], vec![]).combine(&day(
+            None,
+            &[],
+            vec![Field::Project("a".to_string())],
+        ));
+
+        assert_eq!(combined.projects.keys().collect::<Vec<_>>(), vec!["b"]);
+    }
+
+    #[rstest]
+    fn combine_clears_all_projects() {
+        let combined = day(None, &[("a", 1), ("b", 2)], vec![]).combine(&day(
+            None,
+            &[],
+            vec![Field::Projects],
+        ));
+
+        assert!(combined.projects.is_empty());
+    }
 
     #[rstest]
     fn debug() {
@@ -79,13 +175,15 @@ mod test {
             stop: Some(NaiveTime::parse_from_str("17:00:00", "%H:%M:%S").expect("")),
             lunch: Some(TimeDelta::zero()),
             projects: HashMap::new(),
+            overtime: None,
+            cleared: Vec::new(),
         };
 
         // Format the Day instance using Debug
         let debug_output = format!("{:?}", day);
 
         // Define the expected output string
-        let expected = r#"Day { date: 2025-02-17, start: Some(08:00:00), stop: Some(17:00:00), lunch: Some(TimeDelta { secs: 0, nanos: 0 }), projects: {} }"#;
+        let expected = r#"Day { date: 2025-02-17, start: Some(08:00:00), stop: Some(17:00:00), lunch: Some(TimeDelta { secs: 0, nanos: 0 }), projects: {}, overtime: None, cleared: [] }"#;
 
         // Assert that the Debug output matches the expected format
         assert_eq!(debug_output, expected);
@@ -4,18 +4,19 @@ use crate::mockopen::open;
 use open;
 use std::collections::HashMap;
 use std::io::Error;
-use std::slice::Iter;
 use std::thread::sleep;
 use std::{fs, time};
 
 use build_html::Html;
-use chrono::{NaiveDate, TimeDelta};
-use tabled::grid::records::vec_records::{Cell, CellInfo};
+use chrono::{Datelike, NaiveDate, Weekday};
+use tabled::grid::records::vec_records::Cell;
 use tabled::grid::records::Records;
 use tempfile::tempdir;
 
+use crate::copy_format::{CopyFormat, Layout};
 use crate::day::Day;
 use crate::naive_date::one_date_per_week;
+use crate::settings::Settings;
 use crate::table::create_table;
 
 const JAVASCRIPT: &str = "
@@ -39,19 +40,15 @@ pub fn create_html_table(
     last_date: NaiveDate,
     day_from_date: &HashMap<NaiveDate, Day>,
     show_weekend: bool,
-    project_names: &Vec<String>,
-    working_time_per_day: &TimeDelta,
+    settings: &Settings,
 ) -> Result<(), Error> {
     let html: String = one_date_per_week(first_date, last_date)
         .iter()
         .map(|date| {
-            to_html_table(create_table(
-                *date,
-                &day_from_date,
-                show_weekend,
-                &project_names,
-                working_time_per_day,
-            ))
+            to_html_table(
+                create_table(*date, &day_from_date, show_weekend, settings),
+                &settings.copy_format,
+            )
             .to_html_string()
         })
         .collect();
@@ -68,97 +65,141 @@ pub fn create_html_table(
     Ok(())
 }
 
-fn to_html_table(table: tabled::Table) -> build_html::Table {
-    let mut html_rows: Vec<Vec<String>> = vec![];
-    let mut table_rows = table.get_records().iter_rows().peekable();
-    let mut i = 0;
-    while let Some(table_row) = table_rows.next() {
-        let row_iter = table_row.iter();
-        let is_button_row = i >= 5;
-
-        let html_row = if table_rows.peek().is_none() {
-            to_html_row(row_iter, time_to_decimal_string_flex)
-        } else if is_button_row {
-            to_html_row(row_iter, time_to_decimal_string_normal)
-        } else {
-            row_iter.map(|cell| cell.text().to_string()).collect()
-        };
-
-        html_rows.push(html_row);
-        i += 1;
-    }
-    build_html::Table::from(html_rows)
-}
+fn to_html_table(table: tabled::Table, copy_format: &CopyFormat) -> build_html::Table {
+    let rows: Vec<Vec<String>> = table
+        .get_records()
+        .iter_rows()
+        .map(|row| row.iter().map(|cell| cell.text().to_string()).collect())
+        .collect();
+    // The first column is the row header, so it is never excluded
+    let is_copied_column: Vec<bool> = rows[0]
+        .iter()
+        .enumerate()
+        .map(|(i, date)| i > 0 && (copy_format.include_weekend || !is_weekend(date)))
+        .collect();
 
-fn to_html_row<F>(mut row_iter: Iter<'_, CellInfo<String>>, time_to_string: F) -> Vec<String>
-where
-    F: Fn(String) -> String,
-{
-    let first_cell = row_iter.next().expect("all rows have at least one cell");
-    let cells_except_first: Vec<String> = row_iter
-        .clone()
-        .map(|cell| cell.text().to_string())
-        .map(time_to_string)
+    let row_kinds: Vec<RowKind> = rows
+        .iter()
+        .enumerate()
+        .map(|(i, row)| row_kind(i, &row[0]))
         .collect();
-    let cells_except_first_with_tab_separators = cells_except_first.join("\t");
-    let first_cell_text = format!(
-        "<button onclick=\"copyToClipboard('{}')\">{}</button>",
-        cells_except_first_with_tab_separators,
-        first_cell.text()
-    );
-    let mut html_row: Vec<String> = vec![first_cell_text];
-    for string in cells_except_first {
-        html_row.push(string)
+
+    let mut html_rows: Vec<Vec<String>> = rows
+        .iter()
+        .zip(&row_kinds)
+        .map(|(row, kind)| {
+            if kind.has_copy_button() {
+                let html_row = row
+                    .iter()
+                    .enumerate()
+                    .map(|(j, cell)| {
+                        if j == 0 {
+                            cell.clone()
+                        } else {
+                            copy_format.format(cell, *kind == RowKind::Flex)
+                        }
+                    })
+                    .collect();
+                match copy_format.layout {
+                    Layout::Rows => with_row_button(html_row, &is_copied_column),
+                    Layout::Columns => html_row,
+                }
+            } else {
+                row.clone()
+            }
+        })
+        .collect();
+
+    if copy_format.layout == Layout::Columns {
+        let project_rows: Vec<&Vec<String>> = html_rows
+            .iter()
+            .zip(&row_kinds)
+            .filter(|(_, kind)| **kind == RowKind::Project)
+            .map(|(row, _)| row)
+            .collect();
+        let button_row = column_buttons(&project_rows, &is_copied_column);
+        html_rows.insert(2, button_row);
     }
-    html_row
+    build_html::Table::from(html_rows)
 }
 
-fn time_to_decimal_string_normal(time_str: String) -> String {
-    time_to_decimal_string(time_str, false)
+/// What a row of the table from `create_table` shows, since rows like the
+/// overtime rows are not always there.
+#[derive(PartialEq)]
+enum RowKind {
+    /// The date and weekday rows
+    Header,
+    /// Start, stop and lunch
+    Time,
+    Project,
+    Overtime,
+    Flex,
 }
 
-fn time_to_decimal_string_flex(time_str: String) -> String {
-    time_to_decimal_string(time_str, true)
+impl RowKind {
+    fn has_copy_button(&self) -> bool {
+        matches!(self, RowKind::Project | RowKind::Overtime | RowKind::Flex)
+    }
 }
 
-fn time_to_decimal_string(time_str: String, flex: bool) -> String {
-    // An empty string returns an empty string
-    if time_str.is_empty() {
-        return String::new();
+/// The kind of the row at `index`, from the name in its first cell. Project
+/// names are numbered, so a project can't be taken for another kind of row.
+fn row_kind(index: usize, name: &str) -> RowKind {
+    let is_numbered = name.split_once(". ").is_some_and(|(number, _)| {
+        !number.is_empty() && number.chars().all(|c| c.is_ascii_digit())
+    });
+    if index < 2 {
+        RowKind::Header
+    } else if is_numbered {
+        RowKind::Project
+    } else if name.starts_with("Overtime ") {
+        RowKind::Overtime
+    } else if name == "Flex" {
+        RowKind::Flex
+    } else {
+        RowKind::Time
     }
-    let is_negative = time_str.starts_with('-');
-    let parts: Vec<&str> = time_str.trim_start_matches('-').split(':').collect();
+}
 
-    // Handle format validation
-    if parts.len() != 2 {
-        return format!("Error: Invalid format '{}'. Use HH:MM", time_str);
+fn is_weekend(date: &str) -> bool {
+    match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        Ok(date) => matches!(date.weekday(), Weekday::Sat | Weekday::Sun),
+        Err(_) => false,
     }
+}
 
-    // Parse hours with error handling
-    let hours = match parts[0].parse::<u32>() {
-        Ok(h) if h < 24 => h,
-        Ok(_) => return format!("Error: Hours in '{}' must be < 24", time_str),
-        Err(_) => return format!("Error: Invalid hours '{}'", parts[0]),
-    };
-
-    // Parse minutes with error handling
-    let minutes = match parts[1].parse::<u32>() {
-        Ok(m) if m < 60 => m,
-        Ok(_) => return format!("Error: Minutes in '{}' must be < 60", time_str),
-        Err(_) => return format!("Error: Invalid minutes '{}'", parts[1]),
-    };
-
-    // Calculate and format the result
-    let mut decimal = hours as f64 + (minutes as f64 / 60.0);
-    if is_negative {
-        decimal = -decimal;
-    }
-    if flex {
-        if decimal > 0.0 {
-            return String::new();
-        } else if decimal < 0.0 {
-            decimal = -decimal;
-        }
-    }
-    format!("{:.2}", decimal).replace('.', ",")
+/// Replace the first cell with a button that copies the rest of the row,
+/// separated by tabs.
+fn with_row_button(mut html_row: Vec<String>, is_copied_column: &[bool]) -> Vec<String> {
+    let cells_to_copy: Vec<&str> = html_row
+        .iter()
+        .zip(is_copied_column)
+        .filter(|(_, is_copied)| **is_copied)
+        .map(|(cell, _)| cell.as_str())
+        .collect();
+    html_row[0] = copy_button(&cells_to_copy.join("\t"), &html_row[0]);
+    html_row
+}
+
+/// Create a row with one button per day that copies the project rows of that
+/// day, separated by newlines.
+fn column_buttons(project_rows: &[&Vec<String>], is_copied_column: &[bool]) -> Vec<String> {
+    is_copied_column
+        .iter()
+        .enumerate()
+        .map(|(j, is_copied)| {
+            if !is_copied {
+                return "".to_string();
+            }
+            let cells_to_copy: Vec<&str> = project_rows.iter().map(|row| row[j].as_str()).collect();
+            copy_button(&cells_to_copy.join("\\n"), "Copy")
+        })
+        .collect()
+}
+
+fn copy_button(text_to_copy: &str, label: &str) -> String {
+    format!(
+        "<button onclick=\"copyToClipboard('{}')\">{}</button>",
+        text_to_copy, label
+    )
 }
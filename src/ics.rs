@@ -0,0 +1,461 @@
+use std::collections::HashMap;
+
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, TimeZone, Utc};
+
+use crate::day::Day;
+use crate::table::default_project_time;
+use crate::traits::Parsable;
+
+/// Create an iCalendar (RFC 5545) file with the days from first_date to
+/// last_date.
+///
+/// Days with start and stop become events with the lunch and project times in
+/// the description. Days with only project times, such as vacation or
+/// sickness, become all-day events. The UIDs only depend on the date and on
+/// `calendar_name`, e.g. the path of the data file, so that importing the
+/// file again updates the events instead of duplicating them, while the
+/// events of different profiles are kept apart.
+pub fn create_ics(
+    first_date: NaiveDate,
+    last_date: NaiveDate,
+    day_from_date: &HashMap<NaiveDate, Day>,
+    project_names: &[String],
+    calendar_name: &str,
+) -> String {
+    let dtstamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let calendar_id = format!("{:016x}", fnv1a(calendar_name));
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!(
+            "PRODID:-//timereport//timereport {}//EN",
+            env!("CARGO_PKG_VERSION")
+        ),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+    for date in first_date.iter_days().take_while(|date| *date <= last_date) {
+        if let Some(day) = day_from_date.get(&date) {
+            lines.extend(event(day, project_names, &dtstamp, &calendar_id));
+        }
+    }
+    lines.push("END:VCALENDAR".to_string());
+    lines
+        .iter()
+        .map(|line| fold(line))
+        .collect::<Vec<_>>()
+        .join("\r\n")
+        + "\r\n"
+}
+
+/// The 64-bit FNV-1a hash, which unlike the hasher of the standard library
+/// stays the same across Rust versions.
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn event(day: &Day, project_names: &[String], dtstamp: &str, calendar_id: &str) -> Vec<String> {
+    let date = day.date.format("%Y%m%d").to_string();
+    let mut lines = vec!["BEGIN:VEVENT".to_string()];
+    match (day.start, day.stop) {
+        (Some(start), Some(stop)) => {
+            // A stop time before the start time means that the work continued
+            // past midnight
+            let stop_date = if stop < start {
+                day.date.succ_opt().expect("not the last day in history")
+            } else {
+                day.date
+            };
+            lines.push(format!("UID:{}-work-{}@timereport", date, calendar_id));
+            lines.push(format!("DTSTAMP:{}", dtstamp));
+            lines.push(format!("DTSTART:{}", local_datetime(day.date, start)));
+            lines.push(format!("DTEND:{}", local_datetime(stop_date, stop)));
+            lines.push("SUMMARY:Work".to_string());
+            lines.push(format!(
+                "DESCRIPTION:{}",
+                escape(&description(day, project_names))
+            ));
+        }
+        (None, None) if !day.projects.is_empty() => {
+            let next_date = day
+                .date
+                .succ_opt()
+                .expect("not the last day in history")
+                .format("%Y%m%d");
+            let mut absences: Vec<&String> = day.projects.keys().collect();
+            absences.sort();
+            let summary = absences
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            lines.push(format!("UID:{}-absence-{}@timereport", date, calendar_id));
+            lines.push(format!("DTSTAMP:{}", dtstamp));
+            lines.push(format!("DTSTART;VALUE=DATE:{}", date));
+            lines.push(format!("DTEND;VALUE=DATE:{}", next_date));
+            lines.push(format!("SUMMARY:{}", escape(&summary)));
+            lines.push(format!(
+                "DESCRIPTION:{}",
+                escape(&description(day, project_names))
+            ));
+            lines.push("TRANSP:TRANSPARENT".to_string());
+        }
+        // Days with only a start or only a stop cannot become an event
+        _ => return vec![],
+    }
+    lines.push("END:VEVENT".to_string());
+    lines
+}
+
+fn local_datetime(date: NaiveDate, time: NaiveTime) -> String {
+    NaiveDateTime::new(date, time)
+        .format("%Y%m%dT%H%M%S")
+        .to_string()
+}
+
+fn description(day: &Day, project_names: &[String]) -> String {
+    let mut lines = vec![];
+    if let Some(lunch) = day.lunch {
+        lines.push(format!("Lunch: {}", lunch.to_hhmm()));
+    }
+    if let Some(timedelta) = default_project_time(day) {
+        lines.push(format!("1. Default project: {}", timedelta.to_hhmm()));
+    }
+    for (index, project_name) in project_names.iter().enumerate() {
+        if let Some(timedelta) = day.projects.get(project_name) {
+            lines.push(format!(
+                "{}. {}: {}",
+                index + 2,
+                project_name,
+                timedelta.to_hhmm()
+            ));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Escape a TEXT value according to RFC 5545 section 3.3.11.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Fold a content line so that no line is longer than 75 octets, according
+/// to RFC 5545 section 3.1.
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut line_length = 0;
+    for c in line.chars() {
+        if line_length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            line_length = 1;
+        }
+        folded.push(c);
+        line_length += c.len_utf8();
+    }
+    folded
+}
+
+/// The start and end of an event, in local time.
+#[derive(Debug, PartialEq)]
+pub enum EventTime {
+    AllDay(NaiveDate, NaiveDate),
+    Timed(NaiveDateTime, NaiveDateTime),
+}
+
+/// The parts of a VEVENT that are needed for importing.
+#[derive(Debug, PartialEq)]
+pub struct Event {
+    pub time: EventTime,
+    pub summary: String,
+    pub categories: Vec<String>,
+}
+
+/// Parse the VEVENTs in an iCalendar file.
+///
+/// Times in UTC are converted to local time, and times with a TZID are
+/// assumed to already be in local time. Recurrence rules are not expanded.
+pub fn parse_ics(text: &str) -> Result<Vec<Event>, String> {
+    let mut events = vec![];
+    let mut properties: Option<Vec<(String, String, String)>> = None;
+    for line in unfold(text) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if line == "BEGIN:VEVENT" {
+            properties = Some(vec![]);
+        } else if line == "END:VEVENT" {
+            match properties.take() {
+                Some(properties) => events.push(to_event(&properties)?),
+                None => return Err("END:VEVENT without BEGIN:VEVENT".to_string()),
+            }
+        } else if let Some(properties) = properties.as_mut() {
+            properties.push(split_property(&line)?);
+        }
+    }
+    if properties.is_some() {
+        return Err("BEGIN:VEVENT without END:VEVENT".to_string());
+    }
+    Ok(events)
+}
+
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for line in text.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// Split a content line into name, parameters and value.
+fn split_property(line: &str) -> Result<(String, String, String), String> {
+    let (name_and_parameters, value) = match line.split_once(':') {
+        Some(split) => split,
+        None => return Err(format!("Could not parse iCalendar line '{}'", line)),
+    };
+    let (name, parameters) = name_and_parameters
+        .split_once(';')
+        .unwrap_or((name_and_parameters, ""));
+    Ok((
+        name.to_uppercase(),
+        parameters.to_uppercase(),
+        value.to_string(),
+    ))
+}
+
+fn to_event(properties: &[(String, String, String)]) -> Result<Event, String> {
+    let get = |target: &str| {
+        properties
+            .iter()
+            .find(|(name, _, _)| name == target)
+            .map(|(_, parameters, value)| (parameters.as_str(), value.as_str()))
+    };
+    let (start_parameters, start_value) = match get("DTSTART") {
+        Some(start) => start,
+        None => return Err("VEVENT without DTSTART".to_string()),
+    };
+    let time = if start_parameters.contains("VALUE=DATE") && !start_value.contains('T') {
+        let start = parse_ics_date(start_value)?;
+        let end = match get("DTEND") {
+            Some((_, value)) => parse_ics_date(value)?,
+            None => start + parse_duration(get("DURATION").map_or("P1D", |(_, value)| value))?,
+        };
+        EventTime::AllDay(start, end)
+    } else {
+        let start = parse_ics_datetime(start_value)?;
+        let end = match get("DTEND") {
+            Some((_, value)) => parse_ics_datetime(value)?,
+            None => start + parse_duration(get("DURATION").map_or("PT0S", |(_, value)| value))?,
+        };
+        EventTime::Timed(start, end)
+    };
+    let summary = get("SUMMARY").map_or(String::new(), |(_, value)| unescape(value));
+    let categories = properties
+        .iter()
+        .filter(|(name, _, _)| name == "CATEGORIES")
+        .flat_map(|(_, _, value)| split_list(value).into_iter().map(unescape))
+        .collect();
+    Ok(Event {
+        time,
+        summary,
+        categories,
+    })
+}
+
+fn parse_ics_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y%m%d")
+        .map_err(|_| format!("Could not parse iCalendar date '{}'", value))
+}
+
+fn parse_ics_datetime(value: &str) -> Result<NaiveDateTime, String> {
+    let error = || format!("Could not parse iCalendar date-time '{}'", value);
+    match value.strip_suffix('Z') {
+        Some(utc_value) => {
+            let utc =
+                NaiveDateTime::parse_from_str(utc_value, "%Y%m%dT%H%M%S").map_err(|_| error())?;
+            Ok(Utc
+                .from_utc_datetime(&utc)
+                .with_timezone(&Local)
+                .naive_local())
+        }
+        None => NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").map_err(|_| error()),
+    }
+}
+
+/// Parse a DURATION value such as "PT1H30M" or "P1D".
+fn parse_duration(value: &str) -> Result<TimeDelta, String> {
+    let error = || format!("Could not parse iCalendar duration '{}'", value);
+    let (sign, value) = match value.strip_prefix('-') {
+        Some(value) => (-1, value),
+        None => (1, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let value = value.strip_prefix('P').ok_or_else(error)?;
+    let mut seconds = 0;
+    let mut number = String::new();
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let multiplier = match c {
+            'T' => continue,
+            'W' => 7 * 24 * 3600,
+            'D' => 24 * 3600,
+            'H' => 3600,
+            'M' => 60,
+            'S' => 1,
+            _ => return Err(error()),
+        };
+        seconds += number.parse::<i64>().map_err(|_| error())? * multiplier;
+        number.clear();
+    }
+    TimeDelta::try_seconds(sign * seconds).ok_or_else(error)
+}
+
+/// Split a list value on the commas that are not escaped.
+fn split_list(value: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut start = 0;
+    let mut is_escaped = false;
+    for (index, c) in value.char_indices() {
+        match c {
+            _ if is_escaped => is_escaped = false,
+            '\\' => is_escaped = true,
+            ',' => {
+                items.push(&value[start..index]);
+                start = index + 1;
+            }
+            _ => (),
+        }
+    }
+    items.push(&value[start..]);
+    items
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') | Some('N') => unescaped.push('\n'),
+                Some(other) => unescaped.push(other),
+                None => (),
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{NaiveDate, TimeDelta};
+    use rstest::rstest;
+
+    use crate::ics::{
+        escape, fnv1a, fold, parse_duration, parse_ics, split_list, unescape, unfold, Event,
+        EventTime,
+    };
+
+    #[rstest]
+    fn escape_special_characters() {
+        assert_eq!(escape("a,b;c\\d\ne"), "a\\,b\\;c\\\\d\\ne");
+    }
+
+    #[rstest]
+    fn fnv1a_known_values() {
+        assert_eq!(fnv1a(""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a("a"), 0xaf63dc4c8601ec8c);
+    }
+
+    #[rstest]
+    fn fold_long_line() {
+        let line = "x".repeat(100);
+
+        let folded = fold(&line);
+
+        let lines: Vec<&str> = folded.split("\r\n").collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].len(), 75);
+        assert_eq!(lines[1], format!(" {}", "x".repeat(25)));
+    }
+
+    #[rstest]
+    fn fold_does_not_split_characters() {
+        let line = "å".repeat(40);
+
+        let folded = fold(&line);
+
+        assert!(folded.split("\r\n").all(|line| line.len() <= 75));
+        assert_eq!(folded.replace("\r\n ", ""), line);
+    }
+
+    #[rstest]
+    fn unescape_is_inverse_of_escape() {
+        let text = "a,b;c\\d\ne";
+
+        assert_eq!(unescape(&escape(text)), text);
+    }
+
+    #[rstest]
+    fn split_list_keeps_escaped_commas() {
+        assert_eq!(
+            split_list("Off,Sick\\, child,A\\\\"),
+            vec!["Off", "Sick\\, child", "A\\\\"]
+        );
+    }
+
+    #[rstest]
+    fn unfold_is_inverse_of_fold() {
+        let line = "x".repeat(200);
+
+        assert_eq!(unfold(&fold(&line)), vec![line]);
+    }
+
+    #[rstest]
+    #[case("PT1H30M", 5400)]
+    #[case("P1D", 86400)]
+    #[case("-PT15M", -900)]
+    fn duration(#[case] value: &str, #[case] seconds: i64) {
+        assert_eq!(
+            parse_duration(value),
+            Ok(TimeDelta::try_seconds(seconds).expect(""))
+        );
+    }
+
+    #[rstest]
+    fn parse_all_day_event() {
+        let text = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTART;VALUE=DATE:20250416\r\nSUMMARY:Vacation\r\nCATEGORIES:Off,Leave\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+
+        let events = parse_ics(text);
+
+        let date = NaiveDate::from_ymd_opt(2025, 4, 16).expect("");
+        assert_eq!(
+            events,
+            Ok(vec![Event {
+                time: EventTime::AllDay(date, date.succ_opt().expect("")),
+                summary: "Vacation".to_string(),
+                categories: vec!["Off".to_string(), "Leave".to_string()],
+            }])
+        );
+    }
+
+    #[rstest]
+    fn parse_unterminated_event() {
+        let text = "BEGIN:VEVENT\nDTSTART:20250416T080000\n";
+
+        assert_eq!(
+            parse_ics(text),
+            Err("BEGIN:VEVENT without END:VEVENT".to_string())
+        );
+    }
+}
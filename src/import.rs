@@ -0,0 +1,478 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use chrono::{NaiveDate, NaiveTime, TimeDelta};
+use serde::{Deserialize, Serialize};
+
+use crate::day::Day;
+use crate::ics::{Event, EventTime};
+
+/// Convert calendar events between first_date and last_date to days.
+///
+/// An event belongs to a project if its summary or one of its categories is
+/// the name of the project. The earliest start and the latest stop of the
+/// timed events of a date become the start and stop of that day, and the
+/// durations of the events that belong to a project are added to that
+/// project. All-day events that belong to a project, such as vacation, count
+/// as a full working day on the project. Other all-day events are ignored.
+pub fn days_from_events(
+    events: &[Event],
+    first_date: NaiveDate,
+    last_date: NaiveDate,
+    project_names: &[String],
+    working_time_per_day: &TimeDelta,
+) -> Vec<Day> {
+    let mut day_from_date: BTreeMap<NaiveDate, Day> = BTreeMap::new();
+    for event in events {
+        let project = project_of(event, project_names);
+        match event.time {
+            EventTime::Timed(start, stop) => {
+                let date = start.date();
+                if date < first_date || date > last_date {
+                    continue;
+                }
+                let day = day_from_date.entry(date).or_insert_with(|| empty_day(date));
+                day.start = Some(day.start.map_or(start.time(), |old| old.min(start.time())));
+                day.stop = Some(day.stop.map_or(stop.time(), |old| old.max(stop.time())));
+                if let Some(project) = project {
+                    *day.projects.entry(project).or_insert(TimeDelta::zero()) += stop - start;
+                }
+            }
+            EventTime::AllDay(first_event_date, end_date) => {
+                let project = match project {
+                    Some(project) => project,
+                    None => continue,
+                };
+                // The end date of an all-day event is exclusive, but an event
+                // without an end date still lasts one day
+                let end_date = end_date.max(
+                    first_event_date
+                        .succ_opt()
+                        .expect("not the last day in history"),
+                );
+                let dates = first_event_date
+                    .iter_days()
+                    .take_while(|date| *date < end_date)
+                    .filter(|date| *date >= first_date && *date <= last_date);
+                for date in dates {
+                    let day = day_from_date.entry(date).or_insert_with(|| empty_day(date));
+                    day.projects.insert(project.clone(), *working_time_per_day);
+                }
+            }
+        }
+    }
+    day_from_date.into_values().collect()
+}
+
+fn project_of(event: &Event, project_names: &[String]) -> Option<String> {
+    std::iter::once(&event.summary)
+        .chain(event.categories.iter())
+        .find_map(|name| {
+            project_names
+                .iter()
+                .find(|project_name| project_name.to_lowercase() == name.trim().to_lowercase())
+        })
+        .cloned()
+}
+
+/// The columns of a CSV file exported from another time tracker.
+///
+/// Built-in formats can be overridden, and new formats added, under
+/// `csv_formats` in the config file. Either stop or duration is needed to
+/// know how long an entry is.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CsvFormat {
+    pub date: String,
+    pub date_format: String,
+    pub start: Option<String>,
+    pub stop: Option<String>,
+    pub duration: Option<String>,
+    pub project: Option<String>,
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+}
+
+fn default_delimiter() -> char {
+    ','
+}
+
+impl CsvFormat {
+    fn new(date: &str, date_format: &str, start: &str, stop: &str, project: &str) -> Self {
+        Self {
+            date: date.to_string(),
+            date_format: date_format.to_string(),
+            start: Some(start.to_string()),
+            stop: Some(stop.to_string()),
+            duration: None,
+            project: Some(project.to_string()),
+            delimiter: default_delimiter(),
+        }
+    }
+}
+
+/// Find a CSV format by name, preferring the formats from the config file
+/// over the built-in ones.
+pub fn csv_format(
+    name: &str,
+    csv_formats: &HashMap<String, CsvFormat>,
+) -> Result<CsvFormat, String> {
+    if let Some(csv_format) = csv_formats.get(name) {
+        return Ok(csv_format.clone());
+    }
+    match name {
+        "toggl" => Ok(CsvFormat::new(
+            "Start date",
+            "%Y-%m-%d",
+            "Start time",
+            "End time",
+            "Project",
+        )),
+        "clockify" => Ok(CsvFormat::new(
+            "Start Date",
+            "%m/%d/%Y",
+            "Start Time",
+            "End Time",
+            "Project",
+        )),
+        "generic-csv" => Ok(CsvFormat::new(
+            "date", "%Y-%m-%d", "start", "stop", "project",
+        )),
+        _ => Err(format!("Unknown import format '{}'", name)),
+    }
+}
+
+/// One row of a CSV file.
+struct Entry {
+    date: NaiveDate,
+    start: Option<NaiveTime>,
+    stop: Option<NaiveTime>,
+    duration: Option<TimeDelta>,
+    project: Option<String>,
+}
+
+/// Convert the rows of a CSV file between first_date and last_date to days.
+///
+/// The earliest start and the latest stop of a date become the start and stop
+/// of that day, and the durations of the entries with a project are added to
+/// that project. Returns the days and the names of the projects in the file
+/// that are not in project_names.
+pub fn days_from_csv(
+    text: &str,
+    csv_format: &CsvFormat,
+    first_date: NaiveDate,
+    last_date: NaiveDate,
+    project_names: &[String],
+) -> Result<(Vec<Day>, Vec<String>), String> {
+    let mut day_from_date: BTreeMap<NaiveDate, Day> = BTreeMap::new();
+    let mut unknown_projects: Vec<String> = vec![];
+    for entry in parse_csv(text, csv_format)? {
+        if entry.date < first_date || entry.date > last_date {
+            continue;
+        }
+        let day = day_from_date
+            .entry(entry.date)
+            .or_insert_with(|| empty_day(entry.date));
+        if let Some(start) = entry.start {
+            day.start = Some(day.start.map_or(start, |old| old.min(start)));
+        }
+        if let Some(stop) = entry.stop {
+            day.stop = Some(day.stop.map_or(stop, |old| old.max(stop)));
+        }
+        let duration = match (entry.duration, entry.start, entry.stop) {
+            (Some(duration), _, _) => duration,
+            (None, Some(start), Some(stop)) => stop - start,
+            _ => TimeDelta::zero(),
+        };
+        if let Some(project) = entry.project {
+            if !project_names.contains(&project) && !unknown_projects.contains(&project) {
+                unknown_projects.push(project.clone());
+            }
+            *day.projects.entry(project).or_insert(TimeDelta::zero()) += duration;
+        }
+    }
+    Ok((day_from_date.into_values().collect(), unknown_projects))
+}
+
+fn parse_csv(text: &str, csv_format: &CsvFormat) -> Result<Vec<Entry>, String> {
+    if !csv_format.delimiter.is_ascii() {
+        return Err(format!(
+            "The CSV delimiter must be an ASCII character, not '{}'",
+            csv_format.delimiter
+        ));
+    }
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(csv_format.delimiter as u8)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    let headers = match reader.headers() {
+        Ok(headers) => headers.clone(),
+        Err(error) => return Err(format!("Could not read CSV header: {}", error)),
+    };
+    let index_of = |column: &Option<String>| -> Result<Option<usize>, String> {
+        match column {
+            None => Ok(None),
+            Some(column) => match headers.iter().position(|header| header.trim() == column) {
+                Some(index) => Ok(Some(index)),
+                None => Err(format!("Column '{}' not found in CSV file", column)),
+            },
+        }
+    };
+    let date_index = index_of(&Some(csv_format.date.clone()))?.expect("column is given");
+    let start_index = index_of(&csv_format.start)?;
+    let stop_index = index_of(&csv_format.stop)?;
+    let duration_index = index_of(&csv_format.duration)?;
+    let project_index = index_of(&csv_format.project)?;
+
+    let mut entries = vec![];
+    for (row, record) in reader.records().enumerate() {
+        let record = match record {
+            Ok(record) => record,
+            Err(error) => return Err(format!("Could not read CSV row {}: {}", row + 2, error)),
+        };
+        let field = |index: Option<usize>| {
+            index
+                .and_then(|index| record.get(index))
+                .map(|field| field.trim())
+                .filter(|field| !field.is_empty())
+        };
+        let with_row = |message: String| format!("{} on CSV row {}", message, row + 2);
+        let date_field = field(Some(date_index)).unwrap_or("");
+        let date = match NaiveDate::parse_from_str(date_field, &csv_format.date_format) {
+            Ok(date) => date,
+            Err(_) => {
+                return Err(with_row(format!(
+                    "Could not parse date '{}' with format '{}'",
+                    date_field, csv_format.date_format
+                )))
+            }
+        };
+        entries.push(Entry {
+            date,
+            start: field(start_index)
+                .map(parse_csv_time)
+                .transpose()
+                .map_err(with_row)?,
+            stop: field(stop_index)
+                .map(parse_csv_time)
+                .transpose()
+                .map_err(with_row)?,
+            duration: field(duration_index)
+                .map(parse_csv_duration)
+                .transpose()
+                .map_err(with_row)?,
+            project: field(project_index).map(|project| project.to_string()),
+        });
+    }
+    Ok(entries)
+}
+
+fn parse_csv_time(text: &str) -> Result<NaiveTime, String> {
+    ["%H:%M:%S", "%H:%M", "%I:%M:%S %p", "%I:%M %p"]
+        .iter()
+        .find_map(|format| NaiveTime::parse_from_str(text, format).ok())
+        .ok_or(format!("Could not parse time '{}'", text))
+}
+
+/// Parse a duration on the format "HH:MM:SS", "HH:MM" or decimal hours.
+fn parse_csv_duration(text: &str) -> Result<TimeDelta, String> {
+    let error = || format!("Could not parse duration '{}'", text);
+    let seconds = if text.contains(':') {
+        let parts: Vec<i64> = text
+            .split(':')
+            .map(|part| part.parse::<i64>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| error())?;
+        match parts.as_slice() {
+            [hours, minutes] => hours * 3600 + minutes * 60,
+            [hours, minutes, seconds] => hours * 3600 + minutes * 60 + seconds,
+            _ => return Err(error()),
+        }
+    } else {
+        let hours: f64 = text.replace(',', ".").parse().map_err(|_| error())?;
+        (hours * 3600.0).round() as i64
+    };
+    TimeDelta::try_seconds(seconds).ok_or_else(error)
+}
+
+fn empty_day(date: NaiveDate) -> Day {
+    Day {
+        date,
+        start: None,
+        stop: None,
+        lunch: None,
+        projects: HashMap::new(),
+        overtime: None,
+        cleared: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use chrono::{NaiveDate, NaiveTime, TimeDelta};
+    use rstest::rstest;
+
+    use crate::ics::{Event, EventTime};
+    use crate::import::{
+        csv_format, days_from_csv, days_from_events, parse_csv_duration, CsvFormat,
+    };
+
+    fn timed_event(summary: &str, start: &str, stop: &str) -> Event {
+        let date = NaiveDate::from_ymd_opt(2025, 4, 15).expect("");
+        Event {
+            time: EventTime::Timed(
+                date.and_time(NaiveTime::parse_from_str(start, "%H:%M").expect("")),
+                date.and_time(NaiveTime::parse_from_str(stop, "%H:%M").expect("")),
+            ),
+            summary: summary.to_string(),
+            categories: vec![],
+        }
+    }
+
+    #[rstest]
+    fn earliest_start_latest_stop_and_project_time() {
+        let events = vec![
+            timed_event("Standup", "08:30", "09:00"),
+            timed_event("project a", "13:00", "16:15"),
+        ];
+        let date = NaiveDate::from_ymd_opt(2025, 4, 15).expect("");
+
+        let days = days_from_events(
+            &events,
+            date,
+            date,
+            &["Project A".to_string()],
+            &TimeDelta::try_hours(8).expect(""),
+        );
+
+        assert_eq!(days.len(), 1);
+        assert_eq!(days[0].start, NaiveTime::from_hms_opt(8, 30, 0));
+        assert_eq!(days[0].stop, NaiveTime::from_hms_opt(16, 15, 0));
+        assert_eq!(
+            days[0].projects.get("Project A"),
+            Some(&TimeDelta::try_minutes(195).expect(""))
+        );
+    }
+
+    #[rstest]
+    fn events_outside_range_are_ignored() {
+        let events = vec![timed_event("Standup", "08:30", "09:00")];
+        let date = NaiveDate::from_ymd_opt(2025, 4, 16).expect("");
+
+        let days = days_from_events(
+            &events,
+            date,
+            date,
+            &[],
+            &TimeDelta::try_hours(8).expect(""),
+        );
+
+        assert!(days.is_empty());
+    }
+
+    #[rstest]
+    #[case("01:30:00", 5400)]
+    #[case("1:30", 5400)]
+    #[case("1,5", 5400)]
+    #[case("0.25", 900)]
+    fn csv_duration(#[case] text: &str, #[case] seconds: i64) {
+        assert_eq!(
+            parse_csv_duration(text),
+            Ok(TimeDelta::try_seconds(seconds).expect(""))
+        );
+    }
+
+    #[rstest]
+    fn clockify_csv() {
+        let text = "Project,Client,Description,Start Date,Start Time,End Date,End Time\n\
+                    Project A,,Coding,04/15/2025,08:30:00 AM,04/15/2025,11:00:00 AM\n\
+                    ,,Email,04/15/2025,01:00:00 PM,04/15/2025,04:00:00 PM\n";
+        let date = NaiveDate::from_ymd_opt(2025, 4, 15).expect("");
+
+        let (days, unknown_projects) = days_from_csv(
+            text,
+            &csv_format("clockify", &HashMap::new()).expect(""),
+            date,
+            date,
+            &["Project A".to_string()],
+        )
+        .expect("");
+
+        assert!(unknown_projects.is_empty());
+        assert_eq!(days[0].start, NaiveTime::from_hms_opt(8, 30, 0));
+        assert_eq!(days[0].stop, NaiveTime::from_hms_opt(16, 0, 0));
+        assert_eq!(
+            days[0].projects.get("Project A"),
+            Some(&TimeDelta::try_minutes(150).expect(""))
+        );
+    }
+
+    #[rstest]
+    fn configured_csv_format_with_duration() {
+        let mut csv_formats = HashMap::new();
+        csv_formats.insert(
+            "harvest".to_string(),
+            CsvFormat {
+                date: "Date".to_string(),
+                date_format: "%Y-%m-%d".to_string(),
+                start: None,
+                stop: None,
+                duration: Some("Hours".to_string()),
+                project: Some("Project".to_string()),
+                delimiter: ';',
+            },
+        );
+        let text = "Date;Project;Hours\n2025-04-15;Project B;2,5\n";
+        let date = NaiveDate::from_ymd_opt(2025, 4, 15).expect("");
+
+        let (days, unknown_projects) = days_from_csv(
+            text,
+            &csv_format("harvest", &csv_formats).expect(""),
+            date,
+            date,
+            &[],
+        )
+        .expect("");
+
+        assert_eq!(unknown_projects, vec!["Project B".to_string()]);
+        assert_eq!(
+            days[0].projects.get("Project B"),
+            Some(&TimeDelta::try_minutes(150).expect(""))
+        );
+    }
+
+    #[rstest]
+    fn missing_column() {
+        let date = NaiveDate::from_ymd_opt(2025, 4, 15).expect("");
+
+        let result = days_from_csv(
+            "foo,bar\n",
+            &csv_format("toggl", &HashMap::new()).expect(""),
+            date,
+            date,
+            &[],
+        );
+
+        assert_eq!(
+            result.err(),
+            Some("Column 'Start date' not found in CSV file".to_string())
+        );
+    }
+
+    #[rstest]
+    fn non_ascii_delimiter() {
+        let csv_format = CsvFormat {
+            delimiter: '§',
+            ..csv_format("toggl", &HashMap::new()).expect("")
+        };
+        let date = NaiveDate::from_ymd_opt(2025, 4, 15).expect("");
+
+        let result = days_from_csv("Start date\n", &csv_format, date, date, &[]);
+
+        assert_eq!(
+            result.err(),
+            Some("The CSV delimiter must be an ASCII character, not '§'".to_string())
+        );
+    }
+}
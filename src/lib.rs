@@ -1,41 +1,41 @@
-use argparse::consume_after_target;
-use argparse::consume_bool;
-use argparse::consume_dates;
-use argparse::consume_two_after_target;
+use argparse::{Command, ConfigCommand, Entry, Format, Period, Show, Undo};
 use chrono::prelude::*;
 use chrono::Duration;
 use chrono::TimeDelta;
 use naive_date::last_day_of_month;
 use std::collections::HashMap;
+use std::fs;
+mod lunch;
 mod naive_date;
+mod overtime;
+mod rounding;
 use std::path::Path;
+use storage::{Backend, Storage};
 mod traits;
 use traits::Parsable;
 mod argparse;
+mod check;
+mod completions;
+mod compliance;
 mod config;
+mod copy_format;
 mod day;
 #[cfg(feature = "mock-open")]
 pub mod mockopen;
 // Rust note: need to do pub table here since it is used in the binary crate main.rs
 mod html_table;
+mod ics;
+mod import;
+mod settings;
+mod shell;
+pub mod storage;
+mod suggest;
 pub mod table;
 mod timedelta;
-use day::Day;
-
-const MONTHS: &[&str] = &[
-    "january",
-    "february",
-    "march",
-    "april",
-    "may",
-    "june",
-    "july",
-    "august",
-    "september",
-    "october",
-    "november",
-    "december",
-];
+mod tui;
+pub use config::Config;
+pub use day::Day;
+pub use shell::{batch, shell};
 
 pub fn parse_time(text: &str) -> Result<NaiveTime, String> {
     let time_string = if text.contains(":") {
@@ -51,342 +51,796 @@ pub fn parse_time(text: &str) -> Result<NaiveTime, String> {
     }
 }
 
-fn parse_projects(
-    args: Vec<String>,
-    project_names: &Vec<String>,
-) -> Result<(HashMap<String, TimeDelta>, Vec<String>), String> {
-    let (result, args) = consume_two_after_target("project", args);
-    let (project, timedelta) = match result {
-        Ok(option) => match option {
-            Some((project, timedelta)) => (project, timedelta),
-            None => return Ok((HashMap::new(), args)),
+fn create_html_table(
+    first_date: NaiveDate,
+    last_date: NaiveDate,
+    day_from_date: &HashMap<NaiveDate, Day>,
+    show_weekend: bool,
+    settings: &settings::Settings,
+) -> Result<String, String> {
+    match html_table::create_html_table(
+        first_date,
+        last_date,
+        day_from_date,
+        show_weekend,
+        settings,
+    ) {
+        Ok(_) => Ok("".to_string()),
+        Err(error) => Err(format!("Error: '{}'", error.to_string())),
+    }
+}
+
+fn undo(storage: &dyn Storage, undo: Undo, now: NaiveDateTime) -> Result<String, String> {
+    let mut config = config::load(storage, now.date())?;
+    let previous_day_from_date = &config.rounded_day_from_date();
+
+    let mut dates = vec![];
+    match undo {
+        Undo::Date(date) => match config.undo_date(date) {
+            Ok(undone_dates) => dates.extend(undone_dates),
+            Err(message) => return Err(message),
         },
-        Err(message) => return Err(message),
-    };
-    let timedelta = match TimeDelta::from_str(&timedelta) {
-        Ok(dt) => dt,
-        Err(message) => return Err(message),
+        Undo::Steps(step_count) => {
+            for i in 0..step_count {
+                match config.undo() {
+                    Ok(undone_dates) => dates.extend(undone_dates),
+                    // Undo as many steps as possible
+                    Err(_) if i > 0 => break,
+                    Err(message) => return Err(message),
+                }
+            }
+        }
+    }
+    config.save(storage)?;
+    if dates.is_empty() {
+        dates.push(now.date());
+    }
+    Ok(create_terminal_table_for_dates(
+        &dates,
+        &config,
+        previous_day_from_date,
+        now,
+    ))
+}
+
+fn redo(storage: &dyn Storage, now: NaiveDateTime) -> Result<String, String> {
+    let mut config = config::load(storage, now.date())?;
+    let previous_day_from_date = &config.rounded_day_from_date();
+
+    let mut dates = config.redo()?;
+    config.save(storage)?;
+    if dates.is_empty() {
+        dates.push(now.date());
+    }
+    Ok(create_terminal_table_for_dates(
+        &dates,
+        &config,
+        previous_day_from_date,
+        now,
+    ))
+}
+
+fn history(storage: &dyn Storage, count: usize, today: NaiveDate) -> Result<String, String> {
+    let config = config::load(storage, today)?;
+    let history = config.history();
+    if history.is_empty() {
+        return Ok("No history".to_string());
+    }
+    let mut builder = tabled::builder::Builder::default();
+    builder.push_record(["", "time", "command", "dates"]);
+    for (index, (entry, mut dates)) in history.into_iter().take(count).enumerate() {
+        dates.sort();
+        dates.dedup();
+        builder.push_record([
+            (index + 1).to_string(),
+            entry.timestamp.map_or(String::new(), |timestamp| {
+                timestamp.format("%Y-%m-%d %H:%M").to_string()
+            }),
+            entry.command.clone(),
+            dates
+                .iter()
+                .map(|date| date.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        ]);
+    }
+    Ok(builder
+        .build()
+        .with(tabled::settings::Style::rounded())
+        .to_string())
+}
+
+/// Create terminal tables for the weeks from the first to the last of the
+/// dates, showing the weekend if any of the dates is on a weekend.
+fn create_terminal_table_for_dates(
+    dates: &[NaiveDate],
+    config: &config::Config,
+    previous_day_from_date: &HashMap<NaiveDate, Day>,
+    now: NaiveDateTime,
+) -> String {
+    let first_date = dates.iter().min().expect("dates cannot be empty");
+    let last_date = dates.iter().max().expect("dates cannot be empty");
+    let show_weekend = dates
+        .iter()
+        .any(|date| matches!(date.weekday(), Weekday::Sat | Weekday::Sun));
+    table::create_terminal_table(
+        *first_date,
+        *last_date,
+        &config.rounded_day_from_date(),
+        previous_day_from_date,
+        show_weekend,
+        &config.settings,
+        now,
+    )
+}
+
+fn import(
+    mut config: config::Config,
+    storage: &dyn Storage,
+    import: argparse::Import,
+    now: NaiveDateTime,
+) -> Result<String, String> {
+    let argparse::Import {
+        file,
+        format,
+        dates,
+        add_projects,
+        commit,
+    } = import;
+    let text = match fs::read_to_string(&file) {
+        Ok(text) => text,
+        Err(error) => return Err(format!("Error when trying to read {}: {}", file, error)),
     };
-    let mut map = HashMap::new();
-    let project = if !project_names.contains(&project) {
-        let project_index: usize = match project.parse() {
-            Ok(project_index) => project_index,
-            Err(_) => return Err(format!("Unknown project '{}'", project)),
-        };
-        if project_index == 0 {
-            return Err("No project with index 0".to_string());
+    let first_date = dates.iter().min().copied().unwrap_or(NaiveDate::MIN);
+    let last_date = dates.iter().max().copied().unwrap_or(NaiveDate::MAX);
+    let format = format.unwrap_or_else(|| {
+        if file.to_lowercase().ends_with(".csv") {
+            "generic-csv".to_string()
+        } else {
+            "ics".to_string()
         }
-        if project_index == 1 {
-            return Err("Cannot report time on default project".to_string());
+    });
+    let previous_day_from_date = &config.rounded_day_from_date();
+
+    let days = if format == "ics" {
+        let events = ics::parse_ics(&text)?;
+        import::days_from_events(
+            &events,
+            first_date,
+            last_date,
+            &config.settings.project_names,
+            &config.settings.working_time_per_day,
+        )
+    } else {
+        let csv_format = import::csv_format(&format, &config.settings.csv_formats)?;
+        let (days, unknown_projects) = import::days_from_csv(
+            &text,
+            &csv_format,
+            first_date,
+            last_date,
+            &config.settings.project_names,
+        )?;
+        if !unknown_projects.is_empty() && !add_projects {
+            return Err(format!(
+                "Unknown project '{}'. Add the projects with 'add' first, or use --add-projects to add them automatically.",
+                unknown_projects.join("', '")
+            ));
         }
-        // -2 here since the first non-default project has index 2
-        match project_names.get(project_index - 2) {
-            Some(project_name) => project_name.to_string(),
-            None => return Err(format!("No project with index {}", project_index)),
+        for project_name in unknown_projects {
+            config.add_project(project_name);
         }
+        days
+    };
+    if days.is_empty() {
+        return Err("Nothing to import".to_string());
+    }
+
+    let dates: Vec<NaiveDate> = days.iter().map(|day| day.date).collect();
+    config.add_days(days);
+    let table = create_terminal_table_for_dates(&dates, &config, previous_day_from_date, now);
+    if commit {
+        config.save(storage)?;
+        Ok(table)
     } else {
-        project
+        Ok(format!(
+            "{}\n\nPreview only, nothing was saved. Run the command again with --commit to import.",
+            table
+        ))
+    }
+}
+
+fn suggest(
+    mut config: config::Config,
+    storage: &dyn Storage,
+    date: Option<NaiveDate>,
+    record: bool,
+    now: NaiveDateTime,
+) -> Result<String, String> {
+    let date = date.unwrap_or(now.date());
+    if config.settings.git_repositories.is_empty() {
+        return Err(
+            "No git repositories configured. Add them under git_repositories in the config file."
+                .to_string(),
+        );
+    }
+    let previous_day_from_date = &config.rounded_day_from_date();
+    let (start, stop, lunch) = match previous_day_from_date.get(&date) {
+        Some(Day {
+            start: Some(start),
+            stop: Some(stop),
+            lunch,
+            ..
+        }) => (*start, *stop, lunch.unwrap_or(TimeDelta::zero())),
+        _ => {
+            return Err(format!(
+                "Start and stop must be reported for {} first",
+                date
+            ))
+        }
     };
-    map.insert(project, timedelta);
-    Ok((map, args))
+    let mut commits = vec![];
+    for repository in &config.settings.git_repositories {
+        match suggest::commits(repository, date.and_time(start), date.and_time(stop)) {
+            Ok(repository_commits) => commits.extend(repository_commits),
+            Err(message) => return Err(message),
+        }
+    }
+    let project_times = suggest::estimate_project_times(date, start, stop, lunch, commits);
+    if project_times.is_empty() {
+        return Err(format!("No commits on any project found for {}", date));
+    }
+
+    if record {
+        let mut projects = HashMap::new();
+        for (project, timedelta) in project_times {
+            let project = argparse::project_name(&project, &config.settings.project_names)?;
+            projects.insert(project, timedelta);
+        }
+        config.add_days(vec![Day {
+            date,
+            start: None,
+            stop: None,
+            lunch: None,
+            projects,
+            overtime: None,
+            cleared: Vec::new(),
+        }]);
+        config.save(storage)?;
+        return Ok(create_terminal_table_for_dates(
+            &[date],
+            &config,
+            previous_day_from_date,
+            now,
+        ));
+    }
+    let mut lines = vec![format!(
+        "Suggested project times for {}, based on git commits:",
+        date
+    )];
+    for (project, timedelta) in project_times {
+        let project = if project.contains(' ') {
+            format!("\"{}\"", project)
+        } else {
+            project
+        };
+        lines.push(format!(
+            "  t {} project {} {}",
+            date,
+            project,
+            timedelta.to_hhmm()
+        ));
+    }
+    lines.push("Run the command again with --record to record them.".to_string());
+    Ok(lines.join("\n"))
 }
 
-fn parse_days(
-    args: Vec<String>,
-    project_names: &Vec<String>,
-    last: bool,
+fn compact(
+    mut config: config::Config,
+    storage: &dyn Storage,
+    date: Option<NaiveDate>,
     today: NaiveDate,
-) -> Result<(Vec<Day>, Vec<String>), String> {
-    let (start, args) = consume_after_target("start", args);
-    let start = match start {
-        Ok(option) => match option {
-            None => None,
-            Some(text) => match parse_time(&text) {
-                Ok(dt) => Some(dt),
-                Err(e) => return Err(e),
-            },
-        },
-        Err(error) => return Err(error),
-    };
+) -> Result<String, String> {
+    let cutoff = date.unwrap_or_else(|| {
+        today - Duration::try_days(config.settings.compact_keep_days).expect("few days")
+    });
+    let removed_count = config.compact(cutoff);
+    config.save(storage)?;
+    Ok(format!(
+        "Removed {} redundant entries for days before {}. Changes to these days can no longer be undone.",
+        removed_count, cutoff
+    ))
+}
 
-    let (stop, args) = consume_after_target("stop", args);
-    let stop = match stop {
-        Ok(option) => match option {
-            None => None,
-            Some(text) => match parse_time(&text) {
-                Ok(dt) => Some(dt),
-                Err(e) => return Err(e),
-            },
-        },
-        Err(error) => return Err(error),
-    };
+fn archive(
+    mut config: config::Config,
+    storage: &dyn Storage,
+    year: i32,
+    today: NaiveDate,
+) -> Result<String, String> {
+    if year >= today.year() {
+        return Err(format!("Only past years can be archived, not {}", year));
+    }
+    // The archive is written first, so that the days are never only in
+    // memory
+    let moved_count = config.archive(year, storage)?;
+    config.save(storage)?;
+    Ok(format!(
+        "Moved {} entries for {} to {}",
+        moved_count,
+        year,
+        storage.archive_name(year)
+    ))
+}
 
-    let (lunch, args) = consume_after_target("lunch", args);
-    let lunch = match lunch {
-        Ok(option) => match option {
-            None => None,
-            Some(text) => match TimeDelta::from_str(&text) {
-                Ok(dt) => Some(dt),
-                Err(e) => return Err(e),
-            },
-        },
-        Err(error) => return Err(error),
-    };
+fn convert(storage: &dyn Storage, target: &str) -> Result<String, String> {
+    let mut config = config::load_without_compaction(storage)?;
+    // The target is in the format given by its extension
+    config.settings.storage = None;
+    let target = Path::new(target);
+    if fs::metadata(target).is_ok() {
+        return Err(format!("{} already exists", target.to_string_lossy()));
+    }
+    let target_settings_path = target.with_extension("toml");
+    if let Some(settings_path) = storage.settings_path() {
+        if is_same_file(&settings_path, &target_settings_path) {
+            return Err(format!(
+                "{} would share the settings file {} with {}. Use another file name.",
+                target.to_string_lossy(),
+                settings_path.to_string_lossy(),
+                storage.name()
+            ));
+        }
+    }
+    let target_storage = storage::from_backend(
+        storage::backend_from_extension(target),
+        target,
+        &target_settings_path,
+    );
+    match config.convert(storage, target_storage.as_ref()) {
+        Ok(()) => Ok(format!(
+            "Converted {} to {}",
+            storage.name(),
+            target.to_string_lossy()
+        )),
+        Err(message) => Err(message),
+    }
+}
 
-    let (dates, args) = consume_dates(args, today);
-    let (projects, args) = match parse_projects(args, project_names) {
-        Ok((projects, args)) => (projects, args),
-        Err(message) => return Err(message),
+/// Refuse a storage setting that would read the data file in another format
+/// than it is written in.
+fn check_backend(settings: &settings::Settings, storage: &dyn Storage) -> Result<(), String> {
+    let Some(current) = storage.backend() else {
+        return Ok(());
+    };
+    let backend = settings
+        .storage
+        .unwrap_or_else(|| storage::backend_from_extension(Path::new(&storage.name())));
+    if backend == current {
+        return Ok(());
+    }
+    let name = |backend: Backend| match backend {
+        Backend::Json => "json",
+        Backend::Sqlite => "sqlite",
     };
+    Err(format!(
+        "{} is a {} file, which cannot be read as {}. Use 't convert FILE' to copy the data to a file of the other format.",
+        storage.name(),
+        name(current),
+        name(backend)
+    ))
+}
 
-    let dates = if dates.is_empty() {
-        vec![Local::now().date_naive()]
-    } else {
-        dates
+/// Whether the paths are the same file, also if only one of them is
+/// relative. The files do not need to exist.
+fn is_same_file(path: &Path, other: &Path) -> bool {
+    let absolute = |path: &Path| {
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        fs::canonicalize(directory)
+            .map(|directory| directory.join(path.file_name().unwrap_or_default()))
+            .unwrap_or(path.to_path_buf())
     };
-    let days = dates
-        .iter()
-        .map(|date| {
-            if last {
-                *date - Duration::try_weeks(1).expect("hardcoded int")
-            } else {
-                *date
+    absolute(path) == absolute(other)
+}
+
+fn config_command(
+    mut config: config::Config,
+    storage: &dyn Storage,
+    command: ConfigCommand,
+) -> Result<String, String> {
+    match command {
+        ConfigCommand::Get(None) => Ok(config.settings.to_toml()),
+        ConfigCommand::Get(Some(key)) => config.settings.get(&key),
+        ConfigCommand::Set(key, value) => {
+            config.settings.set(&key, &value)?;
+            if key == "storage" {
+                check_backend(&config.settings, storage)?;
             }
-        })
-        .map(|date| Day {
-            date,
-            start,
-            stop,
-            lunch,
-            projects: projects.clone(),
-        })
-        .collect();
-    Ok((days, args))
+            config.save(storage)?;
+            Ok(format!(
+                "{} = {}",
+                key,
+                config.settings.get(&key).expect("just set")
+            ))
+        }
+    }
 }
 
-fn create_html_table(
-    first_date: NaiveDate,
-    last_date: NaiveDate,
-    day_from_date: &HashMap<NaiveDate, Day>,
-    show_weekend: bool,
-    project_names: &Vec<String>,
-    working_time_per_day: &TimeDelta,
-) -> String {
-    match html_table::create_html_table(
+/// Report problems in the data of a period, by default the current month.
+fn check(
+    config: config::Config,
+    period: Option<Period>,
+    today: NaiveDate,
+) -> Result<String, String> {
+    let (first_date, last_date) = check_period(period, today);
+    let day_from_date = config.day_from_date();
+    let findings = check::check(
         first_date,
         last_date,
-        day_from_date,
-        show_weekend,
-        project_names,
-        working_time_per_day,
+        &day_from_date,
+        config.days(),
+        &config.settings.lunch,
+        today,
+    );
+    let text = check::format_findings(&findings, first_date, last_date.min(today));
+    // With rounding on entry, only the rounded times are stored
+    if config.settings.rounding.apply == rounding::Apply::Entry {
+        return Ok(text);
+    }
+    match rounding::format_differences(
+        first_date,
+        last_date.min(today),
+        &day_from_date,
+        &config.settings.rounding,
     ) {
-        Ok(_) => "".to_string(),
-        Err(error) => format!("Error: '{}'", error.to_string()),
+        Some(differences) => Ok(format!("{}\n\n{}", text, differences)),
+        None => Ok(text),
     }
 }
 
-fn undo(path: &Path) -> String {
-    let mut config = match config::load(path) {
-        Ok(config) => config,
-        Err(message) => return message,
-    };
-    let previous_day_from_date = &config.day_from_date();
+/// Check the working time regulations for a period, by default the current
+/// month.
+fn compliance(
+    config: config::Config,
+    period: Option<Period>,
+    today: NaiveDate,
+) -> Result<String, String> {
+    let (first_date, last_date) = check_period(period, today);
+    let last_date = last_date.min(today);
+    let report = compliance::compliance(
+        first_date,
+        last_date,
+        &table::with_implied_lunch(&config.day_from_date(), &config.settings.lunch),
+        config.settings.working_time_per_day,
+        &config.settings.compliance,
+    );
+    Ok(compliance::format_report(&report, first_date, last_date))
+}
 
-    let date = match config.undo() {
-        Ok(date) => date,
-        Err(message) => return message,
-    };
-    config.save(path);
-    let show_weekend = matches!(date.weekday(), Weekday::Sat | Weekday::Sun);
-    table::create_terminal_table(
-        date,
-        date,
-        &config.day_from_date(),
-        previous_day_from_date,
-        show_weekend,
-        &config.project_names,
-        &config.working_time_per_day,
-    )
+/// The dates of the period given to commands that look at more than one
+/// day, where a week is from Monday to Sunday. Without a period, the current
+/// month up to today.
+fn check_period(period: Option<Period>, today: NaiveDate) -> (NaiveDate, NaiveDate) {
+    match period {
+        None => (today.with_day(1).expect("first day of month exists"), today),
+        Some(Period::Week { last }) => {
+            let (date, _) = period_dates(&Period::Week { last }, today, today);
+            let week_days = table::days_in_week_of(date, true);
+            (week_days[0], week_days[6])
+        }
+        Some(period) => period_dates(&period, today, today),
+    }
 }
 
-fn redo(path: &Path) -> String {
-    let mut config = match config::load(path) {
-        Ok(config) => config,
-        Err(message) => return message,
-    };
-    let previous_day_from_date = &config.day_from_date();
+/// Encrypt the data file and the archives in place. `encrypted` is the
+/// storage for the same files as `storage`, with a passphrase.
+pub fn encrypt(storage: &dyn Storage, encrypted: &dyn Storage) -> String {
+    match rewrite(storage, encrypted) {
+        Ok(()) => format!("Encrypted {}", storage.name()),
+        Err(message) => message,
+    }
+}
+
+/// Decrypt the data file and the archives in place, the reverse of `encrypt`.
+pub fn decrypt(encrypted: &dyn Storage, storage: &dyn Storage) -> String {
+    match rewrite(encrypted, storage) {
+        Ok(()) => format!("Decrypted {}", storage.name()),
+        Err(message) => message,
+    }
+}
 
-    let date = match config.redo() {
-        Ok(date) => date,
+/// Load all data, including the archives, before saving it again in the
+/// other format.
+fn rewrite(from: &dyn Storage, to: &dyn Storage) -> Result<(), String> {
+    let config = config::load_without_compaction(from)?;
+    config.convert(from, to)
+}
+
+/// Show the time worked per day in each profile and in total, for checking
+/// the working time limits when working for more than one employer.
+pub fn combined(
+    args: Vec<String>,
+    profiles: &[(String, &dyn Storage)],
+    today: NaiveDate,
+) -> String {
+    let entry = match argparse::parse(&args, &[], today) {
+        Ok(Command::Entry(entry)) => entry,
+        Ok(_) => Entry::default(),
         Err(message) => return message,
     };
-    config.save(path);
-    let show_weekend = matches!(date.weekday(), Weekday::Sat | Weekday::Sun);
-    table::create_terminal_table(
-        date,
-        date,
-        &config.day_from_date(),
-        previous_day_from_date,
-        show_weekend,
-        &config.project_names,
-        &config.working_time_per_day,
-    )
+    let Some(Show {
+        period: Period::Week { last },
+        format: Format::Terminal,
+    }) = entry.show
+    else {
+        return "Usage: t [DATE] show [last] week --all-profiles".to_string();
+    };
+    if entry.has_content() {
+        return "Usage: t [DATE] show [last] week --all-profiles".to_string();
+    }
+    let date = match entry.dates.as_slice() {
+        [] => today,
+        [date] => *date,
+        _ => return "Only one date can be given".to_string(),
+    };
+    let (date, _) = period_dates(&Period::Week { last }, date, today);
+    let mut day_from_dates = Vec::new();
+    for (name, storage) in profiles {
+        match config::load(*storage, today) {
+            Ok(config) => day_from_dates.push((name.clone(), config.rounded_day_from_date())),
+            Err(message) => return message,
+        }
+    }
+    let show_weekend = entry.weekend
+        || day_from_dates.iter().any(|(_, day_from_date)| {
+            table::days_in_week_of(date, true)
+                .iter()
+                .filter(|date| matches!(date.weekday(), Weekday::Sat | Weekday::Sun))
+                .any(|date| day_from_date.contains_key(date))
+        });
+    table::create_combined_table(date, &day_from_dates, show_weekend)
 }
 
-pub fn get_show_weekend(days: &Vec<Day>, args: Vec<String>) -> (bool, Vec<String>) {
-    let (show_weekend, args) = consume_bool("--weekend", args);
-    let is_day_on_weekend = days
-        .iter()
-        .any(|day| matches!(day.date.weekday(), Weekday::Sat | Weekday::Sun));
-    return (show_weekend | is_day_on_weekend, args);
+/// Run one command at the time `now`, which is given for testing.
+pub fn main(args: Vec<String>, storage: &dyn Storage, now: NaiveDateTime) -> String {
+    match run(args, storage, now) {
+        Ok(output) | Err(output) => output,
+    }
 }
 
-pub fn main(args: Vec<String>, path: &Path, today: NaiveDate) -> String {
+/// Run one command, returning its output, or the error message if the
+/// command failed.
+fn run(args: Vec<String>, storage: &dyn Storage, now: NaiveDateTime) -> Result<String, String> {
+    let today = now.date();
     if args.contains(&"--help".to_string()) {
-        return format!(
+        if let Some(help) = argparse::help(&args) {
+            return Ok(help);
+        }
+        return Ok(format!(
             r#"Timereport {}
 
 Usage:
-  t [{{DATE|[last] WEEKDAY|yesterday}}...] [start TIME] [stop TIME] [lunch TIME]
+  t [{{DATE|[last] WEEKDAY|yesterday}}...] [start TIME] [stop TIME] [lunch TIME] [overtime TIME]
+  t [{{DATE|[last] WEEKDAY|yesterday}}...] clear [start|stop|lunch|overtime|project PROJECT]
   t add PROJECT
   t project PROJECT TIME
-  t show [last] {{week|month|MONTH}} [html]
+  t [DATE] show {{[last] week|MONTH [YEAR]}} [html|ics]
+  t undo [STEPS|--date DATE]
+  t redo
+  t history [COUNT]
+  t compact [DATE]
+  t archive YEAR
+  t check [[last] week|MONTH [YEAR]|YEAR]
+  t compliance [[last] week|MONTH [YEAR]|YEAR]
+  t convert FILE
+  t encrypt
+  t decrypt
+  t where
+  t tui
+  t shell
+  t batch < FILE
+  t completions {{bash|zsh|fish}}
+  t config [get [KEY]|set KEY VALUE]
+  t suggest [DATE] [--record]
+  t import [--format {{ics|toggl|clockify|generic-csv|FORMAT}}] FILE [DATE [DATE]] [--add-projects] [--commit]
+
+Run t COMMAND --help, e.g. t start --help, for help on a command.
 
 Options:
-  --weekend  Show Saturday and Sunday
-  --help     Print help
-  --version  Print version
+  --file FILE        Use FILE as data file
+  --profile PROFILE  Use the data and settings of PROFILE
+  --all-profiles     Show the time worked in all profiles, with show [last] week
+  --weekend          Show Saturday and Sunday
+  --help             Print help
+  --version          Print version
 "#,
             env!("CARGO_PKG_VERSION")
-        );
+        ));
     }
     if args.contains(&"--version".to_string()) {
-        return env!("CARGO_PKG_VERSION").to_string();
+        return Ok(env!("CARGO_PKG_VERSION").to_string());
     }
-    let mut config = match config::load(path) {
-        Ok(config) => config,
-        Err(message) => return message,
-    };
-    let (has_undo, args) = consume_bool("undo", args);
-    if has_undo {
-        return undo(path);
-    }
-    let (has_redo, args) = consume_bool("redo", args);
-    if has_redo {
-        return redo(path);
-    }
-    let (project_name, args) = consume_after_target("add", args);
-    let (last, args) = consume_bool("last", args);
-    match project_name {
-        Ok(project_name) => match project_name {
-            Some(project_name) => {
-                config.add_project(project_name);
-                config.save(path);
-            }
-            None => (),
-        },
-        Err(message) => return message,
-    };
-
-    let (show_html, args_after_show_html) = consume_bool("html", args);
-    let (result_for_arg_after_show, args_after_consuming_show) =
-        consume_after_target("show", args_after_show_html);
+    let mut config = config::load(storage, today)?;
+    config.set_command(args.join(" "), now);
+    match argparse::parse(&args, &config.settings.project_names, today)? {
+        Command::Entry(entry) => report(config, storage, entry, now),
+        Command::Add(project_name) => {
+            config.add_project(project_name);
+            report(config, storage, Entry::default(), now)
+        }
+        Command::Undo(undo_command) => undo(storage, undo_command, now),
+        Command::Redo => redo(storage, now),
+        Command::History(count) => history(storage, count, today),
+        Command::Compact(date) => compact(config, storage, date, today),
+        Command::Archive(year) => archive(config, storage, year, today),
+        Command::Check(period) => check(config, period, today),
+        Command::Compliance(period) => compliance(config, period, today),
+        Command::Convert(target) => convert(storage, &target),
+        Command::Tui => {
+            let previous_day_from_date = &config.rounded_day_from_date();
+            let date = tui::run(config, storage, now)?;
+            // Show the last week again, since the UI is gone
+            let config = config::load(storage, today)?;
+            Ok(create_terminal_table_for_dates(
+                &[date],
+                &config,
+                previous_day_from_date,
+                now,
+            ))
+        }
+        Command::Config(command) => config_command(config, storage, command),
+        Command::Suggest { date, record } => suggest(config, storage, date, record, now),
+        Command::Import(import_command) => import(config, storage, import_command, now),
+        Command::Completions(shell) => {
+            completions::completions(shell.as_deref(), &config.settings.project_names)
+        }
+        Command::Standalone(name) => Err(format!("{} must be run from the command line", name)),
+    }
+}
 
-    let arg_after_show = match result_for_arg_after_show {
-        Err(message) => return message,
-        Ok(value_after_show) => value_after_show,
+/// Report the values of the entry for its days, by default today, and show
+/// the week of the first day or the period after `show`.
+fn report(
+    mut config: config::Config,
+    storage: &dyn Storage,
+    entry: Entry,
+    now: NaiveDateTime,
+) -> Result<String, String> {
+    let today = now.date();
+    let dates = if entry.dates.is_empty() {
+        vec![today]
+    } else {
+        entry.dates.clone()
     };
+    let show_weekend = entry.weekend
+        || dates
+            .iter()
+            .any(|date| matches!(date.weekday(), Weekday::Sat | Weekday::Sun));
+    let has_content = entry.has_content();
+    let days: Vec<Day> = dates
+        .iter()
+        .map(|date| Day {
+            date: *date,
+            start: entry.start,
+            stop: entry.stop,
+            lunch: entry.lunch,
+            projects: entry.projects.clone(),
+            overtime: entry.overtime,
+            cleared: entry.cleared.clone(),
+        })
+        .filter(|day| day.has_content())
+        .collect();
 
-    let result = parse_days(
-        args_after_consuming_show,
-        &config.project_names,
-        last,
-        today,
-    );
-    let (days, args_after_parse_days) = match result {
-        Ok((days, args)) => (days, args),
-        Err(message) => return message,
-    };
-    let date_to_display = match days.as_slice() {
-        [] => unreachable!("days cannot be empty"),
-        [first, ..] => first.date,
+    let previous_day_from_date = &config.rounded_day_from_date();
+    // The entered times are not stored when rounding on entry, so say what
+    // was rounded
+    let rounding_changes: Vec<String> = match config.settings.rounding.apply {
+        rounding::Apply::Entry => days
+            .iter()
+            .flat_map(|day| config.settings.rounding.changes(day))
+            .collect(),
+        rounding::Apply::Report => Vec::new(),
     };
-    let (show_weekend, args_after_show_weekend) = get_show_weekend(&days, args_after_parse_days);
+    config.add_days(days);
 
-    let previous_day_from_date = &config.day_from_date();
-    for day in days {
-        if day.has_content() {
-            config.add_day(day);
+    if let Some(show) = entry.show {
+        if has_content {
+            config.save(storage)?;
         }
-    }
-
-    match arg_after_show.as_deref() {
-        None => {}
-        Some(value) => {
-            let (first_date, last_date) = match value {
-                "week" => {
-                    let date = if last {
-                        Local::now().date_naive() - Duration::try_weeks(1).expect("hardcoded int")
-                    } else {
-                        Local::now().date_naive()
-                    };
-                    (date, date)
-                }
-                _ if MONTHS.contains(&value) => {
-                    let arg_month_number = MONTHS
-                        .iter()
-                        .position(|month| month == &value)
-                        .expect("already ensured that the month is in the list")
-                        as u32;
-                    let current_month_number = Local::now().month0();
-                    let year = if arg_month_number > current_month_number {
-                        Local::now().year() - 1
-                    } else {
-                        Local::now().year()
-                    };
-                    let first_date = NaiveDate::from_ymd_opt(year, arg_month_number + 1, 1)
-                        .expect("should be inside range");
-                    let last_date = last_day_of_month(first_date);
-                    (first_date, last_date)
-                }
-                _ => return format!("Unknown show command: {}", value),
-            };
-            if show_html {
-                return create_html_table(
+        let date = entry.dates.first().copied().unwrap_or(today);
+        let (first_date, last_date) = period_dates(&show.period, date, today);
+        return match show.format {
+            Format::Ics => Ok(ics::create_ics(
+                first_date,
+                last_date,
+                &table::with_implied_lunch(&config.rounded_day_from_date(), &config.settings.lunch),
+                &config.settings.project_names,
+                // The absolute path, so that the UIDs do not depend on how
+                // the path to the data file was given
+                &fs::canonicalize(storage.name())
+                    .map_or(storage.name(), |path| path.to_string_lossy().to_string()),
+            )),
+            Format::Html => create_html_table(
+                first_date,
+                last_date,
+                &config.rounded_day_from_date(),
+                show_weekend,
+                &config.settings,
+            ),
+            Format::Terminal => {
+                let table = table::create_terminal_table(
                     first_date,
                     last_date,
-                    &config.day_from_date(),
+                    &config.rounded_day_from_date(),
+                    previous_day_from_date,
                     show_weekend,
-                    &config.project_names,
-                    &config.working_time_per_day,
+                    &config.settings,
+                    now,
                 );
-            } else {
-                return table::create_terminal_table(
+                // The overtime of a month is compensated together
+                if first_date == last_date {
+                    return Ok(table);
+                }
+                match table::create_overtime_summary(
                     first_date,
                     last_date,
-                    &config.day_from_date(),
-                    previous_day_from_date,
-                    show_weekend,
-                    &config.project_names,
-                    &config.working_time_per_day,
-                );
+                    &config.rounded_day_from_date(),
+                    &config.settings,
+                ) {
+                    Some(summary) => Ok(format!("{}\n\n{}", table, summary)),
+                    None => Ok(table),
+                }
             }
-        }
-    };
-    if !args_after_show_weekend.is_empty() {
-        return format!(
-            "Unknown or extra argument '{}'",
-            args_after_show_weekend.join(", ")
-        );
+        };
     }
-    config.save(path);
-    table::create_terminal_table(
-        date_to_display,
-        date_to_display,
-        &config.day_from_date(),
+    config.save(storage)?;
+    let table = table::create_terminal_table(
+        dates[0],
+        dates[0],
+        &config.rounded_day_from_date(),
         previous_day_from_date,
         show_weekend,
-        &config.project_names,
-        &config.working_time_per_day,
-    )
+        &config.settings,
+        now,
+    );
+    if rounding_changes.is_empty() {
+        return Ok(table);
+    }
+    Ok(format!(
+        "{}\nRounded {}",
+        table,
+        rounding_changes.join(", ")
+    ))
+}
+
+/// The first and last date of a period, where a week is given as the same
+/// date twice. The week is the one of `date`, and a month without a year is
+/// the last such month up to today.
+fn period_dates(period: &Period, date: NaiveDate, today: NaiveDate) -> (NaiveDate, NaiveDate) {
+    match *period {
+        Period::Week { last } => {
+            let date = if last {
+                date - Duration::try_weeks(1).expect("hardcoded int")
+            } else {
+                date
+            };
+            (date, date)
+        }
+        Period::Month { month, year } => {
+            let year = match year {
+                Some(year) => year,
+                None if month > today.month() => today.year() - 1,
+                None => today.year(),
+            };
+            let first_date =
+                NaiveDate::from_ymd_opt(year, month, 1).expect("should be inside range");
+            (first_date, last_day_of_month(first_date))
+        }
+        Period::Year(year) => (
+            NaiveDate::from_ymd_opt(year, 1, 1).expect("should be inside range"),
+            NaiveDate::from_ymd_opt(year, 12, 31).expect("should be inside range"),
+        ),
+    }
 }
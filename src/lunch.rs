@@ -0,0 +1,212 @@
+//! The lunch of days without a recorded lunch, from a default lunch per
+//! weekday and statutory break rules.
+
+use chrono::{Datelike, TimeDelta, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::day::Day;
+use crate::timedelta::{
+    deserialize_option_hhmm_timedelta, deserialize_seconds_or_hhmm_timedelta,
+    serialize_hhmm_timedelta, serialize_option_hhmm_timedelta,
+};
+use crate::traits::Parsable;
+
+/// A break that is required when working longer than `after` without one,
+/// e.g. at least 30 minutes after 6 hours.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Break {
+    #[serde(serialize_with = "serialize_hhmm_timedelta")]
+    #[serde(deserialize_with = "deserialize_seconds_or_hhmm_timedelta")]
+    pub after: TimeDelta,
+    #[serde(serialize_with = "serialize_hhmm_timedelta")]
+    #[serde(deserialize_with = "deserialize_seconds_or_hhmm_timedelta")]
+    pub at_least: TimeDelta,
+}
+
+impl Break {
+    /// Parse a list of breaks like "6:00 30m, 9:00 45m".
+    pub fn parse_list(text: &str) -> Result<Vec<Break>, String> {
+        text.split(',')
+            .map(|item| item.trim())
+            .filter(|item| !item.is_empty())
+            .map(
+                |item| match item.split_whitespace().collect::<Vec<_>>()[..] {
+                    [after, at_least] => Ok(Break {
+                        after: TimeDelta::from_str(after)?,
+                        at_least: TimeDelta::from_str(at_least)?,
+                    }),
+                    _ => Err(format!("Expected a time and a break, got '{}'", item)),
+                },
+            )
+            .collect()
+    }
+
+    pub fn format_list(breaks: &[Break]) -> String {
+        breaks
+            .iter()
+            .map(|item| format!("{} {}", item.after.to_hhmm(), item.at_least.to_hhmm()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Stored under `lunch` in the settings file. A recorded lunch always
+/// takes precedence, and the implied lunch is never stored.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[serde(default)]
+pub struct LunchRules {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(serialize_with = "serialize_option_hhmm_timedelta")]
+    #[serde(deserialize_with = "deserialize_option_hhmm_timedelta")]
+    pub monday: Option<TimeDelta>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(serialize_with = "serialize_option_hhmm_timedelta")]
+    #[serde(deserialize_with = "deserialize_option_hhmm_timedelta")]
+    pub tuesday: Option<TimeDelta>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(serialize_with = "serialize_option_hhmm_timedelta")]
+    #[serde(deserialize_with = "deserialize_option_hhmm_timedelta")]
+    pub wednesday: Option<TimeDelta>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(serialize_with = "serialize_option_hhmm_timedelta")]
+    #[serde(deserialize_with = "deserialize_option_hhmm_timedelta")]
+    pub thursday: Option<TimeDelta>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(serialize_with = "serialize_option_hhmm_timedelta")]
+    #[serde(deserialize_with = "deserialize_option_hhmm_timedelta")]
+    pub friday: Option<TimeDelta>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(serialize_with = "serialize_option_hhmm_timedelta")]
+    #[serde(deserialize_with = "deserialize_option_hhmm_timedelta")]
+    pub saturday: Option<TimeDelta>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(serialize_with = "serialize_option_hhmm_timedelta")]
+    #[serde(deserialize_with = "deserialize_option_hhmm_timedelta")]
+    pub sunday: Option<TimeDelta>,
+    pub breaks: Vec<Break>,
+}
+
+impl LunchRules {
+    /// The default lunch of the weekday.
+    pub fn weekday(&self, weekday: Weekday) -> &Option<TimeDelta> {
+        match weekday {
+            Weekday::Mon => &self.monday,
+            Weekday::Tue => &self.tuesday,
+            Weekday::Wed => &self.wednesday,
+            Weekday::Thu => &self.thursday,
+            Weekday::Fri => &self.friday,
+            Weekday::Sat => &self.saturday,
+            Weekday::Sun => &self.sunday,
+        }
+    }
+
+    pub fn weekday_mut(&mut self, weekday: Weekday) -> &mut Option<TimeDelta> {
+        match weekday {
+            Weekday::Mon => &mut self.monday,
+            Weekday::Tue => &mut self.tuesday,
+            Weekday::Wed => &mut self.wednesday,
+            Weekday::Thu => &mut self.thursday,
+            Weekday::Fri => &mut self.friday,
+            Weekday::Sat => &mut self.saturday,
+            Weekday::Sun => &mut self.sunday,
+        }
+    }
+
+    /// The lunch of a day without a recorded lunch: the default lunch of the
+    /// weekday, or the required break if that is longer. None if the day
+    /// has a lunch or no start.
+    ///
+    /// A required break is shortened so that it never brings the worked
+    /// time below the limit, e.g. 6:20 at work with a 30 minute break after
+    /// 6 hours gives a 20 minute break.
+    pub fn implied_lunch(&self, day: &Day) -> Option<TimeDelta> {
+        if day.lunch.is_some() || day.start.is_none() {
+            return None;
+        }
+        let required = match (day.start, day.stop) {
+            (Some(start), Some(stop)) => self
+                .breaks
+                .iter()
+                .filter(|item| stop - start > item.after)
+                .map(|item| item.at_least.min(stop - start - item.after))
+                .max(),
+            _ => None,
+        };
+        match (*self.weekday(day.date.weekday()), required) {
+            (Some(default), Some(required)) => Some(default.max(required)),
+            (default, required) => default.or(required),
+        }
+    }
+
+    /// The day with the implied lunch, if it has no lunch.
+    pub fn fill(&self, day: &Day) -> Day {
+        Day {
+            lunch: day.lunch.or(self.implied_lunch(day)),
+            ..day.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use chrono::{NaiveDate, NaiveTime, TimeDelta};
+    use rstest::rstest;
+
+    use crate::day::Day;
+    use crate::lunch::{Break, LunchRules};
+
+    fn minutes(minutes: i64) -> TimeDelta {
+        TimeDelta::try_minutes(minutes).expect("")
+    }
+
+    /// A day on Tuesday 2025-04-15.
+    fn day(start: &str, stop: Option<&str>, lunch: Option<i64>) -> Day {
+        let time = |text: &str| NaiveTime::parse_from_str(text, "%H:%M").expect("");
+        Day {
+            date: NaiveDate::from_ymd_opt(2025, 4, 15).expect(""),
+            start: Some(time(start)),
+            stop: stop.map(time),
+            lunch: lunch.map(minutes),
+            projects: HashMap::new(),
+            overtime: None,
+            cleared: Vec::new(),
+        }
+    }
+
+    fn rules() -> LunchRules {
+        LunchRules {
+            tuesday: Some(minutes(20)),
+            breaks: Break::parse_list("6:00 30m, 9:00 45m").expect(""),
+            ..LunchRules::default()
+        }
+    }
+
+    #[rstest]
+    #[case::default_lunch(day("08:00", Some("13:00"), None), Some(minutes(20)))]
+    #[case::default_before_stop(day("08:00", None, None), Some(minutes(20)))]
+    #[case::required_break(day("08:00", Some("16:00"), None), Some(minutes(30)))]
+    #[case::longer_required_break(day("08:00", Some("18:00"), None), Some(minutes(45)))]
+    #[case::shortened_break(day("08:00", Some("14:25"), None), Some(minutes(25)))]
+    #[case::recorded_lunch(day("08:00", Some("18:00"), Some(0)), None)]
+    fn implied_lunch(#[case] day: Day, #[case] expected: Option<TimeDelta>) {
+        assert_eq!(rules().implied_lunch(&day), expected);
+    }
+
+    #[rstest]
+    fn no_implied_lunch_without_rules() {
+        assert_eq!(
+            LunchRules::default().implied_lunch(&day("08:00", Some("18:00"), None)),
+            None
+        );
+    }
+
+    #[rstest]
+    #[case("6:00")]
+    #[case("6:00 30m 1")]
+    #[case("six 30m")]
+    fn invalid_breaks(#[case] text: &str) {
+        assert!(Break::parse_list(text).is_err());
+    }
+}
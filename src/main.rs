@@ -1,35 +1,276 @@
 use chrono::Local;
-use std::{env, path::PathBuf};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use timereport::storage::{Backend, EncryptedFile, Storage};
 // Rust note: the binary is a separate crate from the library, so we must import
 // using the full name of the library crate here, not just "crate"
 use timereport;
 
 fn main() {
-    let path = match get_timereport_json_path() {
+    // Skip the first argument since it is just the file
+    let args: Vec<_> = env::args().skip(1).collect();
+    let (file, args) = match consume_option("--file", args) {
+        Ok(result) => result,
+        Err(message) => {
+            println!("Error: {}", message);
+            return;
+        }
+    };
+    let (profile, args) = match consume_option("--profile", args) {
+        Ok(result) => result,
+        Err(message) => {
+            println!("Error: {}", message);
+            return;
+        }
+    };
+    let all_profiles = args.contains(&"--all-profiles".to_string());
+    let args: Vec<_> = args
+        .into_iter()
+        .filter(|arg| arg != "--all-profiles")
+        .collect();
+    let path = match get_timereport_json_path(file.as_deref()) {
         Ok(path) => path,
         Err(message) => {
             println!("Error: {}", message);
             return;
         }
     };
-    // Skip the first argument since it is just the file
-    let args: Vec<_> = env::args().skip(1).collect();
-    let output = timereport::main(args, &path, Local::now().date_naive());
+    let is_default = file.is_none() && env::var("TIMEREPORT_PATH").is_err();
+    let settings_path = get_settings_path(&path, is_default);
+    if all_profiles {
+        if profile.is_some() {
+            println!("Error: --profile and --all-profiles cannot be used together");
+            return;
+        }
+        let mut storages = Vec::new();
+        for (name, path, settings_path) in find_profiles(&path, &settings_path) {
+            match open_storage(&path, &settings_path) {
+                Ok(storage) => storages.push((name, storage)),
+                Err(message) => {
+                    println!("Error: {}", message);
+                    return;
+                }
+            }
+        }
+        let profiles: Vec<_> = storages
+            .iter()
+            .map(|(name, storage)| (name.clone(), storage.as_ref()))
+            .collect();
+        println!(
+            "{}",
+            timereport::combined(args, &profiles, Local::now().date_naive())
+        );
+        return;
+    }
+    let (path, settings_path) = match profile {
+        None => (path, settings_path),
+        Some(profile) => match profile_paths(&path, &settings_path, &profile) {
+            Ok(paths) => paths,
+            Err(message) => {
+                println!("Error: {}", message);
+                return;
+            }
+        },
+    };
+    if args == ["where"] {
+        println!("{}", where_text(&path, &settings_path));
+        return;
+    }
+    // On stderr, so that the output can still be redirected to a file
+    for message in first_run_setup(&path, &settings_path) {
+        eprintln!("{message}");
+    }
+    if args == ["encrypt"] || args == ["decrypt"] {
+        let output = match encrypt_or_decrypt(&args[0], &path, &settings_path) {
+            Ok(output) => output,
+            Err(message) => format!("Error: {}", message),
+        };
+        // The shell history would show the data in plain text
+        if timereport::storage::is_encrypted(&path) {
+            let _ = fs::remove_file(path.with_extension("history"));
+        }
+        println!("{output}");
+        return;
+    }
+    // Completing a command must not ask for the passphrase
+    if args == ["completions", "projects"]
+        && timereport::storage::is_encrypted(&path)
+        && env::var("TIMEREPORT_PASSPHRASE").is_err()
+    {
+        return;
+    }
+    let storage = match open_storage(&path, &settings_path) {
+        Ok(storage) => storage,
+        Err(message) => {
+            println!("Error: {}", message);
+            return;
+        }
+    };
+    let output = if args == ["shell"] {
+        // The history is kept next to the data file, for each profile. It
+        // is not encrypted, so there is none for an encrypted data file.
+        let history_path = if timereport::storage::is_encrypted(&path) {
+            None
+        } else {
+            Some(path.with_extension("history"))
+        };
+        timereport::shell(storage.as_ref(), history_path.as_deref(), &|| {
+            Local::now().naive_local()
+        })
+    } else if args == ["batch"] {
+        match io::read_to_string(io::stdin()) {
+            Ok(input) => timereport::batch(&input, storage.as_ref(), Local::now().naive_local()),
+            Err(error) => format!("Error: could not read the commands: {}", error),
+        }
+    } else {
+        timereport::main(args, storage.as_ref(), Local::now().naive_local())
+    };
     println!("{output}")
 }
 
-fn get_timereport_json_path() -> Result<PathBuf, String> {
-    let path_string = if let Ok(timereport_path) = env::var("TIMEREPORT_PATH") {
+/// The storage for the data file, asking for the passphrase if the file is
+/// encrypted.
+fn open_storage(path: &Path, settings_path: &Path) -> Result<Box<dyn Storage>, String> {
+    if timereport::storage::is_encrypted(path) {
+        let passphrase = get_passphrase(path, false)?;
+        Ok(Box::new(EncryptedFile::new(
+            path,
+            settings_path,
+            &passphrase,
+        )))
+    } else {
+        Ok(timereport::storage::from_paths(path, settings_path))
+    }
+}
+
+/// The passphrase from TIMEREPORT_PASSPHRASE, or else asked for. A new
+/// passphrase is asked for twice to avoid typos.
+fn get_passphrase(path: &Path, is_new: bool) -> Result<String, String> {
+    if let Ok(passphrase) = env::var("TIMEREPORT_PASSPHRASE") {
+        if passphrase.is_empty() {
+            return Err("TIMEREPORT_PASSPHRASE is empty".to_string());
+        }
+        return Ok(passphrase);
+    }
+    let prompt = |text: &str| {
+        rpassword::prompt_password(text)
+            .map_err(|error| format!("Could not read the passphrase: {}", error))
+    };
+    let passphrase = prompt(&format!("Passphrase for {}: ", path.to_string_lossy()))?;
+    if passphrase.is_empty() {
+        return Err("The passphrase must not be empty".to_string());
+    }
+    if is_new && prompt("Repeat the passphrase: ")? != passphrase {
+        return Err("The passphrases do not match".to_string());
+    }
+    Ok(passphrase)
+}
+
+fn encrypt_or_decrypt(command: &str, path: &Path, settings_path: &Path) -> Result<String, String> {
+    let is_encrypted = timereport::storage::is_encrypted(path);
+    let plain = timereport::storage::from_paths(path, settings_path);
+    if command == "encrypt" {
+        if is_encrypted {
+            return Err(format!("{} is already encrypted", path.to_string_lossy()));
+        }
+        if timereport::storage::backend(path, settings_path) == Backend::Sqlite {
+            return Err("Only json data files can be encrypted".to_string());
+        }
+        let encrypted = EncryptedFile::new(path, settings_path, &get_passphrase(path, true)?);
+        Ok(timereport::encrypt(plain.as_ref(), &encrypted))
+    } else {
+        if !is_encrypted {
+            return Err(format!("{} is not encrypted", path.to_string_lossy()));
+        }
+        let encrypted = EncryptedFile::new(path, settings_path, &get_passphrase(path, false)?);
+        Ok(timereport::decrypt(&encrypted, plain.as_ref()))
+    }
+}
+
+/// Remove an option and its value, e.g. `--file PATH`, from the arguments and
+/// return the value.
+fn consume_option(
+    option: &str,
+    args: Vec<String>,
+) -> Result<(Option<String>, Vec<String>), String> {
+    let Some(index) = args.iter().position(|arg| arg == option) else {
+        return Ok((None, args));
+    };
+    let mut args = args;
+    if index + 1 >= args.len() {
+        return Err(format!("{} must be followed by a value", option));
+    }
+    let value = args.remove(index + 1);
+    args.remove(index);
+    Ok((Some(value), args))
+}
+
+/// The data file and settings file of a profile, in a directory of their own
+/// under `profiles` next to the files of the default profile.
+fn profile_paths(
+    path: &Path,
+    settings_path: &Path,
+    profile: &str,
+) -> Result<(PathBuf, PathBuf), String> {
+    let is_valid = !profile.is_empty()
+        && profile
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+    if !is_valid {
+        return Err(format!(
+            "Invalid profile name '{}'. Use letters, digits, - and _",
+            profile
+        ));
+    }
+    let in_profile = |path: &Path| {
+        let parent = path.parent().unwrap_or(Path::new(""));
+        parent
+            .join("profiles")
+            .join(profile)
+            .join(path.file_name().unwrap_or_default())
+    };
+    Ok((in_profile(path), in_profile(settings_path)))
+}
+
+/// The default profile and all profiles with a data file, by name.
+fn find_profiles(path: &Path, settings_path: &Path) -> Vec<(String, PathBuf, PathBuf)> {
+    let mut profiles = vec![(
+        "default".to_string(),
+        path.to_path_buf(),
+        settings_path.to_path_buf(),
+    )];
+    let profiles_dir = path.parent().unwrap_or(Path::new("")).join("profiles");
+    let mut names: Vec<String> = match fs::read_dir(profiles_dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    names.sort();
+    for name in names {
+        if let Ok((path, settings_path)) = profile_paths(path, settings_path, &name) {
+            if path.exists() {
+                profiles.push((name, path, settings_path));
+            }
+        }
+    }
+    profiles
+}
+
+/// The data file given by --file, TIMEREPORT_PATH or the platform default.
+///
+/// The parent directory of a file given explicitly must exist, while the
+/// directory of the default file is created by the first run setup.
+fn get_timereport_json_path(file: Option<&str>) -> Result<PathBuf, String> {
+    let path_string = if let Some(file) = file {
+        file.to_string()
+    } else if let Ok(timereport_path) = env::var("TIMEREPORT_PATH") {
         timereport_path
     } else {
-        let user = if let Ok(user) = env::var("USER") {
-            user
-        } else if let Ok(username) = env::var("USERNAME") {
-            username
-        } else {
-            return Err("TIMEREPORT_PATH, USER or USERNAME must be defined".to_string());
-        };
-        format!("C:\\Users\\{}\\Dropbox\\timereport.json", user)
+        return default_timereport_json_path();
     };
     let path = PathBuf::from(&path_string);
     let parent = match path.parent() {
@@ -43,6 +284,100 @@ fn get_timereport_json_path() -> Result<PathBuf, String> {
     }
 }
 
+#[cfg(windows)]
+fn default_timereport_json_path() -> Result<PathBuf, String> {
+    let user = if let Ok(user) = env::var("USER") {
+        user
+    } else if let Ok(username) = env::var("USERNAME") {
+        username
+    } else {
+        return Err("TIMEREPORT_PATH, USER or USERNAME must be defined".to_string());
+    };
+    let path = PathBuf::from(format!("C:\\Users\\{}\\Dropbox\\timereport.json", user));
+    let parent = path.parent().expect("hardcoded path has a parent");
+    if parent.exists() {
+        Ok(path)
+    } else {
+        Err(format!("'{}' is not a directory. timereport.json cannot be created. Try setting the TIMEREPORT_PATH environment variable to a valid path.", parent.to_string_lossy()))
+    }
+}
+
+#[cfg(not(windows))]
+fn default_timereport_json_path() -> Result<PathBuf, String> {
+    match xdg_dir("XDG_DATA_HOME", ".local/share") {
+        Some(dir) => Ok(dir.join("timereport").join("timereport.json")),
+        None => Err("TIMEREPORT_PATH, XDG_DATA_HOME or HOME must be defined".to_string()),
+    }
+}
+
+/// The directory for settings, or None if it cannot be determined.
+#[cfg(windows)]
+fn get_settings_dir() -> Option<PathBuf> {
+    env::var("APPDATA")
+        .ok()
+        .map(|app_data| PathBuf::from(app_data).join("timereport"))
+}
+
+/// The directory for settings, or None if it cannot be determined.
+#[cfg(not(windows))]
+fn get_settings_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", ".config").map(|dir| dir.join("timereport"))
+}
+
+/// The settings file. It is in the settings directory when the data file is
+/// in the default location, and next to the data file otherwise.
+fn get_settings_path(path: &Path, is_default: bool) -> PathBuf {
+    match get_settings_dir() {
+        Some(settings_dir) if is_default => settings_dir.join("timereport.toml"),
+        _ => path.with_extension("toml"),
+    }
+}
+
+/// The base directory from an XDG variable, or the fallback in the home
+/// directory if the variable is not set. Relative paths are ignored, as the
+/// XDG specification requires.
+#[cfg(not(windows))]
+fn xdg_dir(variable: &str, fallback: &str) -> Option<PathBuf> {
+    match env::var(variable) {
+        Ok(dir) if PathBuf::from(&dir).is_absolute() => Some(PathBuf::from(dir)),
+        _ => match env::var("HOME") {
+            Ok(home) if !home.is_empty() => Some(PathBuf::from(home).join(fallback)),
+            _ => None,
+        },
+    }
+}
+
+fn where_text(path: &Path, settings_path: &Path) -> String {
+    format!(
+        "Data file: {}\nSettings file: {}",
+        path.to_string_lossy(),
+        settings_path.to_string_lossy()
+    )
+}
+
+/// Create the directories of the data file and the settings file if they do
+/// not exist, returning a message for each created directory.
+fn first_run_setup(path: &Path, settings_path: &Path) -> Vec<String> {
+    let mut messages = Vec::new();
+    for dir in [path.parent(), settings_path.parent()]
+        .into_iter()
+        .flatten()
+    {
+        if dir.as_os_str().is_empty() || dir.exists() {
+            continue;
+        }
+        match fs::create_dir_all(dir) {
+            Ok(()) => messages.push(format!("Created {}", dir.to_string_lossy())),
+            Err(error) => messages.push(format!(
+                "Error: could not create {}: {}",
+                dir.to_string_lossy(),
+                error
+            )),
+        }
+    }
+    messages
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -50,7 +385,15 @@ mod test {
 
     #[rstest]
     fn call_main_function_for_code_coverage() {
-        main();
+        let dir = tempfile::tempdir().expect("");
+        temp_env::with_vars(
+            [
+                ("TIMEREPORT_PATH", None::<&Path>),
+                ("XDG_DATA_HOME", Some(dir.path())),
+                ("XDG_CONFIG_HOME", Some(dir.path())),
+            ],
+            main,
+        );
     }
 
     fn current_test_file_path() -> PathBuf {
@@ -76,37 +419,57 @@ mod test {
         temp_env::with_var(
             "TIMEREPORT_PATH",
             Some(timereport_json_path.clone()),
-            || assert!(get_timereport_json_path() == Ok(timereport_json_path)),
+            || assert!(get_timereport_json_path(None) == Ok(timereport_json_path)),
         );
     }
 
+    #[rstest]
+    fn passphrase_from_environment() {
+        temp_env::with_var("TIMEREPORT_PASSPHRASE", Some("secret"), || {
+            assert_eq!(
+                get_passphrase(Path::new("timereport.json"), true),
+                Ok("secret".to_string())
+            )
+        });
+    }
+
+    #[rstest]
+    fn empty_passphrase_from_environment() {
+        temp_env::with_var("TIMEREPORT_PASSPHRASE", Some(""), || {
+            assert!(get_passphrase(Path::new("timereport.json"), false).is_err())
+        });
+    }
+
     #[rstest]
     fn timereport_path_is_illegal() {
         temp_env::with_vars([("TIMEREPORT_PATH", Some(""))], || {
-            assert!(get_timereport_json_path()
+            assert!(get_timereport_json_path(None)
                 .expect_err("")
                 .contains("Error when trying to find the parent directory"));
         });
     }
 
+    #[cfg(windows)]
     #[rstest]
     fn user_is_set() {
         temp_env::with_vars([("USERNAME", None), ("USER", Some("MYUSER"))], || {
-            assert!(get_timereport_json_path()
+            assert!(get_timereport_json_path(None)
                 .expect_err("")
                 .contains("'C:\\Users\\MYUSER\\Dropbox' is not a directory"));
         });
     }
 
+    #[cfg(windows)]
     #[rstest]
     fn username_is_set() {
         temp_env::with_vars([("USERNAME", Some("MYUSER")), ("USER", None)], || {
-            assert!(get_timereport_json_path()
+            assert!(get_timereport_json_path(None)
                 .expect_err("")
                 .contains("'C:\\Users\\MYUSER\\Dropbox' is not a directory"));
         });
     }
 
+    #[cfg(windows)]
     #[rstest]
     fn nothing_is_set() {
         temp_env::with_vars(
@@ -116,10 +479,190 @@ mod test {
                 ("USER", None),
             ],
             || {
-                assert!(get_timereport_json_path()
+                assert!(get_timereport_json_path(None)
+                    .expect_err("")
+                    .contains("must be defined"));
+            },
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[rstest]
+    fn nothing_is_set() {
+        temp_env::with_vars(
+            [
+                ("TIMEREPORT_PATH", None::<String>),
+                ("XDG_DATA_HOME", None),
+                ("HOME", None),
+            ],
+            || {
+                assert!(get_timereport_json_path(None)
                     .expect_err("")
                     .contains("must be defined"));
             },
         );
     }
+
+    #[cfg(not(windows))]
+    #[rstest]
+    fn xdg_data_home_is_used() {
+        temp_env::with_vars(
+            [
+                ("TIMEREPORT_PATH", None::<&str>),
+                ("XDG_DATA_HOME", Some("/xdg/data")),
+                ("HOME", Some("/home/me")),
+            ],
+            || {
+                assert_eq!(
+                    get_timereport_json_path(None),
+                    Ok(PathBuf::from("/xdg/data/timereport/timereport.json"))
+                );
+            },
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[rstest]
+    #[case(None)]
+    #[case(Some("relative/path"))]
+    fn home_is_used_without_xdg_data_home(#[case] xdg_data_home: Option<&str>) {
+        temp_env::with_vars(
+            [
+                ("TIMEREPORT_PATH", None),
+                ("XDG_DATA_HOME", xdg_data_home),
+                ("HOME", Some("/home/me")),
+            ],
+            || {
+                assert_eq!(
+                    get_timereport_json_path(None),
+                    Ok(PathBuf::from(
+                        "/home/me/.local/share/timereport/timereport.json"
+                    ))
+                );
+            },
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[rstest]
+    fn xdg_config_home_is_used_for_settings() {
+        temp_env::with_vars([("XDG_CONFIG_HOME", Some("/xdg/config"))], || {
+            assert_eq!(
+                get_settings_path(Path::new("/data/timereport.json"), true),
+                PathBuf::from("/xdg/config/timereport/timereport.toml")
+            );
+        });
+    }
+
+    #[rstest]
+    fn settings_are_next_to_data_file_given_explicitly() {
+        assert_eq!(
+            get_settings_path(Path::new("/data/timereport.json"), false),
+            PathBuf::from("/data/timereport.toml")
+        );
+    }
+
+    #[rstest]
+    fn file_flag_overrides_timereport_path() {
+        let dir = tempfile::tempdir().expect("");
+        let file = dir.path().join("other.json");
+        let args = vec![
+            "show".to_string(),
+            "--file".to_string(),
+            file.to_string_lossy().to_string(),
+            "week".to_string(),
+        ];
+
+        let (file_arg, args) = consume_option("--file", args).expect("");
+
+        assert_eq!(args, vec!["show", "week"]);
+        temp_env::with_var(
+            "TIMEREPORT_PATH",
+            Some("/nonexistent/timereport.json"),
+            || {
+                assert_eq!(get_timereport_json_path(file_arg.as_deref()), Ok(file));
+            },
+        );
+    }
+
+    #[rstest]
+    fn file_flag_without_path() {
+        assert_eq!(
+            consume_option("--file", vec!["--file".to_string()]),
+            Err("--file must be followed by a value".to_string())
+        );
+    }
+
+    #[rstest]
+    fn first_run_setup_creates_directories() {
+        let dir = tempfile::tempdir().expect("");
+        let path = dir.path().join("data").join("timereport.json");
+        let settings_path = dir.path().join("config").join("timereport.toml");
+
+        let messages = first_run_setup(&path, &settings_path);
+
+        assert_eq!(messages.len(), 2);
+        assert!(path.parent().expect("").is_dir());
+        assert!(settings_path.parent().expect("").is_dir());
+        assert!(first_run_setup(&path, &settings_path).is_empty());
+    }
+
+    #[rstest]
+    fn profile_has_own_directory() {
+        assert_eq!(
+            profile_paths(
+                Path::new("/data/timereport.json"),
+                Path::new("/config/timereport.toml"),
+                "acme"
+            ),
+            Ok((
+                PathBuf::from("/data/profiles/acme/timereport.json"),
+                PathBuf::from("/config/profiles/acme/timereport.toml")
+            ))
+        );
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("../acme")]
+    #[case("a b")]
+    fn invalid_profile_name(#[case] profile: &str) {
+        assert!(profile_paths(
+            Path::new("/data/timereport.json"),
+            Path::new("/data/timereport.toml"),
+            profile
+        )
+        .is_err());
+    }
+
+    #[rstest]
+    fn profiles_with_data_are_found() {
+        let dir = tempfile::tempdir().expect("");
+        let path = dir.path().join("timereport.json");
+        let settings_path = dir.path().join("timereport.toml");
+        for profile in ["beta", "acme"] {
+            let (profile_path, _) = profile_paths(&path, &settings_path, profile).expect("");
+            fs::create_dir_all(profile_path.parent().expect("")).expect("");
+            fs::write(profile_path, "").expect("");
+        }
+        fs::create_dir_all(dir.path().join("profiles").join("empty")).expect("");
+
+        let names: Vec<String> = find_profiles(&path, &settings_path)
+            .into_iter()
+            .map(|(name, _, _)| name)
+            .collect();
+
+        assert_eq!(names, vec!["default", "acme", "beta"]);
+    }
+
+    #[rstest]
+    fn where_prints_paths() {
+        assert_eq!(
+            where_text(
+                Path::new("/data/timereport.json"),
+                Path::new("/config/timereport/timereport.toml")
+            ),
+            "Data file: /data/timereport.json\nSettings file: /config/timereport/timereport.toml"
+        );
+    }
 }
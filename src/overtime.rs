@@ -0,0 +1,216 @@
+//! Overtime separated from flex, in categories with different compensation.
+
+use chrono::{Datelike, NaiveDate, NaiveTime, TimeDelta, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::compliance::shift;
+use crate::day::Day;
+
+/// The kinds of overtime, in the order they are shown.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Category {
+    /// Time marked as overtime with `t overtime TIME`
+    Marked,
+    Evening,
+    Weekend,
+    Holiday,
+}
+
+pub const CATEGORIES: [Category; 4] = [
+    Category::Marked,
+    Category::Evening,
+    Category::Weekend,
+    Category::Holiday,
+];
+
+impl Category {
+    pub fn name(&self) -> &str {
+        match self {
+            Category::Marked => "marked",
+            Category::Evening => "evening",
+            Category::Weekend => "weekend",
+            Category::Holiday => "holiday",
+        }
+    }
+}
+
+/// The rules that derive overtime from the recorded times, and the
+/// compensation for each category.
+///
+/// Stored under `overtime` in the settings file. Without rules, only the
+/// time marked with `t overtime TIME` is overtime.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Rules {
+    /// Time worked after this time of day is evening overtime
+    pub evening_after: Option<NaiveTime>,
+    /// All time worked on Saturdays and Sundays is weekend overtime
+    pub weekends: bool,
+    /// All time worked on these dates is holiday overtime
+    pub holidays: Vec<NaiveDate>,
+    pub marked_multiplier: f64,
+    pub evening_multiplier: f64,
+    pub weekend_multiplier: f64,
+    pub holiday_multiplier: f64,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            evening_after: None,
+            weekends: false,
+            holidays: Vec::new(),
+            marked_multiplier: 1.5,
+            evening_multiplier: 1.5,
+            weekend_multiplier: 2.0,
+            holiday_multiplier: 2.0,
+        }
+    }
+}
+
+impl Rules {
+    pub fn multiplier(&self, category: Category) -> f64 {
+        match category {
+            Category::Marked => self.marked_multiplier,
+            Category::Evening => self.evening_multiplier,
+            Category::Weekend => self.weekend_multiplier,
+            Category::Holiday => self.holiday_multiplier,
+        }
+    }
+
+    /// The overtime of a day. Marked overtime replaces the overtime from the
+    /// rules for that day. On holidays and weekends, all worked time is
+    /// overtime.
+    pub fn classify(&self, day: &Day) -> Option<(Category, TimeDelta)> {
+        if let Some(overtime) = day.overtime {
+            return Some((Category::Marked, overtime));
+        }
+        let (start, stop) = shift(day)?;
+        let worked = || stop - start - day.lunch.unwrap_or(TimeDelta::zero());
+        if self.holidays.contains(&day.date) {
+            return Some((Category::Holiday, worked()));
+        }
+        if self.weekends && matches!(day.date.weekday(), Weekday::Sat | Weekday::Sun) {
+            return Some((Category::Weekend, worked()));
+        }
+        let evening_after = day.date.and_time(self.evening_after?);
+        if stop <= evening_after {
+            return None;
+        }
+        Some((Category::Evening, stop - start.max(evening_after)))
+    }
+
+    /// Whether all the time of the day is overtime, so that there is no flex.
+    pub fn is_overtime_day(&self, day: &Day) -> bool {
+        matches!(
+            self.classify(day),
+            Some((Category::Weekend | Category::Holiday, _))
+        )
+    }
+
+    /// The overtime of the days per category, and in total weighted by the
+    /// multipliers.
+    pub fn totals<'a>(
+        &self,
+        days: impl Iterator<Item = &'a Day>,
+    ) -> (Vec<(Category, TimeDelta)>, TimeDelta) {
+        let mut totals: Vec<(Category, TimeDelta)> = CATEGORIES
+            .iter()
+            .map(|category| (*category, TimeDelta::zero()))
+            .collect();
+        for (category, timedelta) in days.filter_map(|day| self.classify(day)) {
+            if let Some((_, total)) = totals.iter_mut().find(|(other, _)| *other == category) {
+                *total += timedelta;
+            }
+        }
+        let weighted_seconds: f64 = totals
+            .iter()
+            .map(|(category, total)| total.num_seconds() as f64 * self.multiplier(*category))
+            .sum();
+        let weighted =
+            TimeDelta::try_seconds(weighted_seconds.round() as i64).expect("overtime is in range");
+        (totals, weighted)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use chrono::{NaiveDate, NaiveTime, TimeDelta};
+    use rstest::rstest;
+
+    use crate::day::Day;
+    use crate::overtime::{Category, Rules};
+
+    fn day(date: &str, start: &str, stop: &str, overtime: Option<i64>) -> Day {
+        let time = |text: &str| NaiveTime::parse_from_str(text, "%H:%M").expect("");
+        Day {
+            date: NaiveDate::parse_from_str(date, "%Y-%m-%d").expect(""),
+            start: Some(time(start)),
+            stop: Some(time(stop)),
+            lunch: Some(TimeDelta::try_minutes(30).expect("")),
+            projects: HashMap::new(),
+            overtime: overtime.map(|hours| TimeDelta::try_hours(hours).expect("")),
+            cleared: Vec::new(),
+        }
+    }
+
+    fn hours(hours: i64) -> TimeDelta {
+        TimeDelta::try_hours(hours).expect("")
+    }
+
+    fn rules() -> Rules {
+        Rules {
+            evening_after: NaiveTime::from_hms_opt(18, 0, 0),
+            weekends: true,
+            holidays: vec![NaiveDate::from_ymd_opt(2025, 4, 18).expect("")],
+            ..Rules::default()
+        }
+    }
+
+    #[rstest]
+    // Tuesday
+    #[case(day("2025-04-15", "08:00", "16:00", None), None)]
+    #[case(day("2025-04-15", "08:00", "20:00", None), Some((Category::Evening, hours(2))))]
+    #[case(day("2025-04-15", "19:00", "21:00", None), Some((Category::Evening, hours(2))))]
+    #[case(day("2025-04-15", "16:00", "01:00", None), Some((Category::Evening, hours(7))))]
+    #[case(day("2025-04-15", "08:00", "16:00", Some(1)), Some((Category::Marked, hours(1))))]
+    // Good Friday
+    #[case(day("2025-04-18", "08:30", "12:00", None), Some((Category::Holiday, hours(3))))]
+    // Saturday
+    #[case(day("2025-04-19", "08:30", "20:00", None), Some((Category::Weekend, hours(11))))]
+    fn classify(#[case] day: Day, #[case] expected: Option<(Category, TimeDelta)>) {
+        assert_eq!(rules().classify(&day), expected);
+    }
+
+    #[rstest]
+    fn no_overtime_without_rules() {
+        assert_eq!(
+            Rules::default().classify(&day("2025-04-19", "08:00", "20:00", None)),
+            None
+        );
+    }
+
+    #[rstest]
+    fn weighted_total() {
+        let days = [
+            day("2025-04-15", "08:00", "20:00", None),
+            day("2025-04-19", "08:30", "12:00", None),
+        ];
+
+        let (totals, weighted) = rules().totals(days.iter());
+
+        assert_eq!(
+            totals,
+            vec![
+                (Category::Marked, hours(0)),
+                (Category::Evening, hours(2)),
+                (Category::Weekend, hours(3)),
+                (Category::Holiday, hours(0)),
+            ]
+        );
+        // 2 * 1.5 + 3 * 2
+        assert_eq!(weighted, hours(9));
+    }
+}
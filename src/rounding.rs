@@ -0,0 +1,348 @@
+//! Rounding the recorded times to whole blocks of minutes, e.g. for an
+//! employer that reports in 15-minute blocks.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use chrono::{NaiveDate, NaiveTime, TimeDelta, Timelike};
+use serde::{Deserialize, Serialize};
+
+use crate::day::Day;
+use crate::table::worked_time;
+use crate::traits::Parsable;
+
+/// The block sizes that can be rounded to, in minutes.
+pub const MINUTES: [i64; 5] = [5, 6, 10, 15, 30];
+
+const SECONDS_PER_DAY: i64 = 24 * 3600;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Nearest,
+    Up,
+    Down,
+}
+
+/// How one field is rounded, written as e.g. `nearest 15` or `up 30`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+pub struct Rule {
+    pub direction: Direction,
+    pub minutes: i64,
+}
+
+impl Rule {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let usage = || {
+            format!(
+                "Unknown rounding '{}'. Use nearest, up or down followed by one of {}",
+                text,
+                MINUTES.map(|minutes| minutes.to_string()).join(", ")
+            )
+        };
+        let (direction, minutes) = text.trim().split_once(' ').ok_or_else(usage)?;
+        let direction = match direction {
+            "nearest" => Direction::Nearest,
+            "up" => Direction::Up,
+            "down" => Direction::Down,
+            _ => return Err(usage()),
+        };
+        match minutes.trim().trim_end_matches('m').parse::<i64>() {
+            Ok(minutes) if MINUTES.contains(&minutes) => Ok(Self { direction, minutes }),
+            _ => Err(usage()),
+        }
+    }
+
+    fn round_seconds(&self, seconds: i64) -> i64 {
+        let block = self.minutes * 60;
+        let rounded_down = seconds.div_euclid(block) * block;
+        match self.direction {
+            Direction::Down => rounded_down,
+            Direction::Up if rounded_down == seconds => seconds,
+            Direction::Up => rounded_down + block,
+            // Halfway rounds up, like 8:07:30 to 8:15
+            Direction::Nearest if seconds - rounded_down >= block - seconds + rounded_down => {
+                rounded_down + block
+            }
+            Direction::Nearest => rounded_down,
+        }
+    }
+
+    /// Round a time of day. Times that would round to midnight at the end of
+    /// the day are rounded down instead, to stay on the same day.
+    pub fn round_time(&self, time: NaiveTime) -> NaiveTime {
+        let seconds = time.num_seconds_from_midnight() as i64;
+        let rounded = match self.round_seconds(seconds) {
+            rounded if rounded >= SECONDS_PER_DAY => Rule {
+                direction: Direction::Down,
+                ..*self
+            }
+            .round_seconds(seconds),
+            rounded => rounded,
+        };
+        NaiveTime::from_num_seconds_from_midnight_opt(rounded as u32, 0)
+            .expect("rounded within the day")
+    }
+
+    pub fn round_timedelta(&self, timedelta: TimeDelta) -> TimeDelta {
+        TimeDelta::try_seconds(self.round_seconds(timedelta.num_seconds()))
+            .expect("rounding changes the time by less than an hour")
+    }
+}
+
+impl TryFrom<String> for Rule {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        Self::parse(&text)
+    }
+}
+
+impl From<Rule> for String {
+    fn from(rule: Rule) -> Self {
+        rule.to_string()
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let direction = match self.direction {
+            Direction::Nearest => "nearest",
+            Direction::Up => "up",
+            Direction::Down => "down",
+        };
+        write!(f, "{} {}", direction, self.minutes)
+    }
+}
+
+/// When the rounding is applied.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Apply {
+    /// The rounded values are stored
+    Entry,
+    /// The exact values are stored, and rounded in the tables and exports
+    #[default]
+    Report,
+}
+
+/// The rounding of each field. Fields without a rule are not rounded.
+///
+/// Stored under `rounding` in the settings file.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[serde(default)]
+pub struct Rounding {
+    pub apply: Apply,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<Rule>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Rule>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lunch: Option<Rule>,
+    /// The rule for the time on all projects
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project: Option<Rule>,
+}
+
+impl Rounding {
+    pub fn round(&self, day: &Day) -> Day {
+        let round_time = |rule: Option<Rule>, time: Option<NaiveTime>| match (rule, time) {
+            (Some(rule), Some(time)) => Some(rule.round_time(time)),
+            _ => time,
+        };
+        Day {
+            start: round_time(self.start, day.start),
+            stop: round_time(self.stop, day.stop),
+            lunch: match (self.lunch, day.lunch) {
+                (Some(rule), Some(lunch)) => Some(rule.round_timedelta(lunch)),
+                _ => day.lunch,
+            },
+            projects: match self.project {
+                Some(rule) => day
+                    .projects
+                    .iter()
+                    .map(|(name, timedelta)| (name.clone(), rule.round_timedelta(*timedelta)))
+                    .collect(),
+                None => day.projects.clone(),
+            },
+            ..day.clone()
+        }
+    }
+
+    /// The fields changed by rounding the day, e.g. "start 08:03 to 08:00".
+    pub fn changes(&self, day: &Day) -> Vec<String> {
+        let rounded = self.round(day);
+        let time = |time: Option<NaiveTime>| time.map(|time| time.format("%H:%M").to_string());
+        let mut changes = Vec::new();
+        for (name, before, after) in [
+            ("start", time(day.start), time(rounded.start)),
+            ("stop", time(day.stop), time(rounded.stop)),
+            (
+                "lunch",
+                day.lunch.map(|lunch| lunch.to_hhmm()),
+                rounded.lunch.map(|lunch| lunch.to_hhmm()),
+            ),
+        ] {
+            if let (Some(before), Some(after)) = (before, after) {
+                if before != after {
+                    changes.push(format!("{} {} to {}", name, before, after));
+                }
+            }
+        }
+        let mut project_names: Vec<&String> = day.projects.keys().collect();
+        project_names.sort();
+        for name in project_names {
+            let (before, after) = (day.projects[name], rounded.projects[name]);
+            if before != after {
+                changes.push(format!(
+                    "{} {} to {}",
+                    name,
+                    before.to_hhmm(),
+                    after.to_hhmm()
+                ));
+            }
+        }
+        changes
+    }
+}
+
+/// The days from `first_date` to `last_date` that change when rounded, with
+/// the changes and the difference in worked time, as text.
+pub fn format_differences(
+    first_date: NaiveDate,
+    last_date: NaiveDate,
+    day_from_date: &HashMap<NaiveDate, Day>,
+    rounding: &Rounding,
+) -> Option<String> {
+    let mut lines = Vec::new();
+    let mut total = TimeDelta::zero();
+    for date in first_date.iter_days().take_while(|date| *date <= last_date) {
+        let Some(day) = day_from_date.get(&date) else {
+            continue;
+        };
+        let mut changes = rounding.changes(day);
+        if changes.is_empty() {
+            continue;
+        }
+        if let (Some(before), Some(after)) = (worked_time(day), worked_time(&rounding.round(day))) {
+            changes.push(format!("{} worked", signed_hhmm(after - before)));
+            total += after - before;
+        }
+        lines.push(format!(
+            "  {} {}: {}",
+            date,
+            date.format("%a"),
+            changes.join(", ")
+        ));
+    }
+    if lines.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "Rounding:\n{}\n  Total: {} worked",
+        lines.join("\n"),
+        signed_hhmm(total)
+    ))
+}
+
+fn signed_hhmm(timedelta: TimeDelta) -> String {
+    if timedelta < TimeDelta::zero() {
+        timedelta.to_hhmm()
+    } else {
+        format!("+{}", timedelta.to_hhmm())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use chrono::{NaiveDate, NaiveTime, TimeDelta};
+    use rstest::rstest;
+
+    use crate::day::Day;
+    use crate::rounding::{Apply, Rounding, Rule};
+
+    fn time(text: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(text, "%H:%M:%S").expect("")
+    }
+
+    #[rstest]
+    #[case("nearest 15", "08:07:29", "08:00:00")]
+    #[case("nearest 15", "08:07:30", "08:15:00")]
+    #[case("up 15", "08:01:00", "08:15:00")]
+    #[case("up 15", "08:15:00", "08:15:00")]
+    #[case("down 15", "08:14:59", "08:00:00")]
+    #[case("nearest 6", "08:04:00", "08:06:00")]
+    #[case("up 30", "23:50:00", "23:30:00")]
+    #[case("nearest 10", "23:56:00", "23:50:00")]
+    fn round_time(#[case] rule: &str, #[case] before: &str, #[case] after: &str) {
+        let rule = Rule::parse(rule).expect("");
+
+        assert_eq!(rule.round_time(time(before)), time(after));
+    }
+
+    #[rstest]
+    #[case("nearest 15", 52, 45)]
+    #[case("up 5", 41, 45)]
+    #[case("down 30", 59, 30)]
+    fn round_timedelta(#[case] rule: &str, #[case] before: i64, #[case] after: i64) {
+        let rule = Rule::parse(rule).expect("");
+
+        assert_eq!(
+            rule.round_timedelta(TimeDelta::try_minutes(before).expect("")),
+            TimeDelta::try_minutes(after).expect("")
+        );
+    }
+
+    #[rstest]
+    #[case("nearest")]
+    #[case("nearest 7")]
+    #[case("sideways 15")]
+    #[case("up fifteen")]
+    fn invalid_rule(#[case] text: &str) {
+        assert!(Rule::parse(text).is_err());
+    }
+
+    #[rstest]
+    fn rule_round_trip() {
+        let rule = Rule::parse("down 15").expect("");
+
+        assert_eq!(Rule::parse(&rule.to_string()), Ok(rule));
+    }
+
+    #[rstest]
+    fn round_day_and_describe_changes() {
+        let rounding = Rounding {
+            apply: Apply::Report,
+            start: Some(Rule::parse("down 15").expect("")),
+            stop: Some(Rule::parse("up 15").expect("")),
+            lunch: None,
+            project: Some(Rule::parse("nearest 15").expect("")),
+        };
+        let day = Day {
+            date: NaiveDate::from_ymd_opt(2025, 4, 15).expect(""),
+            start: Some(time("08:03:00")),
+            stop: Some(time("16:58:00")),
+            lunch: Some(TimeDelta::try_minutes(32).expect("")),
+            projects: HashMap::from([("A".to_string(), TimeDelta::try_minutes(50).expect(""))]),
+            overtime: None,
+            cleared: Vec::new(),
+        };
+
+        let rounded = rounding.round(&day);
+
+        assert_eq!(rounded.start, Some(time("08:00:00")));
+        assert_eq!(rounded.stop, Some(time("17:00:00")));
+        assert_eq!(rounded.lunch, day.lunch);
+        assert_eq!(
+            rounding.changes(&day),
+            vec![
+                "start 08:03 to 08:00",
+                "stop 16:58 to 17:00",
+                "A 00:50 to 00:45"
+            ]
+        );
+    }
+}
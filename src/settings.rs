@@ -0,0 +1,474 @@
+use std::collections::HashMap;
+
+use chrono::{NaiveDate, NaiveTime, TimeDelta, Weekday};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::argparse;
+use crate::compliance::Limits;
+use crate::copy_format::CopyFormat;
+use crate::import::CsvFormat;
+use crate::lunch::{Break, LunchRules};
+use crate::overtime::Rules;
+use crate::rounding::{Rounding, Rule};
+use crate::storage::Backend;
+use crate::suggest::GitRepository;
+use crate::timedelta::{deserialize_seconds_or_hhmm_timedelta, serialize_hhmm_timedelta};
+use crate::traits::Parsable;
+
+/// The settings that are edited by the user, as opposed to the recorded
+/// data.
+///
+/// Stored in a TOML file next to the data file. Older data files hold the
+/// settings as top-level fields, which are still read if there is no
+/// settings file.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    #[serde(serialize_with = "serialize_hhmm_timedelta")]
+    #[serde(deserialize_with = "deserialize_seconds_or_hhmm_timedelta")]
+    pub working_time_per_day: TimeDelta,
+    /// Changes to days older than this are compacted automatically
+    pub compact_keep_days: i64,
+    /// The number of entries in the log that triggers automatic compaction
+    pub compact_threshold: usize,
+    pub project_names: Vec<String>,
+    pub copy_format: CopyFormat,
+    pub csv_formats: HashMap<String, CsvFormat>,
+    pub git_repositories: Vec<GitRepository>,
+    pub compliance: Limits,
+    pub overtime: Rules,
+    pub rounding: Rounding,
+    pub lunch: LunchRules,
+    /// Overrides the backend given by the extension of the data file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage: Option<Backend>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            working_time_per_day: TimeDelta::new(27900, 0).expect("hardcoded seconds"),
+            compact_keep_days: 90,
+            compact_threshold: 1000,
+            project_names: Vec::new(),
+            copy_format: CopyFormat::default(),
+            csv_formats: HashMap::new(),
+            git_repositories: Vec::new(),
+            compliance: Limits::default(),
+            overtime: Rules::default(),
+            rounding: Rounding::default(),
+            lunch: LunchRules::default(),
+            storage: None,
+        }
+    }
+}
+
+/// The settings that can be read and changed with `t config`.
+pub const KEYS: &[&str] = &[
+    "working_time_per_day",
+    "compact_keep_days",
+    "compact_threshold",
+    "project_names",
+    "copy_format.time_format",
+    "copy_format.decimal_separator",
+    "copy_format.decimals",
+    "copy_format.layout",
+    "copy_format.include_weekend",
+    "copy_format.blank_positive_flex",
+    "compliance.daily_rest",
+    "compliance.weekly_hours",
+    "compliance.averaging_weeks",
+    "compliance.overtime_per_month",
+    "compliance.overtime_per_year",
+    "compliance.near_miss_margin",
+    "overtime.evening_after",
+    "overtime.weekends",
+    "overtime.holidays",
+    "overtime.marked_multiplier",
+    "overtime.evening_multiplier",
+    "overtime.weekend_multiplier",
+    "overtime.holiday_multiplier",
+    "rounding.apply",
+    "rounding.start",
+    "rounding.stop",
+    "rounding.lunch",
+    "rounding.project",
+    "lunch.monday",
+    "lunch.tuesday",
+    "lunch.wednesday",
+    "lunch.thursday",
+    "lunch.friday",
+    "lunch.saturday",
+    "lunch.sunday",
+    "lunch.breaks",
+    "storage",
+];
+
+impl Settings {
+    pub fn from_toml(text: &str) -> Result<Self, String> {
+        toml::from_str(text).map_err(|error| error.message().to_string())
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("settings are serializable")
+    }
+
+    pub fn get(&self, key: &str) -> Result<String, String> {
+        let copy_format = &self.copy_format;
+        Ok(match key {
+            "working_time_per_day" => self.working_time_per_day.to_hhmm(),
+            "compact_keep_days" => self.compact_keep_days.to_string(),
+            "compact_threshold" => self.compact_threshold.to_string(),
+            "project_names" => self.project_names.join(", "),
+            "copy_format.time_format" => serde_name(&copy_format.time_format),
+            "copy_format.decimal_separator" => copy_format.decimal_separator.clone(),
+            "copy_format.decimals" => copy_format.decimals.to_string(),
+            "copy_format.layout" => serde_name(&copy_format.layout),
+            "copy_format.include_weekend" => copy_format.include_weekend.to_string(),
+            "copy_format.blank_positive_flex" => copy_format.blank_positive_flex.to_string(),
+            "compliance.daily_rest" => self.compliance.daily_rest.to_hhmm(),
+            "compliance.weekly_hours" => self.compliance.weekly_hours.to_hhmm(),
+            "compliance.averaging_weeks" => self.compliance.averaging_weeks.to_string(),
+            "compliance.overtime_per_month" => self.compliance.overtime_per_month.to_hhmm(),
+            "compliance.overtime_per_year" => self.compliance.overtime_per_year.to_hhmm(),
+            "compliance.near_miss_margin" => self.compliance.near_miss_margin.to_hhmm(),
+            "overtime.evening_after" => match self.overtime.evening_after {
+                Some(time) => time.format("%H:%M").to_string(),
+                None => "off".to_string(),
+            },
+            "overtime.weekends" => self.overtime.weekends.to_string(),
+            "overtime.holidays" => self
+                .overtime
+                .holidays
+                .iter()
+                .map(|date| date.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            "overtime.marked_multiplier" => self.overtime.marked_multiplier.to_string(),
+            "overtime.evening_multiplier" => self.overtime.evening_multiplier.to_string(),
+            "overtime.weekend_multiplier" => self.overtime.weekend_multiplier.to_string(),
+            "overtime.holiday_multiplier" => self.overtime.holiday_multiplier.to_string(),
+            "rounding.apply" => serde_name(&self.rounding.apply),
+            "rounding.start" => rule_name(self.rounding.start),
+            "rounding.stop" => rule_name(self.rounding.stop),
+            "rounding.lunch" => rule_name(self.rounding.lunch),
+            "rounding.project" => rule_name(self.rounding.project),
+            "lunch.breaks" if self.lunch.breaks.is_empty() => "off".to_string(),
+            "lunch.breaks" => Break::format_list(&self.lunch.breaks),
+            "storage" => match &self.storage {
+                Some(backend) => serde_name(backend),
+                None => "auto".to_string(),
+            },
+            _ => match lunch_weekday(key) {
+                Some(weekday) => match self.lunch.weekday(weekday) {
+                    Some(timedelta) => timedelta.to_hhmm(),
+                    None => "off".to_string(),
+                },
+                None => return Err(unknown_key(key)),
+            },
+        })
+    }
+
+    /// Change a setting, if the value is valid for it.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let invalid = |reason: &str| format!("Invalid value '{}' for {}: {}", value, key, reason);
+        let copy_format = &mut self.copy_format;
+        let limits = &mut self.compliance;
+        let rules = &mut self.overtime;
+        let rounding = &mut self.rounding;
+        let duration = || match TimeDelta::from_str(value) {
+            Ok(timedelta) if timedelta >= TimeDelta::zero() => Ok(timedelta),
+            _ => Err(invalid("must be a duration, e.g. 11:00")),
+        };
+        let multiplier = || match value.parse::<f64>() {
+            Ok(multiplier) if multiplier >= 0.0 && multiplier.is_finite() => Ok(multiplier),
+            _ => Err(invalid("must be a number, e.g. 1.5")),
+        };
+        let rule = || match value {
+            "off" => Ok(None),
+            _ => Rule::parse(value).map(Some).map_err(|_| {
+                invalid("must be nearest, up or down followed by 5, 6, 10, 15 or 30, or off")
+            }),
+        };
+        match key {
+            "working_time_per_day" => {
+                let timedelta = TimeDelta::from_str(value).map_err(|message| invalid(&message))?;
+                if timedelta <= TimeDelta::zero()
+                    || timedelta > TimeDelta::try_hours(24).expect("24 hours")
+                {
+                    return Err(invalid("must be more than 0 and at most 24 hours"));
+                }
+                self.working_time_per_day = timedelta;
+            }
+            "compact_keep_days" => match value.parse::<i64>() {
+                Ok(days) if days >= 0 => self.compact_keep_days = days,
+                _ => return Err(invalid("must be a number of days")),
+            },
+            "compact_threshold" => match value.parse::<usize>() {
+                Ok(count) if count > 0 => self.compact_threshold = count,
+                _ => return Err(invalid("must be a positive number")),
+            },
+            "project_names" => {
+                return Err(
+                    "Projects cannot be set directly. Use 't add PROJECT' to add a project."
+                        .to_string(),
+                )
+            }
+            "copy_format.time_format" => {
+                copy_format.time_format =
+                    from_serde_name(value).map_err(|_| invalid("must be decimal or hhmm"))?
+            }
+            "copy_format.decimal_separator" => {
+                if value.is_empty() {
+                    return Err(invalid("must not be empty"));
+                }
+                copy_format.decimal_separator = value.to_string();
+            }
+            "copy_format.decimals" => match value.parse::<usize>() {
+                Ok(decimals) if decimals <= 6 => copy_format.decimals = decimals,
+                _ => return Err(invalid("must be a number from 0 to 6")),
+            },
+            "copy_format.layout" => {
+                copy_format.layout =
+                    from_serde_name(value).map_err(|_| invalid("must be rows or columns"))?
+            }
+            "copy_format.include_weekend" => {
+                copy_format.include_weekend = value
+                    .parse()
+                    .map_err(|_| invalid("must be true or false"))?
+            }
+            "copy_format.blank_positive_flex" => {
+                copy_format.blank_positive_flex = value
+                    .parse()
+                    .map_err(|_| invalid("must be true or false"))?
+            }
+            "compliance.daily_rest" => limits.daily_rest = duration()?,
+            "compliance.weekly_hours" => limits.weekly_hours = duration()?,
+            "compliance.averaging_weeks" => match value.parse::<usize>() {
+                Ok(weeks) if weeks > 0 => limits.averaging_weeks = weeks,
+                _ => return Err(invalid("must be a positive number of weeks")),
+            },
+            "compliance.overtime_per_month" => limits.overtime_per_month = duration()?,
+            "compliance.overtime_per_year" => limits.overtime_per_year = duration()?,
+            "compliance.near_miss_margin" => limits.near_miss_margin = duration()?,
+            "overtime.evening_after" => {
+                rules.evening_after = match value {
+                    "off" => None,
+                    _ => Some(
+                        NaiveTime::parse_from_str(value, "%H:%M")
+                            .map_err(|_| invalid("must be a time, e.g. 18:00, or off"))?,
+                    ),
+                }
+            }
+            "overtime.weekends" => {
+                rules.weekends = value
+                    .parse()
+                    .map_err(|_| invalid("must be true or false"))?
+            }
+            "overtime.holidays" => {
+                rules.holidays = value
+                    .split(',')
+                    .map(|date| date.trim())
+                    .filter(|date| !date.is_empty())
+                    .map(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d"))
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| invalid("must be dates like 2025-12-25, separated by commas"))?
+            }
+            "overtime.marked_multiplier" => rules.marked_multiplier = multiplier()?,
+            "overtime.evening_multiplier" => rules.evening_multiplier = multiplier()?,
+            "overtime.weekend_multiplier" => rules.weekend_multiplier = multiplier()?,
+            "overtime.holiday_multiplier" => rules.holiday_multiplier = multiplier()?,
+            "rounding.apply" => {
+                rounding.apply =
+                    from_serde_name(value).map_err(|_| invalid("must be entry or report"))?
+            }
+            "rounding.start" => rounding.start = rule()?,
+            "rounding.stop" => rounding.stop = rule()?,
+            "rounding.lunch" => rounding.lunch = rule()?,
+            "rounding.project" => rounding.project = rule()?,
+            "lunch.breaks" => {
+                self.lunch.breaks = match value {
+                    "off" => Vec::new(),
+                    _ => Break::parse_list(value).map_err(|_| {
+                        invalid(
+                            "must be a time worked and a break, e.g. 6:00 30m, 9:00 45m, or off",
+                        )
+                    })?,
+                }
+            }
+            "storage" => {
+                self.storage = match value {
+                    "auto" => None,
+                    _ => Some(
+                        from_serde_name(value)
+                            .map_err(|_| invalid("must be json, sqlite or auto"))?,
+                    ),
+                }
+            }
+            _ => match lunch_weekday(key) {
+                Some(weekday) => {
+                    *self.lunch.weekday_mut(weekday) = match value {
+                        "off" => None,
+                        _ => Some(
+                            duration()
+                                .map_err(|_| invalid("must be a duration, e.g. 45m, or off"))?,
+                        ),
+                    }
+                }
+                None => return Err(unknown_key(key)),
+            },
+        }
+        Ok(())
+    }
+}
+
+fn unknown_key(key: &str) -> String {
+    match argparse::closest(key, KEYS.iter().copied()) {
+        Some(closest) => format!("Unknown setting '{}'. Did you mean '{}'?", key, closest),
+        None => format!("Unknown setting '{}'. Settings: {}", key, KEYS.join(", ")),
+    }
+}
+
+/// The weekday of a `lunch.WEEKDAY` key.
+fn lunch_weekday(key: &str) -> Option<Weekday> {
+    if !KEYS.contains(&key) {
+        return None;
+    }
+    key.strip_prefix("lunch.")?.parse().ok()
+}
+
+fn rule_name(rule: Option<Rule>) -> String {
+    match rule {
+        Some(rule) => rule.to_string(),
+        None => "off".to_string(),
+    }
+}
+
+/// The name of an enum variant as written in the settings file.
+fn serde_name<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(name)) => name,
+        _ => unreachable!("only used for unit variants"),
+    }
+}
+
+fn from_serde_name<T: DeserializeOwned>(name: &str) -> Result<T, serde_json::Error> {
+    serde_json::from_value(serde_json::Value::String(name.to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::TimeDelta;
+    use rstest::rstest;
+
+    use crate::copy_format::Layout;
+    use crate::settings::Settings;
+
+    #[rstest]
+    fn toml_round_trip() {
+        let settings = Settings {
+            project_names: vec!["Project A".to_string()],
+            ..Settings::default()
+        };
+
+        let text = settings.to_toml();
+
+        assert!(text.contains("working_time_per_day = \"07:45\""));
+        assert_eq!(Settings::from_toml(&text), Ok(settings));
+    }
+
+    #[rstest]
+    fn working_time_in_seconds_is_read() {
+        let settings = Settings::from_toml("working_time_per_day = 28800").expect("");
+
+        assert_eq!(
+            settings.working_time_per_day,
+            TimeDelta::try_hours(8).expect("")
+        );
+    }
+
+    #[rstest]
+    #[case("working_time_per_day", "8:00", "08:00")]
+    #[case("working_time_per_day", "7:45", "07:45")]
+    #[case("compact_keep_days", "30", "30")]
+    #[case("copy_format.layout", "columns", "columns")]
+    #[case("copy_format.include_weekend", "false", "false")]
+    #[case("compliance.overtime_per_year", "150:00", "150:00")]
+    #[case("compliance.averaging_weeks", "4", "4")]
+    #[case("overtime.evening_after", "18:00", "18:00")]
+    #[case("overtime.evening_after", "off", "off")]
+    #[case("overtime.holidays", "2025-12-25,2025-12-26", "2025-12-25, 2025-12-26")]
+    #[case("overtime.weekend_multiplier", "2.5", "2.5")]
+    #[case("rounding.apply", "entry", "entry")]
+    #[case("rounding.start", "nearest 15", "nearest 15")]
+    #[case("rounding.stop", "up 30m", "up 30")]
+    #[case("rounding.lunch", "off", "off")]
+    #[case("lunch.friday", "30m", "00:30")]
+    #[case("lunch.monday", "off", "off")]
+    #[case("lunch.breaks", "6:00 30m, 9:00 45m", "06:00 00:30, 09:00 00:45")]
+    #[case("lunch.breaks", "off", "off")]
+    #[case("storage", "sqlite", "sqlite")]
+    #[case("storage", "auto", "auto")]
+    fn set_and_get(#[case] key: &str, #[case] value: &str, #[case] expected: &str) {
+        let mut settings = Settings::default();
+
+        settings.set(key, value).expect("valid value");
+
+        assert_eq!(settings.get(key), Ok(expected.to_string()));
+    }
+
+    #[rstest]
+    #[case("working_time_per_day", "25:00")]
+    #[case("working_time_per_day", "seven")]
+    #[case("compact_threshold", "0")]
+    #[case("copy_format.layout", "diagonal")]
+    #[case("copy_format.include_weekend", "yes")]
+    #[case("compliance.averaging_weeks", "0")]
+    #[case("compliance.daily_rest", "long")]
+    #[case("overtime.evening_after", "evening")]
+    #[case("overtime.holidays", "christmas")]
+    #[case("overtime.evening_multiplier", "-1")]
+    #[case("rounding.apply", "always")]
+    #[case("rounding.start", "nearest 7")]
+    #[case("lunch.friday", "long")]
+    #[case("lunch.fri", "30m")]
+    #[case("lunch.breaks", "6:00")]
+    #[case("project_names", "a")]
+    #[case("storage", "csv")]
+    #[case("no_such_setting", "1")]
+    fn invalid_values_are_refused(#[case] key: &str, #[case] value: &str) {
+        let mut settings = Settings::default();
+
+        assert!(settings.set(key, value).is_err());
+        assert_eq!(settings, Settings::default());
+    }
+
+    #[rstest]
+    fn evening_after_without_seconds_is_read() {
+        let settings = Settings::from_toml("[overtime]\nevening_after = \"18:00\"").expect("");
+
+        assert_eq!(
+            settings.get("overtime.evening_after"),
+            Ok("18:00".to_string())
+        );
+    }
+
+    #[rstest]
+    fn rounding_rules_are_read() {
+        let settings =
+            Settings::from_toml("[rounding]\napply = \"entry\"\nproject = \"down 6\"").expect("");
+
+        assert_eq!(settings.get("rounding.apply"), Ok("entry".to_string()));
+        assert_eq!(settings.get("rounding.project"), Ok("down 6".to_string()));
+        assert_eq!(settings.get("rounding.start"), Ok("off".to_string()));
+    }
+
+    #[rstest]
+    fn layout_is_written_like_in_settings_file() {
+        let mut settings = Settings::default();
+        settings.copy_format.layout = Layout::Columns;
+
+        assert!(settings.to_toml().contains("layout = \"columns\""));
+    }
+}
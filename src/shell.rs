@@ -0,0 +1,203 @@
+//! Running many commands against one loaded config, interactively with
+//! `t shell` or from standard input with `t batch`.
+//!
+//! The changes are kept in memory and saved together at the end, so either
+//! all of them are saved or none.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+
+use crate::config;
+use crate::day::Day;
+use crate::storage::{Storage, Transaction};
+
+/// Commands that take over the terminal or standard input themselves, or
+/// that write other files than the ones of the transaction
+const UNSUPPORTED_COMMANDS: &[&str] = &[
+    "shell", "batch", "tui", "encrypt", "decrypt", "where", "convert",
+];
+
+const SHELL_HELP: &str = "Enter commands without the leading t, e.g. '2025-04-15 start 8'.
+exit or Ctrl-D saves the changes and quits, abort quits without saving.";
+
+/// Read commands from the terminal until the user quits, with the history
+/// of earlier sessions in `history_path`, if given. A failing command
+/// changes nothing, and the user can go on with the next one.
+///
+/// `now` is called for each command, as a session can go on past midnight.
+pub fn shell(
+    storage: &dyn Storage,
+    history_path: Option<&Path>,
+    now: &dyn Fn() -> NaiveDateTime,
+) -> String {
+    let transaction = Transaction::new(storage);
+    let previous_day_from_date = match config::load(&transaction, now().date()) {
+        Ok(config) => config.rounded_day_from_date(),
+        Err(message) => return message,
+    };
+    let mut editor = match DefaultEditor::new() {
+        Ok(editor) => editor,
+        Err(error) => return format!("Could not start the shell: {}", error),
+    };
+    if let Some(history_path) = history_path {
+        // There is no history file the first time
+        let _ = editor.load_history(history_path);
+    }
+    println!("{}", SHELL_HELP);
+    loop {
+        let line = match editor.readline("t> ") {
+            Ok(line) => line,
+            // Ctrl-C only discards the line being entered
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(error) => {
+                return format!("Could not read the command: {}\nNothing was saved.", error)
+            }
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(line);
+        match line {
+            "exit" | "quit" => break,
+            "abort" => return "Nothing was saved.".to_string(),
+            _ => match run_line(line, &transaction, now()) {
+                Ok(output) | Err(output) => println!("{}", output),
+            },
+        }
+    }
+    if let Some(history_path) = history_path {
+        if let Err(error) = editor.save_history(history_path) {
+            eprintln!("Could not save the shell history: {}", error);
+        }
+    }
+    finish(transaction, &previous_day_from_date, now())
+}
+
+/// Run the commands in `input`, one per line, and save the changes if all
+/// of them succeed. Empty lines and lines starting with # are skipped.
+pub fn batch(input: &str, storage: &dyn Storage, now: NaiveDateTime) -> String {
+    let transaction = Transaction::new(storage);
+    let previous_day_from_date = match config::load(&transaction, now.date()) {
+        Ok(config) => config.rounded_day_from_date(),
+        Err(message) => return message,
+    };
+    for (index, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Err(message) = run_line(line, &transaction, now) {
+            return format!(
+                "Error on line {}: {}\n{}\nNothing was saved.",
+                index + 1,
+                line,
+                message
+            );
+        }
+    }
+    finish(transaction, &previous_day_from_date, now)
+}
+
+/// Run one command in a transaction of its own, so that a failing command
+/// changes nothing.
+fn run_line(line: &str, storage: &dyn Storage, now: NaiveDateTime) -> Result<String, String> {
+    let args = split_line(line)?;
+    if let Some(command) = args
+        .first()
+        .filter(|arg| UNSUPPORTED_COMMANDS.contains(&arg.as_str()))
+    {
+        return Err(format!(
+            "{} cannot be used in the shell or in a batch",
+            command
+        ));
+    }
+    let transaction = Transaction::new(storage);
+    let output = crate::run(args, &transaction, now)?;
+    transaction.commit()?;
+    Ok(output)
+}
+
+/// Save the changes and show the weeks of the changed days, with the
+/// changes highlighted.
+fn finish(
+    transaction: Transaction,
+    previous_day_from_date: &HashMap<NaiveDate, Day>,
+    now: NaiveDateTime,
+) -> String {
+    let config = match config::load(&transaction, now.date()) {
+        Ok(config) => config,
+        Err(message) => return message,
+    };
+    let day_from_date = config.rounded_day_from_date();
+    let changed_dates: Vec<NaiveDate> = day_from_date
+        .keys()
+        .chain(previous_day_from_date.keys())
+        .filter(|date| day_from_date.get(date) != previous_day_from_date.get(date))
+        .copied()
+        .collect();
+    if let Err(message) = transaction.commit() {
+        return message;
+    }
+    if changed_dates.is_empty() {
+        return "No changes".to_string();
+    }
+    crate::create_terminal_table_for_dates(&changed_dates, &config, previous_day_from_date, now)
+}
+
+/// Split a command line into arguments like a shell does, keeping quoted
+/// text like "Project containing spaces" together.
+fn split_line(line: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut arg: Option<String> = None;
+    let mut quote: Option<char> = None;
+    for c in line.chars() {
+        match quote {
+            Some(quote_char) if c == quote_char => quote = None,
+            Some(_) => arg.get_or_insert_with(String::new).push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                // Also for an empty argument, ""
+                arg.get_or_insert_with(String::new);
+            }
+            None if c.is_whitespace() => args.extend(arg.take()),
+            None => arg.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        return Err(format!("Missing closing quote in '{}'", line));
+    }
+    args.extend(arg);
+    Ok(args)
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use crate::shell::split_line;
+
+    #[rstest]
+    #[case("2025-04-15 start 8", vec!["2025-04-15", "start", "8"])]
+    #[case("  stop   17 ", vec!["stop", "17"])]
+    #[case(r#"add "Project containing spaces""#, vec!["add", "Project containing spaces"])]
+    #[case("project 'A B' 1:00", vec!["project", "A B", "1:00"])]
+    #[case(r#"add """#, vec!["add", ""])]
+    #[case("", vec![])]
+    fn split(#[case] line: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(
+            split_line(line),
+            Ok(expected.into_iter().map(String::from).collect())
+        );
+    }
+
+    #[rstest]
+    fn missing_closing_quote() {
+        assert!(split_line(r#"add "Project"#).is_err());
+    }
+}
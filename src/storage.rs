@@ -0,0 +1,603 @@
+//! Where the config and the archives are stored.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::config::Config;
+use crate::day::Day;
+use crate::settings::Settings;
+use encryption::Encryption;
+
+mod encryption;
+mod sqlite;
+
+pub use encryption::is_encrypted;
+
+/// Loads and saves the config and the archived years.
+///
+/// `load` returns a new, empty config if nothing has been stored yet.
+pub trait Storage {
+    fn load(&self) -> Result<Config, String>;
+    fn save(&self, config: &Config) -> Result<(), String>;
+    fn load_archive(&self, year: i32) -> Result<Vec<Day>, String>;
+    fn save_archive(&self, year: i32, days: &[Day]) -> Result<(), String>;
+    /// Where the data is stored, e.g. the path of the data file
+    fn name(&self) -> String;
+    /// Where the archive for the year is stored
+    fn archive_name(&self, year: i32) -> String;
+    /// The format of the data file, if the data is stored in one
+    fn backend(&self) -> Option<Backend> {
+        None
+    }
+    /// The settings file, if the settings are stored in a file of their own
+    fn settings_path(&self) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// The format of the data file.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    Json,
+    Sqlite,
+}
+
+/// The storage for a data file, selected by the `storage` setting or else by
+/// the file extension: an SQLite database for .sqlite, .sqlite3 and .db, and
+/// a json file otherwise. The settings are in a TOML file with the same name
+/// next to the data file.
+pub fn from_path(path: &Path) -> Box<dyn Storage> {
+    from_paths(path, &path.with_extension("toml"))
+}
+
+/// Like `from_path`, but with the settings file at `settings_path`.
+pub fn from_paths(path: &Path, settings_path: &Path) -> Box<dyn Storage> {
+    from_backend(backend(path, settings_path), path, settings_path)
+}
+
+/// The storage for a data file in the given format.
+pub fn from_backend(backend: Backend, path: &Path, settings_path: &Path) -> Box<dyn Storage> {
+    match backend {
+        Backend::Sqlite => Box::new(SqliteFile::new(path, settings_path)),
+        Backend::Json => Box::new(JsonFile::new(path, settings_path)),
+    }
+}
+
+/// The backend given by the extension of the data file.
+pub fn backend_from_extension(path: &Path) -> Backend {
+    if sqlite::is_sqlite(path) {
+        Backend::Sqlite
+    } else {
+        Backend::Json
+    }
+}
+
+/// The backend set in the settings file, or else the one given by the
+/// extension of the data file.
+pub fn backend(path: &Path, settings_path: &Path) -> Backend {
+    // A settings file that cannot be read is reported when loading
+    let setting = fs::read_to_string(settings_path)
+        .ok()
+        .and_then(|contents| Settings::from_toml(&contents).ok())
+        .and_then(|settings| settings.storage);
+    setting.unwrap_or_else(|| backend_from_extension(path))
+}
+
+/// The config in a json file, with the settings in a TOML file and archives
+/// in json files next to it.
+pub struct JsonFile {
+    path: PathBuf,
+    settings_path: PathBuf,
+}
+
+impl JsonFile {
+    pub fn new(path: &Path, settings_path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            settings_path: settings_path.to_path_buf(),
+        }
+    }
+}
+
+impl Storage for JsonFile {
+    fn load(&self) -> Result<Config, String> {
+        let path = &self.path;
+        if fs::metadata(path).is_err() {
+            self.save(&Config::new(Vec::new()))?;
+        }
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) => {
+                return Err(format!(
+                    "Error when trying to access {}: {}",
+                    path.to_string_lossy(),
+                    error
+                ))
+            }
+        };
+        let mut config = match serde_json::from_str::<Config>(&contents) {
+            Ok(config) => config,
+            Err(_) => return Err(format!("Failed to parse {}", path.to_string_lossy())),
+        };
+        load_settings_file(&mut config, &self.settings_path)?;
+        Ok(config)
+    }
+
+    fn save(&self, config: &Config) -> Result<(), String> {
+        let json_string = serde_json::to_string_pretty(&data_without_settings(config)).unwrap();
+        let mut files = changed_settings_file(config, &self.settings_path);
+        files.push((&self.path, json_string.into_bytes()));
+        write_files(&files)
+    }
+
+    fn load_archive(&self, year: i32) -> Result<Vec<Day>, String> {
+        load_archive_file(&archive_path(&self.path, year))
+    }
+
+    fn save_archive(&self, year: i32, days: &[Day]) -> Result<(), String> {
+        save_archive_file(&archive_path(&self.path, year), days)
+    }
+
+    fn name(&self) -> String {
+        self.path.to_string_lossy().to_string()
+    }
+
+    fn archive_name(&self, year: i32) -> String {
+        archive_path(&self.path, year).to_string_lossy().to_string()
+    }
+
+    fn backend(&self) -> Option<Backend> {
+        Some(Backend::Json)
+    }
+
+    fn settings_path(&self) -> Option<PathBuf> {
+        Some(self.settings_path.clone())
+    }
+}
+
+/// The config in an SQLite database, with the settings in a TOML file and
+/// archives in json files next to it.
+pub struct SqliteFile {
+    path: PathBuf,
+    settings_path: PathBuf,
+}
+
+impl SqliteFile {
+    pub fn new(path: &Path, settings_path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            settings_path: settings_path.to_path_buf(),
+        }
+    }
+}
+
+impl Storage for SqliteFile {
+    fn load(&self) -> Result<Config, String> {
+        let mut config = sqlite::load(&self.path)?;
+        load_settings_file(&mut config, &self.settings_path)?;
+        Ok(config)
+    }
+
+    fn save(&self, config: &Config) -> Result<(), String> {
+        write_files(&changed_settings_file(config, &self.settings_path))?;
+        sqlite::save(data_without_settings(config), &self.path)
+    }
+
+    fn load_archive(&self, year: i32) -> Result<Vec<Day>, String> {
+        load_archive_file(&archive_path(&self.path, year))
+    }
+
+    fn save_archive(&self, year: i32, days: &[Day]) -> Result<(), String> {
+        save_archive_file(&archive_path(&self.path, year), days)
+    }
+
+    fn name(&self) -> String {
+        self.path.to_string_lossy().to_string()
+    }
+
+    fn archive_name(&self, year: i32) -> String {
+        archive_path(&self.path, year).to_string_lossy().to_string()
+    }
+
+    fn backend(&self) -> Option<Backend> {
+        Some(Backend::Sqlite)
+    }
+
+    fn settings_path(&self) -> Option<PathBuf> {
+        Some(self.settings_path.clone())
+    }
+}
+
+/// The config in a json file encrypted with a passphrase. The archives are
+/// encrypted in the same way, while the settings are kept in a plain TOML
+/// file so that they can still be edited by hand.
+pub struct EncryptedFile {
+    path: PathBuf,
+    settings_path: PathBuf,
+    encryption: Encryption,
+}
+
+impl EncryptedFile {
+    pub fn new(path: &Path, settings_path: &Path, passphrase: &str) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            settings_path: settings_path.to_path_buf(),
+            encryption: Encryption::new(passphrase),
+        }
+    }
+
+    fn read(&self, path: &Path) -> Result<String, String> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                return Err(format!(
+                    "Error when trying to access {}: {}",
+                    path.to_string_lossy(),
+                    error
+                ))
+            }
+        };
+        let name = path.to_string_lossy();
+        let plaintext = self.encryption.decrypt(&bytes, &name)?;
+        String::from_utf8(plaintext).map_err(|_| format!("Failed to parse {}", name))
+    }
+
+    fn write(&self, path: &Path, json_string: &str) -> Result<(), String> {
+        write_files(&[(path, self.encryption.encrypt(json_string.as_bytes()))])
+    }
+}
+
+impl Storage for EncryptedFile {
+    fn load(&self) -> Result<Config, String> {
+        if fs::metadata(&self.path).is_err() {
+            self.save(&Config::new(Vec::new()))?;
+        }
+        let contents = self.read(&self.path)?;
+        let mut config = match serde_json::from_str::<Config>(&contents) {
+            Ok(config) => config,
+            Err(_) => return Err(format!("Failed to parse {}", self.path.to_string_lossy())),
+        };
+        load_settings_file(&mut config, &self.settings_path)?;
+        Ok(config)
+    }
+
+    fn save(&self, config: &Config) -> Result<(), String> {
+        let json_string = serde_json::to_string(&data_without_settings(config)).unwrap();
+        let mut files = changed_settings_file(config, &self.settings_path);
+        files.push((&self.path, self.encryption.encrypt(json_string.as_bytes())));
+        write_files(&files)
+    }
+
+    fn load_archive(&self, year: i32) -> Result<Vec<Day>, String> {
+        let path = archive_path(&self.path, year);
+        let contents = self.read(&path)?;
+        match serde_json::from_str::<Archive>(&contents) {
+            Ok(archive) => Ok(archive.days),
+            Err(_) => Err(format!("Failed to parse {}", path.to_string_lossy())),
+        }
+    }
+
+    fn save_archive(&self, year: i32, days: &[Day]) -> Result<(), String> {
+        let archive = Archive {
+            days: days.to_vec(),
+        };
+        let json_string = serde_json::to_string(&archive).unwrap();
+        self.write(&archive_path(&self.path, year), &json_string)
+    }
+
+    fn name(&self) -> String {
+        self.path.to_string_lossy().to_string()
+    }
+
+    fn archive_name(&self, year: i32) -> String {
+        archive_path(&self.path, year).to_string_lossy().to_string()
+    }
+
+    fn backend(&self) -> Option<Backend> {
+        Some(Backend::Json)
+    }
+
+    fn settings_path(&self) -> Option<PathBuf> {
+        Some(self.settings_path.clone())
+    }
+}
+
+/// Storage that is kept in memory only, e.g. for tests.
+#[derive(Default)]
+pub struct InMemory {
+    config: RefCell<Option<String>>,
+    archives: RefCell<HashMap<i32, Vec<Day>>>,
+}
+
+impl InMemory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Storage for InMemory {
+    fn load(&self) -> Result<Config, String> {
+        match self.config.borrow().as_deref() {
+            None => Ok(Config::new(Vec::new())),
+            Some(json_string) => Ok(serde_json::from_str(json_string).expect("saved by save")),
+        }
+    }
+
+    fn save(&self, config: &Config) -> Result<(), String> {
+        *self.config.borrow_mut() = Some(serde_json::to_string(config).unwrap());
+        Ok(())
+    }
+
+    fn load_archive(&self, year: i32) -> Result<Vec<Day>, String> {
+        match self.archives.borrow().get(&year) {
+            Some(days) => Ok(days.clone()),
+            None => Err(format!("No archive for {}", year)),
+        }
+    }
+
+    fn save_archive(&self, year: i32, days: &[Day]) -> Result<(), String> {
+        self.archives.borrow_mut().insert(year, days.to_vec());
+        Ok(())
+    }
+
+    fn name(&self) -> String {
+        "memory".to_string()
+    }
+
+    fn archive_name(&self, year: i32) -> String {
+        format!("the archive for {} in memory", year)
+    }
+}
+
+/// Storage that reads from another storage but refuses to save.
+pub struct ReadOnly<S: Storage>(pub S);
+
+impl<S: Storage> Storage for ReadOnly<S> {
+    fn load(&self) -> Result<Config, String> {
+        self.0.load()
+    }
+
+    fn save(&self, _config: &Config) -> Result<(), String> {
+        Err(format!("{} is read-only", self.0.name()))
+    }
+
+    fn load_archive(&self, year: i32) -> Result<Vec<Day>, String> {
+        self.0.load_archive(year)
+    }
+
+    fn save_archive(&self, year: i32, _days: &[Day]) -> Result<(), String> {
+        Err(format!("{} is read-only", self.0.archive_name(year)))
+    }
+
+    fn name(&self) -> String {
+        self.0.name()
+    }
+
+    fn archive_name(&self, year: i32) -> String {
+        self.0.archive_name(year)
+    }
+
+    fn backend(&self) -> Option<Backend> {
+        self.0.backend()
+    }
+
+    fn settings_path(&self) -> Option<PathBuf> {
+        self.0.settings_path()
+    }
+}
+
+/// Storage that keeps the changes in memory until they are committed to
+/// another storage, so that the changes of many commands are saved at once,
+/// or not at all.
+pub struct Transaction<'a> {
+    storage: &'a dyn Storage,
+    config: RefCell<Option<Config>>,
+    is_changed: Cell<bool>,
+    archives: RefCell<HashMap<i32, Vec<Day>>>,
+}
+
+impl<'a> Transaction<'a> {
+    pub fn new(storage: &'a dyn Storage) -> Self {
+        Self {
+            storage,
+            config: RefCell::new(None),
+            is_changed: Cell::new(false),
+            archives: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Save the changes to the other storage, the archives first, like
+    /// `Config::archive`.
+    pub fn commit(self) -> Result<(), String> {
+        for (year, days) in self.archives.into_inner() {
+            self.storage.save_archive(year, &days)?;
+        }
+        match self.config.into_inner() {
+            Some(config) if self.is_changed.get() => self.storage.save(&config),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Storage for Transaction<'_> {
+    /// Load the config from the other storage only the first time.
+    fn load(&self) -> Result<Config, String> {
+        if self.config.borrow().is_none() {
+            *self.config.borrow_mut() = Some(self.storage.load()?);
+        }
+        Ok(self.config.borrow().as_ref().expect("just loaded").clone())
+    }
+
+    fn save(&self, config: &Config) -> Result<(), String> {
+        *self.config.borrow_mut() = Some(config.stored());
+        self.is_changed.set(true);
+        Ok(())
+    }
+
+    fn load_archive(&self, year: i32) -> Result<Vec<Day>, String> {
+        match self.archives.borrow().get(&year) {
+            Some(days) => Ok(days.clone()),
+            None => self.storage.load_archive(year),
+        }
+    }
+
+    fn save_archive(&self, year: i32, days: &[Day]) -> Result<(), String> {
+        self.archives.borrow_mut().insert(year, days.to_vec());
+        Ok(())
+    }
+
+    fn name(&self) -> String {
+        self.storage.name()
+    }
+
+    fn archive_name(&self, year: i32) -> String {
+        self.storage.archive_name(year)
+    }
+
+    fn backend(&self) -> Option<Backend> {
+        self.storage.backend()
+    }
+
+    fn settings_path(&self) -> Option<PathBuf> {
+        self.storage.settings_path()
+    }
+}
+
+/// Replace the settings of the config with the ones in the settings file, if
+/// there is one. Without a settings file, the settings are the ones stored
+/// together with the data by older versions.
+fn load_settings_file(config: &mut Config, path: &Path) -> Result<(), String> {
+    if fs::metadata(path).is_err() {
+        return Ok(());
+    }
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) => {
+            return Err(format!(
+                "Error when trying to access {}: {}",
+                path.to_string_lossy(),
+                error
+            ))
+        }
+    };
+    match Settings::from_toml(&contents) {
+        Ok(settings) => {
+            config.settings = settings;
+            Ok(())
+        }
+        Err(message) => Err(format!(
+            "Failed to parse {}: {}",
+            path.to_string_lossy(),
+            message
+        )),
+    }
+}
+
+/// The settings file to write, unless it already holds the settings, so that
+/// the comments and the layout of a file edited by hand are kept until a
+/// setting is changed.
+fn changed_settings_file<'a>(config: &Config, path: &'a Path) -> Vec<(&'a Path, Vec<u8>)> {
+    let is_unchanged = fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| Settings::from_toml(&contents).ok())
+        .is_some_and(|settings| settings == config.settings);
+    if is_unchanged {
+        Vec::new()
+    } else {
+        vec![(path, config.settings.to_toml().into_bytes())]
+    }
+}
+
+/// Write the files through temporary files next to them, which are then
+/// renamed, so that each file is either replaced completely or not at all.
+/// No file is changed unless all of the contents could be written, but the
+/// renames are done one after another, so a crash in between can replace
+/// only the first files.
+fn write_files(files: &[(&Path, Vec<u8>)]) -> Result<(), String> {
+    let temporary_path = |path: &Path| {
+        let mut name = path.as_os_str().to_owned();
+        name.push(".tmp");
+        PathBuf::from(name)
+    };
+    let error = |path: &Path, error: std::io::Error| {
+        format!(
+            "Error writing to file {}: {}",
+            path.to_string_lossy(),
+            error
+        )
+    };
+    for (index, (path, contents)) in files.iter().enumerate() {
+        if let Err(message) = fs::write(temporary_path(path), contents) {
+            for (path, _) in &files[..=index] {
+                let _ = fs::remove_file(temporary_path(path));
+            }
+            return Err(error(path, message));
+        }
+    }
+    for (path, _) in files {
+        fs::rename(temporary_path(path), path).map_err(|message| error(path, message))?;
+    }
+    Ok(())
+}
+
+/// The json representation of the config, without the settings that are
+/// saved in the settings file.
+fn data_without_settings(config: &Config) -> Map<String, Value> {
+    let Value::Object(mut object) = serde_json::to_value(config).expect("config is serializable")
+    else {
+        unreachable!("config is serialized as an object")
+    };
+    let Value::Object(settings) =
+        serde_json::to_value(&config.settings).expect("settings are serializable")
+    else {
+        unreachable!("settings are serialized as an object")
+    };
+    for key in settings.keys() {
+        object.remove(key);
+    }
+    object
+}
+
+/// The days of a year that has been moved out of the main file.
+#[derive(Serialize, Deserialize)]
+struct Archive {
+    days: Vec<Day>,
+}
+
+/// The path of the archive file for a year, e.g. timereport-2024.json next
+/// to timereport.json. Archives are json files also for other backends.
+fn archive_path(path: &Path, year: i32) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}-{}.json", stem, year))
+}
+
+fn load_archive_file(path: &Path) -> Result<Vec<Day>, String> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) => {
+            return Err(format!(
+                "Error when trying to access archive {}: {}",
+                path.to_string_lossy(),
+                error
+            ))
+        }
+    };
+    match serde_json::from_str::<Archive>(&contents) {
+        Ok(archive) => Ok(archive.days),
+        Err(_) => Err(format!("Failed to parse {}", path.to_string_lossy())),
+    }
+}
+
+fn save_archive_file(path: &Path, days: &[Day]) -> Result<(), String> {
+    let archive = Archive {
+        days: days.to_vec(),
+    };
+    let json_string = serde_json::to_string_pretty(&archive).unwrap();
+    write_files(&[(path, json_string.into_bytes())])
+}
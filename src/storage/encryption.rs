@@ -0,0 +1,183 @@
+//! Encrypting the data file with a passphrase.
+//!
+//! The key is derived from the passphrase with Argon2id and the data is
+//! encrypted with AES-256-GCM. An encrypted file consists of
+//!
+//! - `MAGIC`, marking the file as encrypted
+//! - the salt for the key derivation
+//! - a check value derived together with the key, to tell a wrong passphrase
+//!   from a corrupted file
+//! - the nonce
+//! - the encrypted data, including the authentication tag
+
+use std::cell::RefCell;
+use std::path::Path;
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::Argon2;
+
+const MAGIC: &[u8] = b"timereport-encrypted-1\n";
+const SALT_LENGTH: usize = 16;
+const CHECK_LENGTH: usize = 32;
+const KEY_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 12;
+const HEADER_LENGTH: usize = MAGIC.len() + SALT_LENGTH + CHECK_LENGTH + NONCE_LENGTH;
+
+/// Whether the file starts like an encrypted file.
+pub fn is_encrypted(path: &Path) -> bool {
+    match std::fs::read(path) {
+        Ok(bytes) => bytes.starts_with(MAGIC),
+        Err(_) => false,
+    }
+}
+
+/// The key and the check value derived from the passphrase and a salt.
+struct DerivedKey {
+    salt: [u8; SALT_LENGTH],
+    key: [u8; KEY_LENGTH],
+    check: [u8; CHECK_LENGTH],
+}
+
+/// Encrypts and decrypts with a passphrase.
+///
+/// Deriving a key is slow on purpose, so the last derived key is kept and
+/// its salt is reused for the files written afterwards.
+pub struct Encryption {
+    passphrase: String,
+    derived_key: RefCell<Option<DerivedKey>>,
+}
+
+impl Encryption {
+    pub fn new(passphrase: &str) -> Self {
+        Self {
+            passphrase: passphrase.to_string(),
+            derived_key: RefCell::new(None),
+        }
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> Vec<u8> {
+        let derived_key = match self.derived_key.borrow_mut().take() {
+            Some(derived_key) => derived_key,
+            None => {
+                let mut salt = [0; SALT_LENGTH];
+                OsRng.fill_bytes(&mut salt);
+                self.derive_key(salt)
+            }
+        };
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&derived_key.key));
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext)
+            .expect("encrypting to memory does not fail");
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&derived_key.salt);
+        bytes.extend_from_slice(&derived_key.check);
+        bytes.extend_from_slice(&nonce);
+        bytes.extend(ciphertext);
+        *self.derived_key.borrow_mut() = Some(derived_key);
+        bytes
+    }
+
+    /// Decrypt the contents of the file `name`.
+    pub fn decrypt(&self, bytes: &[u8], name: &str) -> Result<Vec<u8>, String> {
+        let corrupted = || format!("{} is corrupted and cannot be decrypted", name);
+        if !bytes.starts_with(MAGIC) {
+            return Err(format!("{} is not encrypted", name));
+        }
+        if bytes.len() < HEADER_LENGTH {
+            return Err(corrupted());
+        }
+        let (salt, rest) = bytes[MAGIC.len()..].split_at(SALT_LENGTH);
+        let (check, rest) = rest.split_at(CHECK_LENGTH);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LENGTH);
+        let salt: [u8; SALT_LENGTH] = salt.try_into().expect("split at the salt length");
+        let derived_key = match self.derived_key.borrow_mut().take() {
+            Some(derived_key) if derived_key.salt == salt => derived_key,
+            _ => self.derive_key(salt),
+        };
+        if derived_key.check != check {
+            return Err(format!("Wrong passphrase for {}", name));
+        }
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&derived_key.key));
+        let plaintext = cipher.decrypt(Nonce::from_slice(nonce), ciphertext);
+        *self.derived_key.borrow_mut() = Some(derived_key);
+        plaintext.map_err(|_| corrupted())
+    }
+
+    fn derive_key(&self, salt: [u8; SALT_LENGTH]) -> DerivedKey {
+        let mut output = [0; KEY_LENGTH + CHECK_LENGTH];
+        Argon2::default()
+            .hash_password_into(self.passphrase.as_bytes(), &salt, &mut output)
+            .expect("the salt and output lengths are valid");
+        let (key, check) = output.split_at(KEY_LENGTH);
+        DerivedKey {
+            salt,
+            key: key.try_into().expect("split at the key length"),
+            check: check.try_into().expect("the rest is the check value"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use crate::storage::encryption::{Encryption, HEADER_LENGTH};
+
+    #[rstest]
+    fn round_trip() {
+        let encryption = Encryption::new("secret");
+
+        let bytes = encryption.encrypt(b"{\"days\": []}");
+
+        assert!(!bytes.windows(4).any(|window| window == b"days"));
+        assert_eq!(
+            Encryption::new("secret").decrypt(&bytes, "file"),
+            Ok(b"{\"days\": []}".to_vec())
+        );
+    }
+
+    #[rstest]
+    fn wrong_passphrase() {
+        let bytes = Encryption::new("secret").encrypt(b"data");
+
+        assert_eq!(
+            Encryption::new("guess").decrypt(&bytes, "file"),
+            Err("Wrong passphrase for file".to_string())
+        );
+    }
+
+    #[rstest]
+    #[case::changed_data(HEADER_LENGTH)]
+    #[case::changed_nonce(HEADER_LENGTH - 1)]
+    fn corrupted_file(#[case] index: usize) {
+        let encryption = Encryption::new("secret");
+        let mut bytes = encryption.encrypt(b"data");
+        bytes[index] ^= 1;
+
+        assert_eq!(
+            encryption.decrypt(&bytes, "file"),
+            Err("file is corrupted and cannot be decrypted".to_string())
+        );
+    }
+
+    #[rstest]
+    fn truncated_file() {
+        let encryption = Encryption::new("secret");
+        let bytes = encryption.encrypt(b"data");
+
+        assert_eq!(
+            encryption.decrypt(&bytes[..HEADER_LENGTH - 1], "file"),
+            Err("file is corrupted and cannot be decrypted".to_string())
+        );
+    }
+
+    #[rstest]
+    fn new_nonce_for_each_encryption() {
+        let encryption = Encryption::new("secret");
+
+        assert_ne!(encryption.encrypt(b"data"), encryption.encrypt(b"data"));
+    }
+}
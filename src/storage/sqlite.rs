@@ -0,0 +1,273 @@
+//! Storing the config in an SQLite database instead of a json file.
+//!
+//! The days, the undone days and the journals are stored as one row per
+//! entry, in the order of the log. All other fields of the config are stored
+//! in a key-value table with json values. Rows are converted from and to the
+//! json representation of the config, so that the two formats hold exactly
+//! the same data.
+
+use std::path::Path;
+
+use rusqlite::{params, Connection, Transaction};
+use serde_json::{Map, Value};
+
+use crate::config::Config;
+
+/// The fields of the config stored as rows of their own table
+const DAY_TABLES: [&str; 2] = ["days", "undone"];
+const JOURNAL_TABLES: [&str; 2] = ["journal", "undone_journal"];
+
+/// Whether the file extension selects the SQLite backend.
+pub fn is_sqlite(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|extension| extension.to_str()),
+        Some("sqlite" | "sqlite3" | "db")
+    )
+}
+
+pub fn load(path: &Path) -> Result<Config, String> {
+    let error =
+        |error: rusqlite::Error| format!("Failed to read {}: {}", path.to_string_lossy(), error);
+    let connection = Connection::open(path).map_err(error)?;
+    create_tables(&connection).map_err(error)?;
+    let mut object = Map::new();
+    let mut statement = connection
+        .prepare("SELECT key, value FROM settings")
+        .map_err(error)?;
+    let rows = statement
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(error)?;
+    for row in rows {
+        let (key, value) = row.map_err(error)?;
+        match serde_json::from_str(&value) {
+            Ok(value) => object.insert(key, value),
+            Err(_) => return Err(format!("Failed to parse {}", path.to_string_lossy())),
+        };
+    }
+    if object.is_empty() {
+        // A new database, as created when the file does not exist
+        return Ok(Config::new(Vec::new()));
+    }
+    for table in DAY_TABLES {
+        object.insert(
+            table.to_string(),
+            load_days(&connection, table).map_err(error)?,
+        );
+    }
+    for table in JOURNAL_TABLES {
+        object.insert(
+            table.to_string(),
+            load_journal(&connection, table).map_err(error)?,
+        );
+    }
+    match serde_json::from_value(Value::Object(object)) {
+        Ok(config) => Ok(config),
+        Err(_) => Err(format!("Failed to parse {}", path.to_string_lossy())),
+    }
+}
+
+/// Save the json representation of the config.
+pub fn save(mut object: Map<String, Value>, path: &Path) -> Result<(), String> {
+    let error = |error: rusqlite::Error| {
+        format!(
+            "Error writing to file {}: {}",
+            path.to_string_lossy(),
+            error
+        )
+    };
+    let mut connection = Connection::open(path).map_err(error)?;
+    create_tables(&connection).map_err(error)?;
+    // Everything is replaced in one transaction, so that a failed save leaves
+    // the previous data intact
+    let transaction = connection.transaction().map_err(error)?;
+    for table in DAY_TABLES {
+        let days = object.remove(table).unwrap_or_default();
+        save_days(&transaction, table, &days).map_err(error)?;
+    }
+    for table in JOURNAL_TABLES {
+        let entries = object.remove(table).unwrap_or_default();
+        save_journal(&transaction, table, &entries).map_err(error)?;
+    }
+    transaction
+        .execute("DELETE FROM settings", [])
+        .map_err(error)?;
+    for (key, value) in object {
+        transaction
+            .execute(
+                "INSERT INTO settings (key, value) VALUES (?1, ?2)",
+                params![key, value.to_string()],
+            )
+            .map_err(error)?;
+    }
+    transaction.commit().map_err(error)
+}
+
+fn create_tables(connection: &Connection) -> rusqlite::Result<()> {
+    connection.execute_batch(
+        "CREATE TABLE IF NOT EXISTS settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);",
+    )?;
+    for table in DAY_TABLES {
+        connection.execute_batch(&format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                position INTEGER PRIMARY KEY,
+                date TEXT NOT NULL,
+                start TEXT,
+                stop TEXT,
+                lunch INTEGER,
+                projects TEXT NOT NULL,
+                cleared TEXT,
+                overtime INTEGER
+            );",
+            table
+        ))?;
+        add_missing_column(connection, table, "overtime", "INTEGER")?;
+    }
+    for table in JOURNAL_TABLES {
+        connection.execute_batch(&format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                position INTEGER PRIMARY KEY,
+                day_count INTEGER NOT NULL,
+                timestamp TEXT,
+                command TEXT NOT NULL,
+                projects TEXT NOT NULL
+            );",
+            table
+        ))?;
+    }
+    Ok(())
+}
+
+/// Add a column that was added to a table after the database was created.
+fn add_missing_column(
+    connection: &Connection,
+    table: &str,
+    column: &str,
+    column_type: &str,
+) -> rusqlite::Result<()> {
+    let mut statement = connection.prepare(&format!("PRAGMA table_info({})", table))?;
+    let names = statement
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    if !names.iter().any(|name| name == column) {
+        connection.execute_batch(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {};",
+            table, column, column_type
+        ))?;
+    }
+    Ok(())
+}
+
+/// The value of a text field for a column, or NULL if the field is missing or
+/// null.
+fn column(object: &Value, field: &str) -> Option<String> {
+    object
+        .get(field)
+        .and_then(|value| value.as_str())
+        .map(|text| text.to_string())
+}
+
+/// The value of a field as json for a column, or NULL if the field is missing
+/// or null.
+fn json_column_text(object: &Value, field: &str) -> Option<String> {
+    match object.get(field) {
+        None | Some(Value::Null) => None,
+        Some(value) => Some(value.to_string()),
+    }
+}
+
+fn save_days(transaction: &Transaction, table: &str, days: &Value) -> rusqlite::Result<()> {
+    transaction.execute(&format!("DELETE FROM {}", table), [])?;
+    let mut statement = transaction.prepare(&format!(
+        "INSERT INTO {} (position, date, start, stop, lunch, projects, cleared, overtime)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        table
+    ))?;
+    for (position, day) in days.as_array().into_iter().flatten().enumerate() {
+        statement.execute(params![
+            position,
+            column(day, "date"),
+            column(day, "start"),
+            column(day, "stop"),
+            day.get("lunch").and_then(|lunch| lunch.as_i64()),
+            json_column_text(day, "projects"),
+            json_column_text(day, "cleared"),
+            day.get("overtime").and_then(|overtime| overtime.as_i64()),
+        ])?;
+    }
+    Ok(())
+}
+
+fn load_days(connection: &Connection, table: &str) -> rusqlite::Result<Value> {
+    let mut statement = connection.prepare(&format!(
+        "SELECT date, start, stop, lunch, projects, cleared, overtime FROM {} ORDER BY position",
+        table
+    ))?;
+    let rows = statement.query_map([], |row| {
+        let mut day = Map::new();
+        day.insert("date".to_string(), Value::String(row.get(0)?));
+        for (index, field) in ["start", "stop"].iter().enumerate() {
+            let time: Option<String> = row.get(index + 1)?;
+            day.insert(field.to_string(), time.map_or(Value::Null, Value::String));
+        }
+        let lunch: Option<i64> = row.get(3)?;
+        day.insert("lunch".to_string(), lunch.map_or(Value::Null, Value::from));
+        day.insert("projects".to_string(), json_column(row.get(4)?));
+        let cleared: Option<String> = row.get(5)?;
+        if cleared.is_some() {
+            day.insert("cleared".to_string(), json_column(cleared));
+        }
+        let overtime: Option<i64> = row.get(6)?;
+        if let Some(overtime) = overtime {
+            day.insert("overtime".to_string(), Value::from(overtime));
+        }
+        Ok(Value::Object(day))
+    })?;
+    Ok(Value::Array(rows.collect::<rusqlite::Result<_>>()?))
+}
+
+fn save_journal(transaction: &Transaction, table: &str, entries: &Value) -> rusqlite::Result<()> {
+    transaction.execute(&format!("DELETE FROM {}", table), [])?;
+    let mut statement = transaction.prepare(&format!(
+        "INSERT INTO {} (position, day_count, timestamp, command, projects)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        table
+    ))?;
+    for (position, entry) in entries.as_array().into_iter().flatten().enumerate() {
+        statement.execute(params![
+            position,
+            entry["day_count"].as_u64().unwrap_or_default(),
+            column(entry, "timestamp"),
+            column(entry, "command").unwrap_or_default(),
+            json_column_text(entry, "projects").unwrap_or_else(|| "[]".to_string()),
+        ])?;
+    }
+    Ok(())
+}
+
+fn load_journal(connection: &Connection, table: &str) -> rusqlite::Result<Value> {
+    let mut statement = connection.prepare(&format!(
+        "SELECT day_count, timestamp, command, projects FROM {} ORDER BY position",
+        table
+    ))?;
+    let rows = statement.query_map([], |row| {
+        let mut entry = Map::new();
+        entry.insert("day_count".to_string(), Value::from(row.get::<_, u64>(0)?));
+        let timestamp: Option<String> = row.get(1)?;
+        entry.insert(
+            "timestamp".to_string(),
+            timestamp.map_or(Value::Null, Value::String),
+        );
+        entry.insert("command".to_string(), Value::String(row.get(2)?));
+        entry.insert("projects".to_string(), json_column(row.get(3)?));
+        Ok(Value::Object(entry))
+    })?;
+    Ok(Value::Array(rows.collect::<rusqlite::Result<_>>()?))
+}
+
+/// Parse a column holding json, e.g. the projects of a day.
+fn json_column(text: Option<String>) -> Value {
+    text.and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or(Value::Null)
+}
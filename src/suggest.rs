@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta};
+use serde::{Deserialize, Serialize};
+
+/// A local git repository whose commits are used to suggest project times.
+///
+/// Commits on branches matching one of the patterns in `branches` belong to
+/// the project of that pattern, and other commits to `project`. Commits that
+/// belong to no project count as time on the default project.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GitRepository {
+    pub path: String,
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub branches: Vec<BranchProject>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BranchProject {
+    /// A glob pattern as accepted by `git log --branches`, e.g. "feature/abc-*"
+    pub pattern: String,
+    pub project: String,
+}
+
+/// A commit made between start and stop, and the project it belongs to.
+#[derive(Debug, PartialEq)]
+pub struct Commit {
+    pub time: NaiveDateTime,
+    pub project: Option<String>,
+}
+
+/// Find the commits in the repository made by the configured git user
+/// between start and stop.
+pub fn commits(
+    repository: &GitRepository,
+    start: NaiveDateTime,
+    stop: NaiveDateTime,
+) -> Result<Vec<Commit>, String> {
+    let path = Path::new(&repository.path);
+    let author = git(path, &["config", "user.email"]).unwrap_or_default();
+    let since = format!("--since={}", start.format("%Y-%m-%dT%H:%M:%S"));
+    let until = format!("--until={}", stop.format("%Y-%m-%dT%H:%M:%S"));
+    let author_argument = format!("--author={}", author.trim());
+    let log = |selection: &[&str]| -> Result<Vec<(String, NaiveDateTime)>, String> {
+        let mut args = vec!["log", "--format=%H %at", &since, &until];
+        args.extend(selection);
+        if !author.trim().is_empty() {
+            args.push(&author_argument);
+        }
+        let output = git(path, &args)?;
+        Ok(output
+            .lines()
+            .filter_map(|line| line.split_once(' '))
+            .filter_map(|(hash, timestamp)| {
+                let time = DateTime::from_timestamp(timestamp.parse().ok()?, 0)?;
+                Some((hash.to_string(), time.with_timezone(&Local).naive_local()))
+            })
+            .collect())
+    };
+
+    let mut project_from_hash: HashMap<String, String> = HashMap::new();
+    for branch_project in &repository.branches {
+        // Only the commits that are not also on a branch that does not match,
+        // so that commits on the main branch do not belong to every feature
+        // branch
+        let branches = format!("--branches={}", branch_project.pattern);
+        let exclude = format!("--exclude={}", branch_project.pattern);
+        for (hash, _) in log(&[&branches, "--not", &exclude, "--branches"])? {
+            project_from_hash
+                .entry(hash)
+                .or_insert(branch_project.project.clone());
+        }
+    }
+    Ok(log(&["--all"])?
+        .into_iter()
+        // git filters on committer date, but the author date is when the
+        // work was done
+        .filter(|(_, time)| *time >= start && *time <= stop)
+        .map(|(hash, time)| Commit {
+            time,
+            project: project_from_hash
+                .get(&hash)
+                .cloned()
+                .or(repository.project.clone()),
+        })
+        .collect())
+}
+
+fn git(path: &Path, args: &[&str]) -> Result<String, String> {
+    let output = match Command::new("git").arg("-C").arg(path).args(args).output() {
+        Ok(output) => output,
+        Err(error) => return Err(format!("Could not run git: {}", error)),
+    };
+    if !output.status.success() {
+        return Err(format!(
+            "git failed in {}: {}",
+            path.to_string_lossy(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Estimate the time spent on each project from commit times.
+///
+/// The time from the start of the day, or from the previous commit, until a
+/// commit is attributed to the project of that commit. The result is scaled
+/// down to fit in the time worked, i.e. stop - start - lunch, and rounded to
+/// quarters of an hour.
+pub fn estimate_project_times(
+    date: NaiveDate,
+    start: NaiveTime,
+    stop: NaiveTime,
+    lunch: TimeDelta,
+    mut commits: Vec<Commit>,
+) -> Vec<(String, TimeDelta)> {
+    commits.sort_by_key(|commit| commit.time);
+    let mut previous_time = date.and_time(start);
+    let mut time_from_project: HashMap<String, TimeDelta> = HashMap::new();
+    let mut total = TimeDelta::zero();
+    for commit in commits {
+        let interval = commit.time - previous_time;
+        previous_time = commit.time;
+        total += interval;
+        if let Some(project) = commit.project {
+            *time_from_project
+                .entry(project)
+                .or_insert(TimeDelta::zero()) += interval;
+        }
+    }
+    let worked = stop - start - lunch;
+    let scale = if total > worked && !total.is_zero() {
+        worked.num_seconds() as f64 / total.num_seconds() as f64
+    } else {
+        1.0
+    };
+    let mut project_times: Vec<(String, TimeDelta)> = time_from_project
+        .into_iter()
+        .map(|(project, timedelta)| {
+            let quarters = (timedelta.num_seconds() as f64 * scale / 900.0).round() as i64;
+            (
+                project,
+                TimeDelta::try_seconds(quarters * 900).expect("less than a day"),
+            )
+        })
+        .filter(|(_, timedelta)| !timedelta.is_zero())
+        .collect();
+    project_times.sort();
+    project_times
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{NaiveDate, NaiveTime, TimeDelta};
+    use rstest::rstest;
+
+    use crate::suggest::{estimate_project_times, Commit};
+
+    fn commit(time: &str, project: Option<&str>) -> Commit {
+        Commit {
+            time: NaiveDate::from_ymd_opt(2025, 4, 15)
+                .expect("")
+                .and_time(NaiveTime::parse_from_str(time, "%H:%M").expect("")),
+            project: project.map(|project| project.to_string()),
+        }
+    }
+
+    fn time(text: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(text, "%H:%M").expect("")
+    }
+
+    #[rstest]
+    fn time_until_commit_belongs_to_project_of_commit() {
+        let commits = vec![
+            commit("11:00", Some("b")),
+            commit("10:00", Some("a")),
+            commit("12:10", None),
+        ];
+
+        let project_times = estimate_project_times(
+            NaiveDate::from_ymd_opt(2025, 4, 15).expect(""),
+            time("08:00"),
+            time("17:00"),
+            TimeDelta::zero(),
+            commits,
+        );
+
+        assert_eq!(
+            project_times,
+            vec![
+                ("a".to_string(), TimeDelta::try_hours(2).expect("")),
+                ("b".to_string(), TimeDelta::try_hours(1).expect("")),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn estimate_is_scaled_to_worked_time() {
+        let commits = vec![commit("16:00", Some("a"))];
+
+        let project_times = estimate_project_times(
+            NaiveDate::from_ymd_opt(2025, 4, 15).expect(""),
+            time("08:00"),
+            time("16:00"),
+            TimeDelta::try_hours(1).expect(""),
+            commits,
+        );
+
+        assert_eq!(
+            project_times,
+            vec![("a".to_string(), TimeDelta::try_hours(7).expect(""))]
+        );
+    }
+}
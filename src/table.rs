@@ -1,5 +1,8 @@
 use crate::day::Day;
+use crate::lunch::LunchRules;
 use crate::naive_date::one_date_per_week;
+use crate::overtime::{Rules, CATEGORIES};
+use crate::settings::Settings;
 use crate::traits::Parsable;
 use chrono::prelude::*;
 use chrono::TimeDelta;
@@ -17,8 +20,8 @@ pub fn create_terminal_table(
     day_from_date: &HashMap<NaiveDate, Day>,
     previous_day_from_date: &HashMap<NaiveDate, Day>,
     show_weekend: bool,
-    project_names: &Vec<String>,
-    working_time_per_day: &TimeDelta,
+    settings: &Settings,
+    now: NaiveDateTime,
 ) -> String {
     one_date_per_week(first_date, last_date)
         .iter()
@@ -28,8 +31,8 @@ pub fn create_terminal_table(
                 day_from_date,
                 previous_day_from_date,
                 show_weekend,
-                project_names,
-                working_time_per_day,
+                settings,
+                now,
             )
         })
         .collect::<Vec<_>>()
@@ -41,30 +44,34 @@ fn create_terminal_table_string(
     day_from_date: &HashMap<NaiveDate, Day>,
     previous_day_from_date: &HashMap<NaiveDate, Day>,
     show_weekend: bool,
-    project_names: &Vec<String>,
-    working_time_per_day: &TimeDelta,
+    settings: &Settings,
+    now: NaiveDateTime,
 ) -> String {
-    let mut current_table = create_table(
-        *date_to_display,
-        day_from_date,
-        show_weekend,
-        project_names,
-        working_time_per_day,
-    );
-    let previous_table = create_table(
+    let (mut current_table, provisional_cells) =
+        create_table_at(*date_to_display, day_from_date, show_weekend, settings, now);
+    let (previous_table, _) = create_table_at(
         *date_to_display,
         previous_day_from_date,
         show_weekend,
-        project_names,
-        working_time_per_day,
+        settings,
+        now,
     );
 
     let records1 = current_table.get_records().to_vec();
     let records2 = previous_table.get_records().iter().collect::<Vec<_>>();
 
-    for (row_idx, (row1, row2)) in records1.iter().zip(records2.iter()).enumerate() {
-        for (col_idx, (cell1, cell2)) in row1.iter().zip(row2.iter()).enumerate() {
-            if cell1.text() != cell2.text() {
+    for (row_idx, row1) in records1.iter().enumerate() {
+        // Rows like overtime and leave at are not always there, so compare
+        // with the row of the same name
+        let row2 = match records2.get(row_idx) {
+            Some(row2) if row2[0].text() == row1[0].text() => Some(row2),
+            _ => records2
+                .iter()
+                .find(|row2| row2[0].text() == row1[0].text()),
+        };
+        for (col_idx, cell1) in row1.iter().enumerate() {
+            let cell2 = row2.and_then(|row2| row2.get(col_idx));
+            if cell1.text() != cell2.map_or("", |cell2| cell2.text()) {
                 current_table.with(Colorization::exact(
                     [Color::BOLD],
                     Cell::new(row_idx, col_idx),
@@ -72,6 +79,12 @@ fn create_terminal_table_string(
             }
         }
     }
+    for (row_idx, col_idx) in provisional_cells {
+        current_table.with(Colorization::exact(
+            [Color::FG_BRIGHT_BLACK],
+            Cell::new(row_idx, col_idx),
+        ));
+    }
 
     current_table
         .with(
@@ -86,45 +99,190 @@ pub fn create_table(
     date_to_display: NaiveDate,
     day_from_date: &HashMap<NaiveDate, Day>,
     show_weekend: bool,
-    project_names: &Vec<String>,
-    working_time_per_day: &TimeDelta,
+    settings: &Settings,
 ) -> tabled::Table {
-    let mut builder = Builder::default();
     let week_days = days_in_week_of(date_to_display, show_weekend);
-    builder.push_record(date_row(&week_days, "%Y-%m-%d")); // date
-    builder.push_record(date_row(&week_days, "%A")); // weekday
+    Builder::from(table_rows(&week_days, day_from_date, settings)).build()
+}
+
+/// Like `create_table`, but when today has a start and no stop yet, the
+/// values of today that need a stop are computed with the current time as
+/// stop, and a "Leave at" row shows the stop that gives no flex. Also
+/// returns the row and column of these provisional cells.
+fn create_table_at(
+    date_to_display: NaiveDate,
+    day_from_date: &HashMap<NaiveDate, Day>,
+    show_weekend: bool,
+    settings: &Settings,
+    now: NaiveDateTime,
+) -> (tabled::Table, Vec<(usize, usize)>) {
+    let week_days = days_in_week_of(date_to_display, show_weekend);
+    let rows = table_rows(&week_days, day_from_date, settings);
+    let today = now.date();
+    let column = match week_days.iter().position(|date| *date == today) {
+        Some(index) => index + 1,
+        None => return (Builder::from(rows).build(), Vec::new()),
+    };
+    let (day, start) = match day_from_date.get(&today) {
+        Some(day) if day.stop.is_none() && day.start.is_some() => {
+            (day, day.start.expect("checked above"))
+        }
+        _ => return (Builder::from(rows).build(), Vec::new()),
+    };
+
+    // Whole minutes, like the recorded times
+    let now = now
+        .with_second(0)
+        .and_then(|now| now.with_nanosecond(0))
+        .expect("zero seconds are valid");
+    let mut provisional_cells = Vec::new();
+    let mut rows = if now.time() > start {
+        let mut provisional_day_from_date = day_from_date.clone();
+        provisional_day_from_date.insert(
+            today,
+            Day {
+                stop: Some(now.time()),
+                ..day.clone()
+            },
+        );
+        let provisional_rows = table_rows(&week_days, &provisional_day_from_date, settings);
+        for (row_index, row) in provisional_rows.iter().enumerate() {
+            let recorded_row = rows.iter().find(|recorded_row| recorded_row[0] == row[0]);
+            if recorded_row.map(|recorded_row| &recorded_row[column]) != Some(&row[column]) {
+                provisional_cells.push((row_index, column));
+            }
+        }
+        provisional_rows
+    } else {
+        rows
+    };
+
+    let mut leave_at_row = vec!["".to_string(); rows[0].len()];
+    leave_at_row[0] = "Leave at".to_string();
+    leave_at_row[column] = match leave_at(day, settings) {
+        Some(leave_at) => leave_at.format("%H:%M").to_string(),
+        None => "after 24:00".to_string(),
+    };
+    // Before the flex, which is the last row
+    let leave_at_index = rows.len() - 1;
+    rows.insert(leave_at_index, leave_at_row);
+    for (row_index, _) in provisional_cells.iter_mut() {
+        if *row_index >= leave_at_index {
+            *row_index += 1;
+        }
+    }
+    provisional_cells.push((leave_at_index, column));
+    (Builder::from(rows).build(), provisional_cells)
+}
+
+/// The stop that gives no flex, with the lunch implied for that stop, or
+/// None if it is after midnight. A later stop can require a longer break,
+/// which moves the stop again, so this is repeated until the lunch stays
+/// the same.
+fn leave_at(day: &Day, settings: &Settings) -> Option<NaiveTime> {
+    let start = day.start?;
+    let working_time = settings.working_time_per_day + day.overtime.unwrap_or(TimeDelta::zero());
+    let mut lunch = TimeDelta::zero();
+    let mut stop = start;
+    // Each round can at most add the next break
+    for _ in 0..settings.lunch.breaks.len() + 2 {
+        let (next_stop, overflow) = start.overflowing_add_signed(working_time + lunch);
+        if overflow != 0 {
+            return None;
+        }
+        stop = next_stop;
+        let day_from_date = HashMap::from([(
+            day.date,
+            Day {
+                stop: Some(stop),
+                ..day.clone()
+            },
+        )]);
+        let implied_lunch = with_implied_lunch(&day_from_date, &settings.lunch)[&day.date]
+            .lunch
+            .unwrap_or(TimeDelta::zero());
+        if implied_lunch == lunch {
+            break;
+        }
+        lunch = implied_lunch;
+    }
+    Some(stop)
+}
+
+/// The rows of the table for the week, with a header row for the dates and
+/// one for the weekdays.
+fn table_rows(
+    week_days: &Vec<NaiveDate>,
+    day_from_date: &HashMap<NaiveDate, Day>,
+    settings: &Settings,
+) -> Vec<Vec<String>> {
+    let project_names = &settings.project_names;
+    let working_time_per_day = &settings.working_time_per_day;
+    let overtime_rules = &settings.overtime;
+    let recorded_day_from_date = day_from_date;
+    // The times below the lunch use the implied lunch of days without one
+    let day_from_date = &with_implied_lunch(day_from_date, &settings.lunch);
+    let mut rows = Vec::new();
+    rows.push(date_row(week_days, "%Y-%m-%d")); // date
+    rows.push(date_row(week_days, "%A")); // weekday
 
     let mut start_row = vec!["start".to_string()];
-    start_row.extend(starts(&week_days, &day_from_date));
-    builder.push_record(start_row);
+    start_row.extend(starts(week_days, day_from_date));
+    rows.push(start_row);
 
     let mut stop_row = vec!["stop".to_string()];
-    stop_row.extend(stops(&week_days, &day_from_date));
-    builder.push_record(stop_row);
+    stop_row.extend(stops(week_days, day_from_date));
+    rows.push(stop_row);
 
     let mut lunch_row = vec!["lunch".to_string()];
-    lunch_row.extend(lunches(&week_days, &day_from_date));
-    builder.push_record(lunch_row);
+    lunch_row.extend(lunches(week_days, recorded_day_from_date, &settings.lunch));
+    rows.push(lunch_row);
 
     let mut default_project_row = vec!["1. Default project".to_string()];
-    default_project_row.extend(default_project_timedeltas(&week_days, &day_from_date));
-    builder.push_record(default_project_row);
+    default_project_row.extend(default_project_timedeltas(week_days, day_from_date));
+    rows.push(default_project_row);
 
     for (index, project_name) in project_names.iter().enumerate() {
         let mut row = vec![format!("{}. {}", index + 2, project_name.clone())];
-        row.extend(project_timedeltas(
-            &project_name,
-            &week_days,
-            &day_from_date,
-        ));
-        builder.push_record(row);
+        row.extend(project_timedeltas(project_name, week_days, day_from_date));
+        rows.push(row);
+    }
+
+    // Only the categories with overtime this week, to keep the table short
+    for category in CATEGORIES {
+        let timedeltas: Vec<Option<TimeDelta>> = week_days
+            .iter()
+            .map(|date| {
+                day_from_date
+                    .get(date)
+                    .and_then(|day| overtime_rules.classify(day))
+                    .filter(|(other, _)| *other == category)
+                    .map(|(_, timedelta)| timedelta)
+            })
+            .collect();
+        if timedeltas.iter().any(|timedelta| timedelta.is_some()) {
+            let mut row = vec![format!("Overtime {}", category.name())];
+            row.extend(timedeltas.iter().map(|timedelta| match timedelta {
+                Some(timedelta) => timedelta.to_hhmm(),
+                None => "".to_string(),
+            }));
+            rows.push(row);
+        }
     }
 
     let mut flex_row = vec!["Flex".to_string()];
-    flex_row.extend(flex(&week_days, &day_from_date, *working_time_per_day));
-    builder.push_record(flex_row);
+    flex_row.extend(week_days.iter().map(|date| {
+        match day_from_date
+            .get(date)
+            .and_then(|day| flex_time(day, *working_time_per_day, overtime_rules))
+        {
+            Some(timedelta) => timedelta.to_hhmm(),
+            None => "".to_string(),
+        }
+    }));
+    rows.push(flex_row);
 
-    builder.build()
+    rows
 }
 
 fn date_row(week_days: &Vec<NaiveDate>, format: &str) -> Vec<String> {
@@ -162,19 +320,35 @@ fn stops(week_days: &Vec<NaiveDate>, days: &HashMap<NaiveDate, Day>) -> Vec<Stri
         .collect()
 }
 
-fn lunches(week_days: &Vec<NaiveDate>, days: &HashMap<NaiveDate, Day>) -> Vec<String> {
+/// The recorded lunches, and the implied lunches in parentheses.
+fn lunches(
+    week_days: &Vec<NaiveDate>,
+    days: &HashMap<NaiveDate, Day>,
+    lunch_rules: &LunchRules,
+) -> Vec<String> {
     week_days
         .iter()
         .map(|date| match days.get(date) {
             None => "".to_string(),
-            Some(day) => match day.lunch {
-                None => "".to_string(),
-                Some(timedelta) => timedelta.to_hhmm(),
+            Some(day) => match (day.lunch, lunch_rules.implied_lunch(day)) {
+                (Some(timedelta), _) => timedelta.to_hhmm(),
+                (None, Some(timedelta)) => format!("({})", timedelta.to_hhmm()),
+                (None, None) => "".to_string(),
             },
         })
         .collect()
 }
 
+pub(crate) fn with_implied_lunch(
+    day_from_date: &HashMap<NaiveDate, Day>,
+    lunch_rules: &LunchRules,
+) -> HashMap<NaiveDate, Day> {
+    day_from_date
+        .iter()
+        .map(|(date, day)| (*date, lunch_rules.fill(day)))
+        .collect()
+}
+
 fn default_project_timedeltas(
     week_days: &Vec<NaiveDate>,
     days: &HashMap<NaiveDate, Day>,
@@ -191,7 +365,7 @@ fn default_project_timedeltas(
         .collect()
 }
 
-fn default_project_time(day: &Day) -> Option<TimeDelta> {
+pub(crate) fn default_project_time(day: &Day) -> Option<TimeDelta> {
     match (day.start, day.stop, day.lunch) {
         (Some(start), Some(stop), Some(lunch)) => {
             Some(stop - start - lunch - day.projects.values().sum())
@@ -217,26 +391,137 @@ fn project_timedeltas(
         .collect()
 }
 
-fn flex(
-    week_days: &Vec<NaiveDate>,
-    days: &HashMap<NaiveDate, Day>,
+/// The time worked beyond the working time of the day, not counting
+/// overtime. There is no flex on days when all time is overtime.
+pub(crate) fn flex_time(
+    day: &Day,
     working_time_per_day: TimeDelta,
-) -> Vec<String> {
-    week_days
+    overtime_rules: &Rules,
+) -> Option<TimeDelta> {
+    if overtime_rules.is_overtime_day(day) {
+        return None;
+    }
+    let overtime = match overtime_rules.classify(day) {
+        Some((_, timedelta)) => timedelta,
+        None => TimeDelta::zero(),
+    };
+    match (day.start, day.stop, day.lunch) {
+        (Some(start), Some(stop), Some(lunch)) => {
+            Some(stop - start - lunch - overtime - working_time_per_day)
+        }
+        _ => None,
+    }
+}
+
+/// The flex and the overtime of each category from the first to the last
+/// date, with the overtime weighted by the compensation multipliers. None
+/// if there is no overtime.
+pub fn create_overtime_summary(
+    first_date: NaiveDate,
+    last_date: NaiveDate,
+    day_from_date: &HashMap<NaiveDate, Day>,
+    settings: &Settings,
+) -> Option<String> {
+    let working_time_per_day = &settings.working_time_per_day;
+    let overtime_rules = &settings.overtime;
+    let day_from_date = &with_implied_lunch(day_from_date, &settings.lunch);
+    let days: Vec<&Day> = first_date
+        .iter_days()
+        .take_while(|date| *date <= last_date)
+        .filter_map(|date| day_from_date.get(&date))
+        .collect();
+    let (totals, weighted) = overtime_rules.totals(days.iter().copied());
+    if totals.iter().all(|(_, total)| total.is_zero()) {
+        return None;
+    }
+    let flex: TimeDelta = days
         .iter()
-        .map(|date| match days.get(date) {
-            None => "".to_string(),
-            Some(day) => match (day.start, day.stop, day.lunch) {
-                (Some(start), Some(stop), Some(lunch)) => {
-                    (stop - start - lunch - working_time_per_day).to_hhmm()
+        .filter_map(|day| flex_time(day, *working_time_per_day, overtime_rules))
+        .sum();
+    let mut builder = Builder::default();
+    builder.push_record(["".to_string(), first_date.format("%Y-%m").to_string()]);
+    builder.push_record(["Flex".to_string(), flex.to_hhmm()]);
+    for (category, total) in totals.iter().filter(|(_, total)| !total.is_zero()) {
+        builder.push_record([
+            format!(
+                "Overtime {} (x{})",
+                category.name(),
+                overtime_rules.multiplier(*category)
+            ),
+            total.to_hhmm(),
+        ]);
+    }
+    builder.push_record(["Weighted overtime".to_string(), weighted.to_hhmm()]);
+    Some(
+        builder
+            .build()
+            .with(
+                Style::rounded()
+                    .remove_horizontals()
+                    .horizontals([(1, HorizontalLine::inherit(Style::modern()))]),
+            )
+            .to_string(),
+    )
+}
+
+/// The time worked on a day, i.e. stop - start - lunch. A missing lunch
+/// counts as no lunch, so that the time is never underestimated.
+pub(crate) fn worked_time(day: &Day) -> Option<TimeDelta> {
+    match (day.start, day.stop) {
+        (Some(start), Some(stop)) => Some(stop - start - day.lunch.unwrap_or(TimeDelta::zero())),
+        _ => None,
+    }
+}
+
+/// A table with the time worked each day of the week in each profile, and
+/// the total over all profiles.
+pub fn create_combined_table(
+    date_to_display: NaiveDate,
+    profiles: &[(String, HashMap<NaiveDate, Day>)],
+    show_weekend: bool,
+) -> String {
+    let week_days = days_in_week_of(date_to_display, show_weekend);
+    let mut builder = Builder::default();
+    let mut header = date_row(&week_days, "%Y-%m-%d");
+    header.push("Week".to_string());
+    builder.push_record(header);
+    let mut weekday_row = date_row(&week_days, "%A");
+    weekday_row.push("".to_string());
+    builder.push_record(weekday_row);
+
+    let mut totals = vec![TimeDelta::zero(); week_days.len()];
+    for (name, day_from_date) in profiles {
+        let mut row = vec![name.clone()];
+        let mut week_total = TimeDelta::zero();
+        for (index, date) in week_days.iter().enumerate() {
+            match day_from_date.get(date).and_then(worked_time) {
+                Some(timedelta) => {
+                    totals[index] += timedelta;
+                    week_total += timedelta;
+                    row.push(timedelta.to_hhmm());
                 }
-                _ => "".to_string(),
-            },
-        })
-        .collect()
+                None => row.push("".to_string()),
+            }
+        }
+        row.push(week_total.to_hhmm());
+        builder.push_record(row);
+    }
+    let mut total_row = vec!["Total".to_string()];
+    total_row.extend(totals.iter().map(|timedelta| timedelta.to_hhmm()));
+    total_row.push(totals.iter().sum::<TimeDelta>().to_hhmm());
+    builder.push_record(total_row);
+
+    let row_count = profiles.len() + 3;
+    builder
+        .build()
+        .with(Style::rounded().remove_horizontals().horizontals([
+            (2, HorizontalLine::inherit(Style::modern())),
+            (row_count - 1, HorizontalLine::inherit(Style::modern())),
+        ]))
+        .to_string()
 }
 
-fn days_in_week_of(date: NaiveDate, show_weekend: bool) -> Vec<NaiveDate> {
+pub(crate) fn days_in_week_of(date: NaiveDate, show_weekend: bool) -> Vec<NaiveDate> {
     let offset = date.weekday().num_days_from_monday();
     let timedelta_to_last_monday = TimeDelta::try_days(-i64::from(offset)).unwrap();
     let date_of_last_monday = date + timedelta_to_last_monday;
@@ -65,20 +65,53 @@ impl Parsable for TimeDelta {
     }
 }
 
-pub fn serialize_timedelta<S>(timedelta: &TimeDelta, serializer: S) -> Result<S::Ok, S::Error>
+/// Serialize as "HH:MM", for settings that are edited by hand.
+pub fn serialize_hhmm_timedelta<S>(timedelta: &TimeDelta, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
-    serializer.serialize_i64(timedelta.num_seconds())
+    serializer.serialize_str(&timedelta.to_hhmm())
 }
 
-pub fn deserialize_timedelta<'de, D>(deserializer: D) -> Result<TimeDelta, D::Error>
+/// Deserialize either seconds, as in older files, or a string like "7:45".
+pub fn deserialize_seconds_or_hhmm_timedelta<'de, D>(deserializer: D) -> Result<TimeDelta, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let int_or_none = Option::deserialize(deserializer)?;
-    let timedelta_or_none = TimeDelta::try_seconds(int_or_none.unwrap()).unwrap();
-    Ok(timedelta_or_none)
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum SecondsOrText {
+        Seconds(i64),
+        Text(String),
+    }
+    match SecondsOrText::deserialize(deserializer)? {
+        SecondsOrText::Seconds(seconds) => TimeDelta::try_seconds(seconds)
+            .ok_or_else(|| serde::de::Error::custom("seconds out of range")),
+        SecondsOrText::Text(text) => TimeDelta::from_str(&text).map_err(serde::de::Error::custom),
+    }
+}
+
+/// Only called for Some, together with `skip_serializing_if = "Option::is_none"`.
+pub fn serialize_option_hhmm_timedelta<S>(
+    timedelta: &Option<TimeDelta>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match timedelta {
+        Some(timedelta) => serialize_hhmm_timedelta(timedelta, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+pub fn deserialize_option_hhmm_timedelta<'de, D>(
+    deserializer: D,
+) -> Result<Option<TimeDelta>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_seconds_or_hhmm_timedelta(deserializer).map(Some)
 }
 
 pub fn serialize_option_timedelta<S>(
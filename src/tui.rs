@@ -0,0 +1,555 @@
+//! A full-screen terminal UI for browsing and editing the weeks, `t tui`.
+//!
+//! The table is the same as the one printed by the other commands, and
+//! every edit is added to the log like the corresponding command, so it can
+//! be undone with `t undo` afterwards.
+
+use std::collections::HashMap;
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, TimeDelta};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Cell, Paragraph, Row, Table};
+use ratatui::Frame;
+use tabled::grid::records::vec_records::Cell as _;
+
+use crate::config::Config;
+use crate::day::{Day, Field};
+use crate::naive_date::last_day_of_month;
+use crate::storage::Storage;
+use crate::table::{create_table, days_in_week_of, flex_time, with_implied_lunch};
+use crate::traits::Parsable;
+
+/// The rows of the table before the first row with times: the dates and
+/// the weekdays.
+const HEADER_ROW_COUNT: usize = 2;
+const START_ROW: usize = 2;
+const STOP_ROW: usize = 3;
+const LUNCH_ROW: usize = 4;
+/// The first project after the default project
+const FIRST_PROJECT_ROW: usize = 6;
+
+const HELP: &str =
+    "←→↑↓ move  Enter edit  Del clear  PgUp/PgDn week  t today  w weekend  u undo  r redo  q quit";
+
+/// Run the UI until the user quits. Returns a date in the week that was
+/// shown last.
+///
+/// Edits are recorded in the journal with the time the UI was started.
+pub fn run(config: Config, storage: &dyn Storage, now: NaiveDateTime) -> Result<NaiveDate, String> {
+    let mut app = App::new(config, storage, now);
+    let mut terminal = match ratatui::try_init() {
+        Ok(terminal) => terminal,
+        Err(error) => {
+            ratatui::restore();
+            return Err(format!("Could not start the terminal UI: {}", error));
+        }
+    };
+    let result = loop {
+        if let Err(error) = terminal.draw(|frame| app.draw(frame)) {
+            break Err(error.to_string());
+        }
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                if app.handle_key(key) {
+                    break Ok(app.date);
+                }
+            }
+            Ok(_) => (),
+            Err(error) => break Err(error.to_string()),
+        }
+    };
+    ratatui::restore();
+    result
+}
+
+struct App<'a> {
+    config: Config,
+    storage: &'a dyn Storage,
+    now: NaiveDateTime,
+    today: NaiveDate,
+    /// A date in the week that is shown
+    date: NaiveDate,
+    show_weekend: bool,
+    /// The selected row of the table, counting the header rows
+    row: usize,
+    /// The selected day of the week
+    column: usize,
+    /// The text being entered for the selected cell
+    input: Option<String>,
+    /// The result of the last edit, or an error
+    message: String,
+}
+
+impl<'a> App<'a> {
+    fn new(config: Config, storage: &'a dyn Storage, now: NaiveDateTime) -> Self {
+        let today = now.date();
+        Self {
+            config,
+            storage,
+            now,
+            today,
+            date: today,
+            show_weekend: matches!(today.weekday(), chrono::Weekday::Sat | chrono::Weekday::Sun),
+            row: START_ROW,
+            column: today.weekday().num_days_from_monday() as usize,
+            input: None,
+            message: String::new(),
+        }
+    }
+
+    fn week_days(&self) -> Vec<NaiveDate> {
+        days_in_week_of(self.date, self.show_weekend)
+    }
+
+    fn selected_date(&self) -> NaiveDate {
+        self.week_days()[self.column]
+    }
+
+    /// The rows of the table, as shown by the other commands.
+    fn rows(&self) -> Vec<Vec<String>> {
+        create_table(
+            self.date,
+            &self.config.rounded_day_from_date(),
+            self.show_weekend,
+            &self.config.settings,
+        )
+        .get_records()
+        .iter()
+        .map(|row| row.iter().map(|cell| cell.text().to_string()).collect())
+        .collect()
+    }
+
+    /// The field of the selected row, if it can be edited.
+    fn selected_field(&self) -> Option<Field> {
+        match self.row {
+            START_ROW => Some(Field::Start),
+            STOP_ROW => Some(Field::Stop),
+            LUNCH_ROW => Some(Field::Lunch),
+            row if row >= FIRST_PROJECT_ROW => self
+                .config
+                .settings
+                .project_names
+                .get(row - FIRST_PROJECT_ROW)
+                .map(|name| Field::Project(name.clone())),
+            _ => None,
+        }
+    }
+
+    /// Handle a key press. Returns true when the user quits.
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        if let Some(input) = &mut self.input {
+            match key.code {
+                KeyCode::Enter => {
+                    let text = input.trim().to_string();
+                    self.input = None;
+                    self.edit(&text);
+                }
+                KeyCode::Esc => self.input = None,
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Char(character) => input.push(character),
+                _ => (),
+            }
+            return false;
+        }
+        let row_count = self.rows().len();
+        let day_count = self.week_days().len();
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return true,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return true,
+            KeyCode::Up => self.row = (self.row - 1).max(HEADER_ROW_COUNT),
+            KeyCode::Down => self.row = (self.row + 1).min(row_count - 1),
+            KeyCode::Left if self.column > 0 => self.column -= 1,
+            KeyCode::Left => {
+                self.move_week(-1);
+                self.column = self.week_days().len() - 1;
+            }
+            KeyCode::Right if self.column + 1 < day_count => self.column += 1,
+            KeyCode::Right => {
+                self.move_week(1);
+                self.column = 0;
+            }
+            KeyCode::PageUp => self.move_week(-1),
+            KeyCode::PageDown => self.move_week(1),
+            KeyCode::Char('t') => {
+                self.date = self.today;
+                self.column = (self.today.weekday().num_days_from_monday() as usize)
+                    .min(self.week_days().len() - 1);
+            }
+            KeyCode::Char('w') => {
+                self.show_weekend = !self.show_weekend;
+                self.column = self.column.min(self.week_days().len() - 1);
+            }
+            KeyCode::Enter => match self.selected_field() {
+                Some(_) => {
+                    let rows = self.rows();
+                    let value = rows[self.row][self.column + 1].clone();
+                    // Implied lunches are shown in parentheses
+                    self.input = Some(value.trim_matches(|c| c == '(' || c == ')').to_string());
+                }
+                None => self.message = format!("{} cannot be edited", self.rows()[self.row][0]),
+            },
+            KeyCode::Delete | KeyCode::Backspace => match self.selected_field() {
+                Some(_) => self.edit(""),
+                None => self.message = format!("{} cannot be cleared", self.rows()[self.row][0]),
+            },
+            KeyCode::Char('u') => self.undo(),
+            KeyCode::Char('r') => self.redo(),
+            _ => (),
+        }
+        // The number of rows changes with the overtime rows of the week
+        self.row = self.row.min(self.rows().len() - 1);
+        false
+    }
+
+    fn move_week(&mut self, weeks: i64) {
+        self.date += Duration::try_weeks(weeks).expect("few weeks");
+        self.column = self.column.min(self.week_days().len() - 1);
+    }
+
+    /// Set the selected cell to `text`, or clear it if `text` is empty, in
+    /// the same way as the corresponding command.
+    fn edit(&mut self, text: &str) {
+        let Some(field) = self.selected_field() else {
+            return;
+        };
+        let date = self.selected_date();
+        match day_from_edit(date, field, text) {
+            Ok((day, command)) => {
+                self.config
+                    .set_command(format!("{} {}", date, command), self.now);
+                self.config.add_days(vec![day]);
+                self.message = match self.config.save(self.storage) {
+                    Ok(()) => format!("{} {}", date, command),
+                    Err(message) => message,
+                };
+            }
+            Err(message) => self.message = message,
+        }
+    }
+
+    fn undo(&mut self) {
+        self.message = match self.config.undo() {
+            Ok(dates) => {
+                self.show_first(&dates);
+                match self.config.save(self.storage) {
+                    Ok(()) => "Undid the last change".to_string(),
+                    Err(message) => message,
+                }
+            }
+            Err(message) => message,
+        };
+    }
+
+    fn redo(&mut self) {
+        self.message = match self.config.redo() {
+            Ok(dates) => {
+                self.show_first(&dates);
+                match self.config.save(self.storage) {
+                    Ok(()) => "Redid the last undone change".to_string(),
+                    Err(message) => message,
+                }
+            }
+            Err(message) => message,
+        };
+    }
+
+    /// Show the week of the first of the changed dates, if any.
+    fn show_first(&mut self, dates: &[NaiveDate]) {
+        if let Some(date) = dates.iter().min() {
+            self.date = *date;
+            self.show_weekend |=
+                matches!(date.weekday(), chrono::Weekday::Sat | chrono::Weekday::Sun);
+            self.column = self
+                .week_days()
+                .iter()
+                .position(|week_day| week_day == date)
+                .unwrap_or(0);
+        }
+    }
+
+    /// The flex of the week and of the month of the selected day.
+    fn flex_totals(&self) -> (TimeDelta, TimeDelta) {
+        let day_from_date = with_implied_lunch(
+            &self.config.rounded_day_from_date(),
+            &self.config.settings.lunch,
+        );
+        let flex = |dates: &mut dyn Iterator<Item = NaiveDate>| -> TimeDelta {
+            dates
+                .filter_map(|date| day_from_date.get(&date))
+                .filter_map(|day| {
+                    flex_time(
+                        day,
+                        self.config.settings.working_time_per_day,
+                        &self.config.settings.overtime,
+                    )
+                })
+                .sum()
+        };
+        let first_of_month = self
+            .selected_date()
+            .with_day(1)
+            .expect("first day of month exists");
+        let last_of_month = last_day_of_month(first_of_month);
+        (
+            flex(&mut days_in_week_of(self.date, true).into_iter()),
+            flex(
+                &mut first_of_month
+                    .iter_days()
+                    .take_while(|date| *date <= last_of_month),
+            ),
+        )
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let rows = self.rows();
+        let [table_area, flex_area, status_area, help_area] = Layout::vertical([
+            Constraint::Length(rows.len() as u16 + 2),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let label_width = rows
+            .iter()
+            .map(|row| row[0].chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut widths = vec![Constraint::Length(label_width as u16)];
+        widths.extend(vec![Constraint::Length(10); rows[0].len() - 1]);
+        let selected = Style::default().add_modifier(Modifier::REVERSED);
+        let table_rows = rows.iter().enumerate().map(|(row_index, row)| {
+            Row::new(row.iter().enumerate().map(|(column_index, text)| {
+                let cell = Cell::from(text.clone());
+                if row_index == self.row && column_index == self.column + 1 {
+                    cell.style(selected)
+                } else if row_index < HEADER_ROW_COUNT || column_index == 0 {
+                    cell.style(Style::default().add_modifier(Modifier::BOLD))
+                } else {
+                    cell
+                }
+            }))
+        });
+        frame.render_widget(
+            Table::new(table_rows, widths).block(Block::bordered().title(" timereport ")),
+            table_area,
+        );
+
+        let (week_flex, month_flex) = self.flex_totals();
+        frame.render_widget(
+            Paragraph::new(format!(
+                "Flex this week: {}   Flex in {}: {}",
+                week_flex.to_hhmm(),
+                self.selected_date().format("%B"),
+                month_flex.to_hhmm()
+            )),
+            flex_area,
+        );
+        let status = match &self.input {
+            Some(input) => format!("{} {}: {}_", self.selected_date(), rows[self.row][0], input),
+            None => self.message.clone(),
+        };
+        frame.render_widget(Paragraph::new(Line::from(status)), status_area);
+        frame.render_widget(
+            Paragraph::new(HELP).style(Style::default().add_modifier(Modifier::DIM)),
+            help_area,
+        );
+    }
+}
+
+/// The day for setting `field` to `text`, or clearing it if `text` is
+/// empty, and the command that does the same.
+fn day_from_edit(date: NaiveDate, field: Field, text: &str) -> Result<(Day, String), String> {
+    let mut day = Day {
+        date,
+        start: None,
+        stop: None,
+        lunch: None,
+        projects: HashMap::new(),
+        overtime: None,
+        cleared: Vec::new(),
+    };
+    let name = match &field {
+        Field::Start => "start".to_string(),
+        Field::Stop => "stop".to_string(),
+        Field::Lunch => "lunch".to_string(),
+        Field::Project(project) => format!("project {}", project),
+        _ => unreachable!("only the fields of editable rows"),
+    };
+    if text.is_empty() {
+        day.cleared.push(field);
+        return Ok((day, format!("clear {}", name)));
+    }
+    match field {
+        Field::Start => day.start = Some(crate::parse_time(text)?),
+        Field::Stop => day.stop = Some(crate::parse_time(text)?),
+        Field::Lunch => day.lunch = Some(TimeDelta::from_str(text)?),
+        Field::Project(project) => {
+            day.projects.insert(project, TimeDelta::from_str(text)?);
+        }
+        _ => unreachable!("only the fields of editable rows"),
+    }
+    Ok((day, format!("{} {}", name, text)))
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{NaiveDate, NaiveTime};
+    use ratatui::backend::TestBackend;
+    use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use ratatui::Terminal;
+    use rstest::rstest;
+
+    use crate::config;
+    use crate::storage::{InMemory, Storage};
+    use crate::tui::App;
+
+    /// Wednesday
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 4, 16).expect("")
+    }
+
+    fn press(app: &mut App, keys: &[KeyCode]) {
+        for key in keys {
+            app.handle_key(KeyEvent::new(*key, KeyModifiers::NONE));
+        }
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        press(app, &[KeyCode::Enter]);
+        // Replace the current value
+        press(app, &[KeyCode::Backspace; 10]);
+        let keys: Vec<KeyCode> = text.chars().map(KeyCode::Char).collect();
+        press(app, &keys);
+        press(app, &[KeyCode::Enter]);
+    }
+
+    fn new_app(storage: &dyn Storage) -> App<'_> {
+        App::new(
+            config::load(storage, today()).expect(""),
+            storage,
+            today().and_hms_opt(12, 0, 0).expect(""),
+        )
+    }
+
+    fn time(text: &str) -> Option<NaiveTime> {
+        Some(NaiveTime::parse_from_str(text, "%H:%M").expect(""))
+    }
+
+    #[rstest]
+    fn edit_start_and_stop() {
+        let storage = InMemory::new();
+        let mut app = new_app(&storage);
+
+        type_text(&mut app, "8:15");
+        press(&mut app, &[KeyCode::Down]);
+        type_text(&mut app, "17");
+
+        let day_from_date = config::load(&storage, today()).expect("").day_from_date();
+        assert_eq!(day_from_date[&today()].start, time("08:15"));
+        assert_eq!(day_from_date[&today()].stop, time("17:00"));
+    }
+
+    #[rstest]
+    fn edit_previous_day_and_undo() {
+        let storage = InMemory::new();
+        let mut app = new_app(&storage);
+        let yesterday = today().pred_opt().expect("");
+
+        press(&mut app, &[KeyCode::Left]);
+        type_text(&mut app, "9");
+        press(&mut app, &[KeyCode::Char('u')]);
+
+        let config = config::load(&storage, today()).expect("");
+        assert!(!config.day_from_date().contains_key(&yesterday));
+        assert_eq!(config.history().len(), 0);
+    }
+
+    #[rstest]
+    fn clear_lunch() {
+        let storage = InMemory::new();
+        let mut app = new_app(&storage);
+        press(&mut app, &[KeyCode::Down, KeyCode::Down]);
+        type_text(&mut app, "45m");
+
+        press(&mut app, &[KeyCode::Delete]);
+
+        let day_from_date = config::load(&storage, today()).expect("").day_from_date();
+        assert_eq!(day_from_date[&today()].lunch, None);
+    }
+
+    #[rstest]
+    fn invalid_value_is_not_saved() {
+        let storage = InMemory::new();
+        let mut app = new_app(&storage);
+
+        type_text(&mut app, "soon");
+
+        assert!(app.message.contains("Could not parse time string 'soon'"));
+        assert!(config::load(&storage, today()).expect("").days().is_empty());
+    }
+
+    #[rstest]
+    fn default_project_cannot_be_edited() {
+        let storage = InMemory::new();
+        let mut app = new_app(&storage);
+        press(&mut app, &[KeyCode::Down, KeyCode::Down, KeyCode::Down]);
+
+        press(&mut app, &[KeyCode::Enter]);
+
+        assert_eq!(app.input, None);
+        assert_eq!(app.message, "1. Default project cannot be edited");
+    }
+
+    #[rstest]
+    fn move_between_weeks() {
+        let storage = InMemory::new();
+        let mut app = new_app(&storage);
+
+        press(&mut app, &[KeyCode::PageUp, KeyCode::PageUp]);
+        assert_eq!(
+            app.selected_date(),
+            NaiveDate::from_ymd_opt(2025, 4, 2).expect("")
+        );
+
+        // Right from Friday goes to Monday of the next week
+        press(&mut app, &[KeyCode::Right, KeyCode::Right, KeyCode::Right]);
+        assert_eq!(
+            app.selected_date(),
+            NaiveDate::from_ymd_opt(2025, 4, 7).expect("")
+        );
+
+        press(&mut app, &[KeyCode::Char('t')]);
+        assert_eq!(app.selected_date(), today());
+    }
+
+    #[rstest]
+    fn draw_shows_flex_totals() {
+        let storage = InMemory::new();
+        let mut app = new_app(&storage);
+        type_text(&mut app, "8");
+        press(&mut app, &[KeyCode::Down]);
+        type_text(&mut app, "17");
+        press(&mut app, &[KeyCode::Down]);
+        type_text(&mut app, "45m");
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).expect("");
+
+        terminal.draw(|frame| app.draw(frame)).expect("");
+
+        let text: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(text.contains("2025-04-16"));
+        // 9 hours - 45 minutes lunch - 7:45 working time
+        assert!(text.contains("Flex this week: 00:30   Flex in April: 00:30"));
+    }
+}
@@ -0,0 +1,103 @@
+use chrono::NaiveDate;
+use rstest::*;
+use tempfile::TempDir;
+mod utils;
+use utils::*;
+
+fn day_count(temp_dir: &TempDir) -> usize {
+    read_config(temp_dir)["days"].as_array().expect("").len()
+}
+
+fn days_in_archive(temp_dir: &TempDir, name: &str) -> usize {
+    let contents = std::fs::read_to_string(temp_dir.path().join(name)).expect("");
+    let archive: serde_json::Value = serde_json::from_str(&contents).expect("");
+    archive["days"].as_array().expect("").len()
+}
+
+#[rstest]
+fn archive_moves_days_of_year(temp_dir: TempDir) {
+    run("2024-12-18 start 8", &temp_dir);
+    run("2024-12-18 stop 16", &temp_dir);
+    run("2025-01-02 start 9", &temp_dir);
+    let today = NaiveDate::from_ymd_opt(2025, 4, 15).expect("");
+
+    let output = run_mock_date("archive 2024", &temp_dir, today);
+
+    assert!(output.contains("Moved 2 entries for 2024"));
+    assert_eq!(day_count(&temp_dir), 1);
+    assert_eq!(days_in_archive(&temp_dir, "timereport-2024.json"), 1);
+}
+
+#[rstest]
+fn show_reads_archive(temp_dir: TempDir) {
+    run("2024-12-18 start 8", &temp_dir);
+    run("2024-12-18 stop 16", &temp_dir);
+    run("2025-01-02 start 9", &temp_dir);
+    let today = NaiveDate::from_ymd_opt(2025, 4, 15).expect("");
+    let before = run_mock_date("show december 2024", &temp_dir, today);
+    let week_before = run_mock_date("2024-12-18 show week", &temp_dir, today);
+    assert!(before.contains("16:00"));
+
+    run_mock_date("archive 2024", &temp_dir, today);
+
+    assert_eq!(
+        run_mock_date("show december 2024", &temp_dir, today),
+        before
+    );
+    assert_eq!(
+        run_mock_date("2024-12-18 show week", &temp_dir, today),
+        week_before
+    );
+}
+
+#[rstest]
+fn archive_again_keeps_archived_days(temp_dir: TempDir) {
+    let today = NaiveDate::from_ymd_opt(2025, 4, 15).expect("");
+    run("2024-12-18 start 8", &temp_dir);
+    run_mock_date("archive 2024", &temp_dir, today);
+    run("2024-12-18 stop 16", &temp_dir);
+
+    run_mock_date("archive 2024", &temp_dir, today);
+
+    assert_eq!(day_count(&temp_dir), 0);
+    assert_eq!(days_in_archive(&temp_dir, "timereport-2024.json"), 1);
+    let output = run_mock_date("show december 2024", &temp_dir, today);
+    assert!(output.contains("08:00"));
+    assert!(output.contains("16:00"));
+}
+
+#[rstest]
+fn archive_current_year_is_refused(temp_dir: TempDir) {
+    run("2025-01-02 start 9", &temp_dir);
+    let today = NaiveDate::from_ymd_opt(2025, 4, 15).expect("");
+
+    let output = run_mock_date("archive 2025", &temp_dir, today);
+
+    assert_eq!(output, "Only past years can be archived, not 2025");
+}
+
+#[rstest]
+#[case("0201")]
+#[case("20245")]
+fn archive_implausible_year_is_refused(temp_dir: TempDir, #[case] year: &str) {
+    run("2024-12-18 start 8", &temp_dir);
+    let today = NaiveDate::from_ymd_opt(2025, 4, 15).expect("");
+
+    let output = run_mock_date(&format!("archive {}", year), &temp_dir, today);
+
+    assert!(output.starts_with(&format!("Invalid year '{}'", year)));
+    assert_eq!(day_count(&temp_dir), 1);
+}
+
+#[rstest]
+fn archive_keeps_the_days_if_the_archive_cannot_be_written(temp_dir: TempDir) {
+    run("2024-12-18 start 8", &temp_dir);
+    let today = NaiveDate::from_ymd_opt(2025, 4, 15).expect("");
+    std::fs::create_dir(temp_dir.path().join("timereport-2024.json")).expect("");
+
+    let output = run_mock_date("archive 2024", &temp_dir, today);
+
+    assert!(output.starts_with("Error writing to file"), "{}", output);
+    assert_eq!(day_count(&temp_dir), 1);
+    assert!(run_mock_date("show december 2024", &temp_dir, today).contains("08:00"));
+}
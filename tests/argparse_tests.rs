@@ -0,0 +1,79 @@
+use rstest::*;
+use tempfile::TempDir;
+mod utils;
+use utils::*;
+
+#[rstest]
+#[case("week")]
+#[case("last")]
+#[case("show")]
+fn project_named_like_a_keyword(temp_dir: TempDir, #[case] name: &str) {
+    run(&format!("add {}", name), &temp_dir);
+
+    let output = run_mock_date(
+        &format!("2025-04-15 project {} 2", name),
+        &temp_dir,
+        today(),
+    );
+
+    assert!(output.contains(name));
+    assert!(output.contains("02:00"));
+}
+
+#[rstest]
+fn misspelled_keyword_is_pointed_at_and_suggested(temp_dir: TempDir) {
+    let output = run_mock_date("2025-04-15 strat 8", &temp_dir, today());
+
+    assert_eq!(
+        output,
+        "Unknown or extra argument 'strat'. Did you mean 'start'?
+  t 2025-04-15 strat 8
+               ^^^^^"
+    );
+}
+
+#[rstest]
+fn misspelled_command_is_suggested(temp_dir: TempDir) {
+    let output = run("histroy", &temp_dir);
+
+    assert!(output.contains("Did you mean 'history'?"));
+}
+
+#[rstest]
+fn misspelled_project_is_suggested(temp_dir: TempDir) {
+    run("add Sickness", &temp_dir);
+
+    let output = run("project sickess 8", &temp_dir);
+
+    assert!(output.starts_with("Unknown project 'sickess'. Did you mean 'Sickness'?"));
+}
+
+#[rstest]
+fn nothing_is_saved_on_error(temp_dir: TempDir) {
+    run_mock_date("2025-04-15 start 8", &temp_dir, today());
+    let before = read_config(&temp_dir);
+
+    run_mock_date("2025-04-15 stop 17 lunch soon", &temp_dir, today());
+
+    assert_eq!(read_config(&temp_dir), before);
+}
+
+#[rstest]
+#[case("start --help", "Usage: t [DAY...] start TIME")]
+#[case("undo --help", "Usage: t undo [STEPS|--date DAY]")]
+#[case("check --help", "Usage: t check [[last] week|MONTH [YEAR]|YEAR]")]
+fn help_for_command(temp_dir: TempDir, #[case] args: &str, #[case] expected: &str) {
+    let output = run(args, &temp_dir);
+
+    assert!(output.starts_with(expected), "{}", output);
+}
+
+#[rstest]
+fn show_week_of_the_given_date(temp_dir: TempDir) {
+    run("2024-12-18 start 8", &temp_dir);
+
+    let output = run_mock_date("2024-12-18 show week", &temp_dir, today());
+
+    assert!(output.contains("2024-12-16"));
+    assert!(output.contains("08:00"));
+}
@@ -0,0 +1,103 @@
+use rstest::*;
+use tempfile::TempDir;
+use timereport::batch;
+use timereport::storage::from_path;
+mod utils;
+use utils::*;
+
+fn run_batch(input: &str, temp_dir: &TempDir) -> String {
+    batch(
+        input,
+        from_path(&data_path(temp_dir)).as_ref(),
+        today().and_time(noon()),
+    )
+}
+
+#[rstest]
+fn batch_applies_all_commands_and_shows_the_changed_week(temp_dir: TempDir) {
+    let output = run_batch(
+        "2025-04-14 start 8 stop 17\n2025-04-15 start 9\n2025-04-15 stop 18\n",
+        &temp_dir,
+    );
+
+    assert!(output.contains("2025-04-14"));
+    assert!(output.contains("17:00"));
+    assert!(output.contains("18:00"));
+    assert_eq!(
+        read_config(&temp_dir)["days"].as_array().expect("").len(),
+        3
+    );
+}
+
+#[rstest]
+fn batch_saves_nothing_if_a_command_fails(temp_dir: TempDir) {
+    run_mock_date("2025-04-14 start 8", &temp_dir, today());
+    let config_before = read_config(&temp_dir);
+
+    let output = run_batch("2025-04-15 start 9\n2025-04-15 stop 25\n", &temp_dir);
+
+    assert!(output.starts_with("Error on line 2: 2025-04-15 stop 25\n"));
+    assert!(output.ends_with("Nothing was saved."));
+    assert_eq!(read_config(&temp_dir), config_before);
+}
+
+#[rstest]
+fn batch_skips_empty_lines_and_comments(temp_dir: TempDir) {
+    let output = run_batch("# Monday\n\n2025-04-14 start 8\n", &temp_dir);
+
+    assert!(output.contains("08:00"));
+    assert_eq!(
+        read_config(&temp_dir)["days"].as_array().expect("").len(),
+        1
+    );
+}
+
+#[rstest]
+fn batch_keeps_quoted_project_names_together(temp_dir: TempDir) {
+    run_batch(
+        "add \"Project containing spaces\"\n2025-04-15 project \"Project containing spaces\" 1:30\n",
+        &temp_dir,
+    );
+
+    let output = run_mock_date("2025-04-15 show week", &temp_dir, today());
+    assert!(output.contains("Project containing spaces"));
+    assert!(output.contains("01:30"));
+}
+
+#[rstest]
+fn batch_commands_see_earlier_commands_in_the_batch(temp_dir: TempDir) {
+    run_batch(
+        "2025-04-15 start 8\n2025-04-15 stop 17\nundo\n2025-04-15 lunch 45m\n",
+        &temp_dir,
+    );
+
+    let days = read_config(&temp_dir)["days"].clone();
+    assert_eq!(days.as_array().expect("").len(), 2);
+    assert_eq!(days[0]["start"], "08:00:00");
+    assert!(days[1]["stop"].is_null());
+}
+
+#[rstest]
+fn batch_without_changes(temp_dir: TempDir) {
+    assert_eq!(run_batch("show week\n", &temp_dir), "No changes");
+}
+
+#[rstest]
+fn batch_cannot_start_the_terminal_ui(temp_dir: TempDir) {
+    let output = run_batch("tui\n", &temp_dir);
+
+    assert!(output.contains("tui cannot be used in the shell or in a batch"));
+}
+
+#[rstest]
+fn batch_cannot_convert(temp_dir: TempDir) {
+    let target = temp_dir.path().join("converted.sqlite");
+
+    let output = run_batch(
+        &format!("2025-04-14 start 8\nconvert {}\n", target.to_string_lossy()),
+        &temp_dir,
+    );
+
+    assert!(output.contains("convert cannot be used in the shell or in a batch"));
+    assert!(!target.exists());
+}
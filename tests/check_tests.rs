@@ -0,0 +1,84 @@
+use rstest::*;
+use tempfile::TempDir;
+mod utils;
+use utils::*;
+
+#[rstest]
+fn check_complete_week(temp_dir: TempDir) {
+    run_today(
+        "2025-04-14 2025-04-15 start 8 stop 16:30 lunch 45m",
+        &temp_dir,
+    );
+
+    let output = run_today("check week", &temp_dir);
+
+    assert_eq!(output, "No problems found from 2025-04-14 to 2025-04-16");
+}
+
+#[rstest]
+fn check_week_finds_problems(temp_dir: TempDir) {
+    run_today("add ProjectA", &temp_dir);
+    run_today("2025-04-14 start 8", &temp_dir);
+    run_today("2025-04-15 start 8 stop 12 lunch 0", &temp_dir);
+    run_today("2025-04-15 project ProjectA 5", &temp_dir);
+
+    let output = run_today("check week", &temp_dir);
+
+    assert_eq!(
+        output,
+        "2025-04-14 Mon: Start but no stop
+  Fix: t 2025-04-14 stop TIME
+2025-04-15 Tue: Project time 05:00 is more than the worked time 04:00
+  Fix: t 2025-04-15 project ProjectA TIME"
+    );
+}
+
+#[rstest]
+fn check_last_week_finds_missing_days(temp_dir: TempDir) {
+    run_today(
+        "2025-04-07 2025-04-08 2025-04-09 2025-04-10 start 8 stop 16:30 lunch 45m",
+        &temp_dir,
+    );
+
+    let output = run_today("check last week", &temp_dir);
+
+    assert_eq!(
+        output,
+        "2025-04-11 Fri: No data for a weekday
+  Fix: t 2025-04-11 start TIME stop TIME lunch TIME"
+    );
+}
+
+#[rstest]
+fn check_month_by_default(temp_dir: TempDir) {
+    let output = run_today("check", &temp_dir);
+
+    // Every weekday from April 1 to yesterday
+    assert_eq!(output.matches("No data for a weekday").count(), 11);
+}
+
+#[rstest]
+fn check_named_month(temp_dir: TempDir) {
+    let output = run_today("check february", &temp_dir);
+
+    assert_eq!(output.matches("No data for a weekday").count(), 20);
+    assert!(output.contains("2025-02-28"));
+}
+
+#[rstest]
+fn check_does_not_change_data(temp_dir: TempDir) {
+    run_today("2025-04-14 start 8", &temp_dir);
+    let before = read_config(&temp_dir);
+
+    run_today("check week", &temp_dir);
+
+    assert_eq!(read_config(&temp_dir), before);
+}
+
+#[rstest]
+fn check_unknown_period(temp_dir: TempDir) {
+    assert_eq!(
+        run_today("check fortnight", &temp_dir),
+        "Unknown period: fortnight\n  t check fortnight\n          ^^^^^^^^^"
+    );
+}
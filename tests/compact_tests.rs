@@ -0,0 +1,83 @@
+use chrono::NaiveDate;
+use rstest::*;
+use tempfile::TempDir;
+mod utils;
+use utils::*;
+
+fn day_count(temp_dir: &TempDir) -> usize {
+    read_config(temp_dir)["days"].as_array().expect("").len()
+}
+
+#[rstest]
+fn compact_keeps_data(temp_dir: TempDir) {
+    run("2025-04-15 start 8", &temp_dir);
+    run("2025-04-15 stop 16", &temp_dir);
+    run("2025-04-15 clear stop", &temp_dir);
+    run("2025-04-15 lunch 45m", &temp_dir);
+    run("2025-04-16 start 9", &temp_dir);
+    let date = NaiveDate::from_ymd_opt(2025, 4, 15).expect("");
+    let before = run_mock_date("show week", &temp_dir, date);
+    assert!(before.contains("08:00"));
+
+    let output = run("compact 2025-04-16", &temp_dir);
+
+    assert!(output.contains("Removed 3 redundant entries for days before 2025-04-16"));
+    assert_eq!(day_count(&temp_dir), 2);
+    assert_eq!(run_mock_date("show week", &temp_dir, date), before);
+}
+
+#[rstest]
+fn compact_keeps_recent_changes_undoable(temp_dir: TempDir) {
+    run("2025-04-15 start 8", &temp_dir);
+    run("2025-04-15 stop 16", &temp_dir);
+    run("2025-04-16 start 9", &temp_dir);
+    run("compact 2025-04-16", &temp_dir);
+
+    let output = run("undo", &temp_dir);
+    assert!(!output.contains("09:00"));
+
+    let output = run("undo", &temp_dir);
+    assert!(output.contains("Nothing to undo"));
+}
+
+#[rstest]
+fn compact_automatically_above_threshold(temp_dir: TempDir) {
+    set_config_value(&temp_dir, "compact_threshold", serde_json::json!(3));
+    run("2020-01-01 start 8", &temp_dir);
+    run("2020-01-01 stop 16", &temp_dir);
+    run("2020-01-01 lunch 1", &temp_dir);
+    run("2020-01-01 lunch 45m", &temp_dir);
+    assert_eq!(day_count(&temp_dir), 4);
+
+    run("2020-01-02 start 8", &temp_dir);
+
+    assert_eq!(day_count(&temp_dir), 2);
+}
+
+#[rstest]
+fn compact_automatically_relative_to_the_given_date(temp_dir: TempDir) {
+    set_config_value(&temp_dir, "compact_threshold", serde_json::json!(3));
+    let today = NaiveDate::from_ymd_opt(2020, 1, 2).expect("");
+    run_mock_date("2020-01-01 start 8", &temp_dir, today);
+    run_mock_date("2020-01-01 stop 16", &temp_dir, today);
+    run_mock_date("2020-01-01 lunch 1", &temp_dir, today);
+    run_mock_date("2020-01-01 lunch 45m", &temp_dir, today);
+
+    run_mock_date("2020-01-02 start 8", &temp_dir, today);
+
+    assert_eq!(day_count(&temp_dir), 5);
+}
+
+#[rstest]
+fn no_automatic_compaction_without_entries_to_remove(temp_dir: TempDir) {
+    set_config_value(&temp_dir, "compact_threshold", serde_json::json!(1));
+    run("2020-01-01 start 8", &temp_dir);
+    run("2020-01-02 start 8", &temp_dir);
+    let before = read_config(&temp_dir);
+
+    run("2020-01-02 show week", &temp_dir);
+
+    assert_eq!(read_config(&temp_dir), before);
+    run("undo", &temp_dir);
+    assert_eq!(day_count(&temp_dir), 1);
+}
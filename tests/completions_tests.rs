@@ -0,0 +1,68 @@
+use chrono::Local;
+use rstest::*;
+use tempfile::TempDir;
+use timereport::main;
+use timereport::storage::from_path;
+mod utils;
+use utils::*;
+
+/// Run a command with arguments that contain spaces.
+fn run_args(args: &[&str], temp_dir: &TempDir) -> String {
+    main(
+        args.iter().map(|arg| arg.to_string()).collect(),
+        from_path(&data_path(temp_dir)).as_ref(),
+        Local::now().naive_local(),
+    )
+}
+
+#[rstest]
+#[case("bash", "complete -F _timereport timereport t")]
+#[case("zsh", "compdef _timereport timereport t")]
+#[case("fish", "complete -c $command")]
+fn completion_script_for_each_shell(
+    temp_dir: TempDir,
+    #[case] shell: &str,
+    #[case] expected: &str,
+) {
+    let output = run(&format!("completions {}", shell), &temp_dir);
+
+    assert!(output.contains(expected));
+    let words: Vec<&str> = output
+        .lines()
+        .find(|line| line.contains("wednesday"))
+        .expect("a line with the words")
+        .split(|c: char| c.is_whitespace() || c == '"' || c == '\'')
+        .collect();
+    for word in [
+        "start",
+        "stop",
+        "lunch",
+        "project",
+        "add",
+        "show",
+        "undo",
+        "redo",
+        "wednesday",
+        "september",
+    ] {
+        assert!(words.contains(&word), "{} is missing", word);
+    }
+    assert!(output.contains("completions projects"));
+}
+
+#[rstest]
+fn completion_of_projects_lists_indices_and_names(temp_dir: TempDir) {
+    run("add Sickness", &temp_dir);
+    run_args(&["add", "Project containing spaces"], &temp_dir);
+
+    let output = run("completions projects", &temp_dir);
+
+    assert_eq!(output, "2\tSickness\n3\tProject containing spaces");
+}
+
+#[rstest]
+fn unknown_shell(temp_dir: TempDir) {
+    let output = run("completions powershell", &temp_dir);
+
+    assert_eq!(output, "Usage: t completions {bash|zsh|fish}");
+}
@@ -0,0 +1,96 @@
+use rstest::*;
+use tempfile::TempDir;
+mod utils;
+use utils::*;
+
+#[rstest]
+fn compliance_without_problems(temp_dir: TempDir) {
+    run_today("2025-04-14 2025-04-15 start 8 stop 16 lunch 0", &temp_dir);
+
+    let output = run_today("compliance week", &temp_dir);
+
+    assert_eq!(
+        output,
+        "Compliance from 2025-04-14 to 2025-04-16
+
+Violations:
+  None
+
+Near misses:
+  None
+
+Overtime:
+  2025-04: 00:30 of 50:00
+  2025: 00:30 of 200:00
+
+Overtime is the time worked beyond working_time_per_day on weekdays and all time worked on weekends. The overtime rules and marked overtime are not used."
+    );
+}
+
+#[rstest]
+fn compliance_reports_short_rest_across_midnight(temp_dir: TempDir) {
+    run_today("2025-04-14 start 14 stop 1 lunch 0", &temp_dir);
+    run_today("2025-04-15 start 10 stop 16 lunch 0", &temp_dir);
+
+    let output = run_today("compliance week", &temp_dir);
+
+    assert!(output.contains(
+        "Violations:
+  2025-04-15: 09:00 of rest after 2025-04-15 01:00, the limit is at least 11:00"
+    ));
+}
+
+#[rstest]
+fn compliance_lists_zero_length_day_as_not_checked(temp_dir: TempDir) {
+    run_today("2025-04-14 start 8 stop 8 lunch 0", &temp_dir);
+
+    let output = run_today("compliance week", &temp_dir);
+
+    assert!(output.starts_with(
+        "Compliance from 2025-04-14 to 2025-04-16
+
+Not checked, see t check:
+  2025-04-14: Stop is the same as start 08:00
+
+Violations:
+  None"
+    ));
+}
+
+#[rstest]
+fn compliance_uses_implied_lunch(temp_dir: TempDir) {
+    run_today("config set lunch.monday 1:00", &temp_dir);
+    run_today("2025-04-14 start 8 stop 17", &temp_dir);
+
+    let output = run_today("compliance week", &temp_dir);
+
+    assert!(output.contains("  2025-04: 00:15 of 50:00"));
+}
+
+#[rstest]
+fn compliance_uses_configured_limits(temp_dir: TempDir) {
+    run_today("config set compliance.overtime_per_month 1:00", &temp_dir);
+    run_today("2025-04-14 start 6 stop 16 lunch 0", &temp_dir);
+
+    let output = run_today("compliance april", &temp_dir);
+
+    assert!(output.contains("  2025-04: 02:15 of overtime, the limit is at most 01:00"));
+    assert!(output.contains("  2025-04: 02:15 of 01:00"));
+}
+
+#[rstest]
+fn compliance_for_a_year(temp_dir: TempDir) {
+    let output = run_today("compliance 2024", &temp_dir);
+
+    assert!(output.starts_with("Compliance from 2024-01-01 to 2024-12-31"));
+    assert!(output.contains("  2024-12: 00:00 of 50:00"));
+    assert!(output.contains("  2024: 00:00 of 200:00"));
+}
+
+#[rstest]
+fn compliance_unknown_period(temp_dir: TempDir) {
+    assert_eq!(
+        run_today("compliance fortnight", &temp_dir),
+        "Unknown period: fortnight\n  t compliance fortnight\n               ^^^^^^^^^"
+    );
+}
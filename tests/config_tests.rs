@@ -0,0 +1,104 @@
+use rstest::*;
+use tempfile::TempDir;
+mod utils;
+use utils::*;
+
+#[rstest]
+fn config_set_and_get(temp_dir: TempDir) {
+    let output = run("config set working_time_per_day 8:00", &temp_dir);
+    assert_eq!(output, "working_time_per_day = 08:00");
+
+    let output = run("config get working_time_per_day", &temp_dir);
+
+    assert_eq!(output, "08:00");
+}
+
+#[rstest]
+fn config_set_is_used_for_flex(temp_dir: TempDir) {
+    run("config set working_time_per_day 8", &temp_dir);
+
+    let output = run("2025-04-15 start 8 stop 16:30 lunch 30m", &temp_dir);
+
+    let flex_row = output.lines().find(|line| line.contains("Flex")).expect("");
+    assert!(flex_row.contains("00:00"));
+}
+
+#[rstest]
+fn config_set_invalid_value_is_refused(temp_dir: TempDir) {
+    let output = run("config set working_time_per_day 30:00", &temp_dir);
+
+    assert_eq!(
+        output,
+        "Invalid value '30:00' for working_time_per_day: must be more than 0 and at most 24 hours"
+    );
+    assert_eq!(run("config get working_time_per_day", &temp_dir), "07:45");
+}
+
+#[rstest]
+fn config_get_unknown_key(temp_dir: TempDir) {
+    let output = run("config get working_time", &temp_dir);
+
+    assert!(output.starts_with("Unknown setting 'working_time'."));
+    assert!(output.contains("Did you mean 'working_time_per_day'?"));
+}
+
+#[rstest]
+fn settings_are_saved_in_settings_file(temp_dir: TempDir) {
+    run("add ProjectA", &temp_dir);
+    run("config set working_time_per_day 7:30", &temp_dir);
+
+    let settings = std::fs::read_to_string(data_path(&temp_dir).with_extension("toml")).expect("");
+
+    assert!(settings.contains("working_time_per_day = \"07:30\""));
+    assert!(settings.contains("project_names = [\"ProjectA\"]"));
+    let config = read_config(&temp_dir);
+    assert!(config.get("project_names").is_none());
+    assert!(config.get("working_time_per_day").is_none());
+}
+
+#[rstest]
+fn combined_file_from_older_version_works(temp_dir: TempDir) {
+    let path = temp_dir.path().join("timereport.json");
+    std::fs::write(
+        &path,
+        r#"{
+            "project_names": ["Project A"],
+            "days": [],
+            "undone": [],
+            "working_time_per_day": 28800
+        }"#,
+    )
+    .expect("");
+    let storage = timereport::storage::from_path(&path);
+    let run_combined = |command: &str| {
+        let args = command.split_whitespace().map(|s| s.to_string()).collect();
+        timereport::main(args, storage.as_ref(), chrono::Local::now().naive_local())
+    };
+
+    assert_eq!(run_combined("config get working_time_per_day"), "08:00");
+    assert_eq!(run_combined("config get project_names"), "Project A");
+    run_combined("2025-04-15 start 8");
+
+    assert!(temp_dir.path().join("timereport.toml").exists());
+    assert_eq!(run_combined("config get working_time_per_day"), "08:00");
+    assert_eq!(run_combined("config get project_names"), "Project A");
+}
+
+#[rstest]
+fn settings_file_is_kept_when_no_setting_changes(temp_dir: TempDir) {
+    run("", &temp_dir); // To create a settings file
+    let path = data_path(&temp_dir).with_extension("toml");
+    let contents = format!(
+        "# my working time\n{}",
+        std::fs::read_to_string(&path).expect("")
+    );
+    std::fs::write(&path, &contents).expect("");
+
+    run("2025-04-15 start 8", &temp_dir);
+
+    assert_eq!(std::fs::read_to_string(&path).expect(""), contents);
+    run("config set working_time_per_day 8:00", &temp_dir);
+    assert!(std::fs::read_to_string(&path)
+        .expect("")
+        .contains("working_time_per_day = \"08:00\""));
+}
@@ -0,0 +1,153 @@
+use chrono::NaiveDate;
+use rstest::*;
+use std::path::Path;
+use tempfile::TempDir;
+use timereport::main;
+use timereport::storage::from_path;
+mod utils;
+use utils::*;
+
+fn run_with_path(s: &str, path: &Path) -> String {
+    let args: Vec<String> = s.split_whitespace().map(|s| s.to_string()).collect();
+    main(
+        args,
+        from_path(path).as_ref(),
+        NaiveDate::from_ymd_opt(2025, 4, 16)
+            .and_then(|date| date.and_hms_opt(12, 0, 0))
+            .expect(""),
+    )
+}
+
+#[rstest]
+fn convert_json_to_sqlite_and_back_is_lossless(temp_dir: TempDir) {
+    let json_path = temp_dir.path().join("timereport.json");
+    // Other file names, as files with the same name share the settings file
+    let sqlite_path = temp_dir.path().join("converted.sqlite");
+    let back_path = temp_dir.path().join("back.json");
+    run_with_path("add Project A", &json_path);
+    run_with_path("2025-04-15 start 8 lunch 45m stop 17", &json_path);
+    run_with_path("2025-04-15 project Project A 2", &json_path);
+    run_with_path("2025-04-15 clear lunch", &json_path);
+    run_with_path("2025-04-16 start 9", &json_path);
+    run_with_path("undo", &json_path);
+
+    let output = run_with_path(
+        &format!("convert {}", sqlite_path.to_string_lossy()),
+        &json_path,
+    );
+    assert!(output.starts_with("Converted"));
+    run_with_path(
+        &format!("convert {}", back_path.to_string_lossy()),
+        &sqlite_path,
+    );
+
+    let original = std::fs::read_to_string(&json_path).expect("");
+    let back = std::fs::read_to_string(&back_path).expect("");
+    assert_eq!(back, original);
+    let original_settings =
+        std::fs::read_to_string(temp_dir.path().join("timereport.toml")).expect("");
+    let back_settings = std::fs::read_to_string(temp_dir.path().join("back.toml")).expect("");
+    assert_eq!(back_settings, original_settings);
+}
+
+#[rstest]
+fn sqlite_file_works_like_json_file(temp_dir: TempDir) {
+    let json_path = temp_dir.path().join("timereport.json");
+    let sqlite_path = temp_dir.path().join("timereport.db");
+    for path in [&json_path, &sqlite_path] {
+        run_with_path("2025-04-15 start 8 stop 16", path);
+        run_with_path("2025-04-15 lunch 1", path);
+        run_with_path("undo", path);
+        run_with_path("redo", path);
+    }
+
+    assert_eq!(
+        run_with_path("2025-04-15 show week", &sqlite_path),
+        run_with_path("2025-04-15 show week", &json_path)
+    );
+}
+
+#[rstest]
+fn convert_to_existing_file_is_refused(temp_dir: TempDir) {
+    run("", &temp_dir); // To create a config file
+    let other_path = temp_dir.path().join("other.json");
+    std::fs::write(&other_path, "{}").expect("");
+
+    let output = run(
+        &format!("convert {}", other_path.to_string_lossy()),
+        &temp_dir,
+    );
+
+    assert!(output.ends_with("other.json already exists"));
+    assert_eq!(std::fs::read_to_string(&other_path).expect(""), "{}");
+}
+
+#[rstest]
+fn storage_setting_overrides_the_extension(temp_dir: TempDir) {
+    let path = temp_dir.path().join("timereport.dat");
+    std::fs::write(
+        temp_dir.path().join("timereport.toml"),
+        "storage = \"sqlite\"",
+    )
+    .expect("");
+
+    run_with_path("2025-04-15 start 8", &path);
+
+    assert!(std::fs::read(&path)
+        .expect("")
+        .starts_with(b"SQLite format 3"));
+    assert!(run_with_path("2025-04-15 show week", &path).contains("08:00"));
+}
+
+#[rstest]
+fn convert_uses_the_extension_of_the_target(temp_dir: TempDir) {
+    let path = temp_dir.path().join("timereport.dat");
+    let json_path = temp_dir.path().join("converted.json");
+    std::fs::write(
+        temp_dir.path().join("timereport.toml"),
+        "storage = \"sqlite\"",
+    )
+    .expect("");
+    run_with_path("2025-04-15 start 8", &path);
+
+    run_with_path(&format!("convert {}", json_path.to_string_lossy()), &path);
+
+    assert!(std::fs::read_to_string(&json_path)
+        .expect("")
+        .starts_with('{'));
+    assert!(run_with_path("2025-04-15 show week", &json_path).contains("08:00"));
+}
+
+#[rstest]
+fn convert_to_the_same_file_stem_is_refused(temp_dir: TempDir) {
+    let path = temp_dir.path().join("timereport.dat");
+    let settings_path = temp_dir.path().join("timereport.toml");
+    let json_path = temp_dir.path().join("timereport.json");
+    std::fs::write(&settings_path, "storage = \"sqlite\"").expect("");
+    run_with_path("2025-04-15 start 8", &path);
+
+    let output = run_with_path(&format!("convert {}", json_path.to_string_lossy()), &path);
+
+    assert!(
+        output.contains("would share the settings file"),
+        "{}",
+        output
+    );
+    assert!(!json_path.exists());
+    assert!(std::fs::read_to_string(&settings_path)
+        .expect("")
+        .contains("storage = \"sqlite\""));
+    assert!(run_with_path("2025-04-15 show week", &path).contains("08:00"));
+}
+
+#[rstest]
+fn storage_setting_for_the_other_format_is_refused(temp_dir: TempDir) {
+    let path = temp_dir.path().join("timereport.json");
+    run_with_path("2025-04-15 start 8", &path);
+
+    let output = run_with_path("config set storage sqlite", &path);
+
+    assert!(output.contains("Use 't convert FILE'"), "{}", output);
+    assert_eq!(run_with_path("config get storage", &path), "auto");
+    assert!(run_with_path("2025-04-15 show week", &path).contains("08:00"));
+}
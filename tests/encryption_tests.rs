@@ -0,0 +1,116 @@
+use chrono::NaiveDate;
+use rstest::*;
+use std::fs;
+use tempfile::TempDir;
+use timereport::storage::{is_encrypted, EncryptedFile, JsonFile, Storage};
+use timereport::{decrypt, encrypt, main};
+mod utils;
+use utils::temp_dir;
+
+fn run_with_storage(s: &str, storage: &dyn Storage) -> String {
+    let args: Vec<String> = s.split_whitespace().map(|s| s.to_string()).collect();
+    main(
+        args,
+        storage,
+        NaiveDate::from_ymd_opt(2025, 4, 16)
+            .and_then(|date| date.and_hms_opt(12, 0, 0))
+            .expect(""),
+    )
+}
+
+fn encrypted(temp_dir: &TempDir, passphrase: &str) -> EncryptedFile {
+    EncryptedFile::new(
+        &temp_dir.path().join("timereport.json"),
+        &temp_dir.path().join("timereport.toml"),
+        passphrase,
+    )
+}
+
+fn plain(temp_dir: &TempDir) -> JsonFile {
+    JsonFile::new(
+        &temp_dir.path().join("timereport.json"),
+        &temp_dir.path().join("timereport.toml"),
+    )
+}
+
+#[rstest]
+fn encrypted_file_keeps_data_between_commands(temp_dir: TempDir) {
+    run_with_storage("2025-04-15 start 8", &encrypted(&temp_dir, "secret"));
+
+    let output = run_with_storage("2025-04-15 show week", &encrypted(&temp_dir, "secret"));
+
+    assert!(output.contains("08:00"));
+    let bytes = fs::read(temp_dir.path().join("timereport.json")).expect("");
+    assert!(!String::from_utf8_lossy(&bytes).contains("2025-04-15"));
+    assert!(is_encrypted(&temp_dir.path().join("timereport.json")));
+}
+
+#[rstest]
+fn wrong_passphrase_is_reported(temp_dir: TempDir) {
+    run_with_storage("2025-04-15 start 8", &encrypted(&temp_dir, "secret"));
+
+    let output = run_with_storage("2025-04-15 show week", &encrypted(&temp_dir, "guess"));
+
+    assert_eq!(
+        output,
+        format!(
+            "Wrong passphrase for {}",
+            temp_dir.path().join("timereport.json").to_string_lossy()
+        )
+    );
+}
+
+#[rstest]
+fn corrupted_file_is_reported(temp_dir: TempDir) {
+    let path = temp_dir.path().join("timereport.json");
+    run_with_storage("2025-04-15 start 8", &encrypted(&temp_dir, "secret"));
+    let mut bytes = fs::read(&path).expect("");
+    let last = bytes.len() - 1;
+    bytes[last] ^= 1;
+    fs::write(&path, bytes).expect("");
+
+    let output = run_with_storage("2025-04-15 show week", &encrypted(&temp_dir, "secret"));
+
+    assert_eq!(
+        output,
+        format!(
+            "{} is corrupted and cannot be decrypted",
+            path.to_string_lossy()
+        )
+    );
+}
+
+#[rstest]
+fn settings_are_not_encrypted(temp_dir: TempDir) {
+    run_with_storage(
+        "config set working_time_per_day 8:00",
+        &encrypted(&temp_dir, "secret"),
+    );
+
+    let settings = fs::read_to_string(temp_dir.path().join("timereport.toml")).expect("");
+
+    assert!(settings.contains("working_time_per_day = \"08:00\""));
+}
+
+#[rstest]
+fn encrypt_and_decrypt_in_place(temp_dir: TempDir) {
+    let path = temp_dir.path().join("timereport.json");
+    run_with_storage("2024-12-18 start 8", &plain(&temp_dir));
+    run_with_storage("archive 2024", &plain(&temp_dir));
+    run_with_storage("2025-04-15 start 9", &plain(&temp_dir));
+    let before = fs::read_to_string(&path).expect("");
+
+    let output = encrypt(&plain(&temp_dir), &encrypted(&temp_dir, "secret"));
+
+    assert_eq!(output, format!("Encrypted {}", path.to_string_lossy()));
+    assert!(is_encrypted(&path));
+    assert!(is_encrypted(&temp_dir.path().join("timereport-2024.json")));
+    let output = run_with_storage("show december 2024", &encrypted(&temp_dir, "secret"));
+    assert!(output.contains("08:00"));
+
+    let output = decrypt(&encrypted(&temp_dir, "secret"), &plain(&temp_dir));
+
+    assert_eq!(output, format!("Decrypted {}", path.to_string_lossy()));
+    assert_eq!(fs::read_to_string(&path).expect(""), before);
+    assert!(!is_encrypted(&temp_dir.path().join("timereport-2024.json")));
+}
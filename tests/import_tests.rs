@@ -0,0 +1,167 @@
+use chrono::NaiveDate;
+use rstest::*;
+use std::fs;
+use tempfile::TempDir;
+mod utils;
+use utils::*;
+
+const CALENDAR: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+BEGIN:VEVENT\r
+UID:1\r
+DTSTART:20250415T083000\r
+DTEND:20250415T120000\r
+SUMMARY:Meetings\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:2\r
+DTSTART:20250415T130000\r
+DTEND:20250415T161500\r
+SUMMARY:Planning\r
+CATEGORIES:myproject\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:3\r
+DTSTART;VALUE=DATE:20250416\r
+DTEND;VALUE=DATE:20250417\r
+SUMMARY:Vacation\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+fn write_calendar(temp_dir: &TempDir) -> String {
+    let path = temp_dir.path().join("calendar.ics");
+    fs::write(&path, CALENDAR).expect("");
+    path.to_string_lossy().to_string()
+}
+
+fn date() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2025, 4, 15).expect("")
+}
+
+#[rstest]
+fn import_preview_does_not_save(temp_dir: TempDir) {
+    let file = write_calendar(&temp_dir);
+
+    let output = run_mock_date(&format!("import {}", file), &temp_dir, date());
+    assert!(output.contains("\x1b[1m08:30"));
+    assert!(output.contains("\x1b[1m16:15"));
+    assert!(output.contains("Preview only"));
+
+    let output = run_mock_date("show week", &temp_dir, date());
+    assert!(!output.contains("08:30"));
+}
+
+#[rstest]
+fn import_commit_saves(temp_dir: TempDir) {
+    let file = write_calendar(&temp_dir);
+    run("add myproject", &temp_dir);
+
+    run_mock_date(&format!("import {} --commit", file), &temp_dir, date());
+
+    let output = run_mock_date("show week", &temp_dir, date());
+    assert!(output.contains("08:30"));
+    assert!(output.contains("16:15"));
+    assert!(output.contains("03:15"));
+}
+
+#[rstest]
+fn import_date_range(temp_dir: TempDir) {
+    let file = write_calendar(&temp_dir);
+    run("add Vacation", &temp_dir);
+
+    let output = run_mock_date(&format!("import {} 2025-04-16", file), &temp_dir, date());
+
+    assert!(!output.contains("08:30"));
+    assert!(output.contains("\x1b[1m07:45"));
+}
+
+#[rstest]
+fn import_is_one_undoable_step(temp_dir: TempDir) {
+    let file = write_calendar(&temp_dir);
+    run("add Vacation", &temp_dir);
+    run_mock_date(&format!("import {} --commit", file), &temp_dir, date());
+
+    let output = run_mock_date("undo", &temp_dir, date());
+    assert!(!output.contains("08:30"));
+    assert!(!output.contains("07:45"));
+
+    // Undoes "add Vacation"
+    run_mock_date("undo", &temp_dir, date());
+    let output = run_mock_date("undo", &temp_dir, date());
+    assert!(output.contains("Nothing to undo"));
+}
+
+#[rstest]
+fn import_missing_file(temp_dir: TempDir) {
+    let output = run("import does_not_exist.ics", &temp_dir);
+
+    assert!(output.contains("Error when trying to read does_not_exist.ics"));
+}
+
+const TOGGL_CSV: &str = "User,Email,Client,Project,Task,Description,Billable,Start date,Start time,End date,End time,Duration\n\
+    Me,me@example.com,,Project A,,Coding,No,2025-04-15,08:00:00,2025-04-15,10:30:00,02:30:00\n\
+    Me,me@example.com,,,,Email,No,2025-04-15,10:30:00,2025-04-15,16:45:00,06:15:00\n";
+
+fn write_toggl_csv(temp_dir: &TempDir) -> String {
+    let path = temp_dir.path().join("toggl.csv");
+    fs::write(&path, TOGGL_CSV).expect("");
+    path.to_string_lossy().to_string()
+}
+
+#[rstest]
+fn import_toggl_rejects_unknown_project(temp_dir: TempDir) {
+    let file = write_toggl_csv(&temp_dir);
+
+    let output = run(&format!("import --format toggl {}", file), &temp_dir);
+
+    assert!(output.contains("Unknown project 'Project A'"));
+}
+
+#[rstest]
+fn import_toggl_with_known_project(temp_dir: TempDir) {
+    let file = write_toggl_csv(&temp_dir);
+    set_config_value(&temp_dir, "project_names", serde_json::json!(["Project A"]));
+
+    let output = run_mock_date(
+        &format!("import --format toggl {} --commit", file),
+        &temp_dir,
+        date(),
+    );
+
+    assert!(output.contains("\x1b[1m08:00"));
+    assert!(output.contains("\x1b[1m16:45"));
+    assert!(output.contains("\x1b[1m02:30"));
+}
+
+#[rstest]
+fn import_toggl_add_projects_preview(temp_dir: TempDir) {
+    let file = write_toggl_csv(&temp_dir);
+
+    let output = run_mock_date(
+        &format!("import --format toggl {} --add-projects", file),
+        &temp_dir,
+        date(),
+    );
+    assert!(output.contains("2. Project A"));
+    assert!(output.contains("Preview only"));
+
+    let output = run_mock_date("show week", &temp_dir, date());
+    assert!(!output.contains("Project A"));
+}
+
+#[rstest]
+fn import_unknown_format(temp_dir: TempDir) {
+    let file = write_toggl_csv(&temp_dir);
+
+    let output = run(&format!("import --format foo {}", file), &temp_dir);
+
+    assert!(output.contains("Unknown import format 'foo'"));
+}
+
+#[rstest]
+fn format_without_import(temp_dir: TempDir) {
+    let output = run("--format toggl", &temp_dir);
+
+    assert!(output.contains("--format can only be used with import"));
+}
@@ -0,0 +1,62 @@
+use rstest::*;
+use tempfile::TempDir;
+mod utils;
+use utils::*;
+
+#[rstest]
+fn default_lunch_is_implied(temp_dir: TempDir) {
+    run_today("config set lunch.tuesday 45m", &temp_dir);
+
+    let output = run_today("2025-04-15 start 8 stop 17", &temp_dir);
+
+    assert!(row(&output, "lunch")
+        .expect("row exists")
+        .contains("(00:45)"));
+    assert!(row(&output, "Default project")
+        .expect("row exists")
+        .contains("08:15"));
+    assert!(row(&output, "Flex").expect("row exists").contains("00:30"));
+    assert_eq!(
+        read_config(&temp_dir)["days"][0]["lunch"],
+        serde_json::Value::Null
+    );
+}
+
+#[rstest]
+fn recorded_lunch_takes_precedence(temp_dir: TempDir) {
+    run_today("config set lunch.tuesday 45m", &temp_dir);
+
+    let output = run_today("2025-04-15 start 8 stop 17 lunch 30m", &temp_dir);
+
+    assert!(row(&output, "lunch").expect("row exists").contains("00:30"));
+    assert!(!row(&output, "lunch").expect("row exists").contains('('));
+}
+
+#[rstest]
+fn required_break_is_implied(temp_dir: TempDir) {
+    run_today("config set lunch.breaks 6:00 30m, 9:00 45m", &temp_dir);
+
+    let output = run_today("2025-04-15 start 8 stop 18", &temp_dir);
+
+    assert!(row(&output, "lunch")
+        .expect("row exists")
+        .contains("(00:45)"));
+}
+
+#[rstest]
+fn no_implied_lunch_by_default(temp_dir: TempDir) {
+    let output = run_today("2025-04-15 start 8 stop 17", &temp_dir);
+
+    assert!(!row(&output, "lunch").expect("row exists").contains("00:"));
+    assert!(!row(&output, "Flex").expect("row exists").contains("00:"));
+}
+
+#[rstest]
+fn check_accepts_implied_lunch(temp_dir: TempDir) {
+    run_today("2025-04-15 start 8 stop 17", &temp_dir);
+    assert!(run_today("check week", &temp_dir).contains("No lunch"));
+
+    run_today("config set lunch.tuesday 45m", &temp_dir);
+
+    assert!(!run_today("check week", &temp_dir).contains("No lunch"));
+}
@@ -1,19 +1,12 @@
 use chrono::{Duration, Local, NaiveDate};
 use regex::Regex;
 use rstest::*;
-use std::fs::File;
-use std::io::Read;
 use tempfile::TempDir;
 mod utils;
 use utils::*;
 
 fn config_contents(temp_dir: &TempDir) -> String {
-    let mut contents = String::new();
-    let path_buf = temp_dir.path().join("timereport.json");
-    let path = path_buf.as_path();
-    let mut file = File::open(path).expect("");
-    file.read_to_string(&mut contents).expect("");
-    contents
+    read_config(temp_dir).to_string()
 }
 
 #[rstest]
@@ -231,3 +224,129 @@ fn help(temp_dir: TempDir) {
 
     assert!(output.contains("Usage"));
 }
+
+#[rstest]
+fn undo_multiple_days_in_one_step(temp_dir: TempDir) {
+    run("monday tuesday start 8", &temp_dir);
+
+    let output = run("undo", &temp_dir);
+
+    assert!(!output.contains("8:00"));
+}
+
+#[rstest]
+fn undo_several_steps(temp_dir: TempDir) {
+    run("2025-04-15 start 8", &temp_dir);
+    run("2025-04-15 stop 16", &temp_dir);
+    run("2025-04-15 lunch 45m", &temp_dir);
+
+    let output = run("undo 2", &temp_dir);
+
+    assert!(output.contains("08:00"));
+    assert!(!output.contains("16:00"));
+    assert!(!output.contains("00:45"));
+}
+
+#[rstest]
+fn undo_date_keeps_later_changes(temp_dir: TempDir) {
+    run("2025-04-14 start 7", &temp_dir);
+    run("2025-04-15 start 8", &temp_dir);
+    run("2025-04-16 start 9", &temp_dir);
+
+    let output = run("undo --date 2025-04-15", &temp_dir);
+
+    assert!(output.contains("07:00"));
+    assert!(!output.contains("08:00"));
+    assert!(output.contains("09:00"));
+}
+
+#[rstest]
+fn undo_date_without_changes(temp_dir: TempDir) {
+    run("2025-04-14 start 7", &temp_dir);
+
+    let output = run("undo --date 2025-04-15", &temp_dir);
+
+    assert!(output.contains("Nothing to undo for 2025-04-15"));
+}
+
+#[rstest]
+fn undo_add_project(temp_dir: TempDir) {
+    run("add myproject", &temp_dir);
+
+    let output = run("undo", &temp_dir);
+    assert!(!output.contains("myproject"));
+
+    let output = run("redo", &temp_dir);
+    assert!(output.contains("2. myproject"));
+}
+
+#[rstest]
+fn history_lists_commands(temp_dir: TempDir) {
+    run("2025-04-15 start 8", &temp_dir);
+    run("add myproject", &temp_dir);
+
+    let output = run("history", &temp_dir);
+
+    let lines: Vec<&str> = output.lines().collect();
+    assert!(lines[3].contains("add myproject"));
+    assert!(lines[4].contains("2025-04-15 start 8"));
+    assert!(lines[4].contains("│ 2025-04-15 │"));
+}
+
+#[rstest]
+fn history_shows_the_time_of_the_command(temp_dir: TempDir) {
+    let now = today().and_hms_opt(9, 30, 0).expect("valid time");
+    run_mock_time("2025-04-15 start 8", &temp_dir, now);
+
+    let output = run("history", &temp_dir);
+
+    assert!(output.contains("│ 2025-04-16 09:30 │ 2025-04-15 start 8 │"));
+}
+
+#[rstest]
+fn history_is_empty(temp_dir: TempDir) {
+    let output = run("history", &temp_dir);
+
+    assert!(output.contains("No history"));
+}
+
+#[rstest]
+fn clear_stop(temp_dir: TempDir) {
+    run("2025-04-15 start 8 stop 16", &temp_dir);
+
+    let output = run("2025-04-15 clear stop", &temp_dir);
+
+    assert!(output.contains("08:00"));
+    assert!(!output.contains("16:00"));
+}
+
+#[rstest]
+fn clear_day(temp_dir: TempDir) {
+    run("2025-04-15 start 8 stop 16 lunch 45m", &temp_dir);
+
+    let output = run("2025-04-15 clear", &temp_dir);
+
+    assert!(!output.contains("08:00"));
+    assert!(!output.contains("16:00"));
+    assert!(!output.contains("00:45"));
+}
+
+#[rstest]
+fn set_after_clear(temp_dir: TempDir) {
+    run("2025-04-15 start 8", &temp_dir);
+    run("2025-04-15 clear start", &temp_dir);
+
+    let output = run("2025-04-15 start 9", &temp_dir);
+
+    assert!(output.contains("09:00"));
+}
+
+#[rstest]
+fn undo_clear(temp_dir: TempDir) {
+    run("2025-04-15 start 8", &temp_dir);
+    run("2025-04-15 clear start", &temp_dir);
+
+    let output = run("undo", &temp_dir);
+
+    assert!(output.contains("\x1b[1m08:00"));
+}
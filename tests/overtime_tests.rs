@@ -0,0 +1,73 @@
+use rstest::*;
+use tempfile::TempDir;
+mod utils;
+use utils::*;
+
+#[rstest]
+fn marked_overtime_is_not_flex(temp_dir: TempDir) {
+    let output = run_today("2025-04-15 start 8 stop 18 lunch 45m overtime 2", &temp_dir);
+
+    let overtime_row = row(&output, "Overtime marked").expect("overtime row");
+    assert!(overtime_row.contains("02:00"));
+    // 10 hours worked, 45 minutes lunch, 2 hours of overtime and 7:45 working time
+    assert!(row(&output, "Flex").expect("flex row").contains("-00:30"));
+}
+
+#[rstest]
+fn no_overtime_rows_without_overtime(temp_dir: TempDir) {
+    let output = run_today("2025-04-15 start 8 stop 18 lunch 45m", &temp_dir);
+
+    assert!(row(&output, "Overtime").is_none());
+    assert!(row(&output, "Flex").expect("flex row").contains("01:30"));
+}
+
+#[rstest]
+fn evening_overtime_from_rule(temp_dir: TempDir) {
+    run_today("config set overtime.evening_after 18:00", &temp_dir);
+
+    let output = run_today("2025-04-15 start 8 stop 20 lunch 45m", &temp_dir);
+
+    assert!(row(&output, "Overtime evening")
+        .expect("overtime row")
+        .contains("02:00"));
+    assert!(row(&output, "Flex").expect("flex row").contains("01:30"));
+}
+
+#[rstest]
+fn clear_overtime(temp_dir: TempDir) {
+    run_today("2025-04-15 start 8 stop 18 lunch 45m overtime 2", &temp_dir);
+
+    let output = run_today("2025-04-15 clear overtime", &temp_dir);
+
+    assert!(row(&output, "Overtime").is_none());
+}
+
+#[rstest]
+fn month_summary_with_weighted_overtime(temp_dir: TempDir) {
+    run_today("config set overtime.weekends true", &temp_dir);
+    run_today("config set overtime.evening_after 18:00", &temp_dir);
+    run_today("2025-04-15 start 8 stop 20 lunch 45m", &temp_dir);
+    run_today("2025-04-12 start 10 stop 14 lunch 0", &temp_dir);
+
+    let output = run_today("show april 2025", &temp_dir);
+
+    assert!(row(&output, "Overtime evening (x1.5)")
+        .expect("evening row")
+        .contains("02:00"));
+    assert!(row(&output, "Overtime weekend (x2)")
+        .expect("weekend row")
+        .contains("04:00"));
+    // 2 * 1.5 + 4 * 2
+    assert!(row(&output, "Weighted overtime")
+        .expect("weighted row")
+        .contains("11:00"));
+}
+
+#[rstest]
+fn no_month_summary_without_overtime(temp_dir: TempDir) {
+    run_today("2025-04-15 start 8 stop 20 lunch 45m", &temp_dir);
+
+    let output = run_today("show april 2025", &temp_dir);
+
+    assert!(row(&output, "Weighted overtime").is_none());
+}
@@ -0,0 +1,63 @@
+use rstest::*;
+use timereport::storage::{InMemory, Storage};
+use timereport::{combined, main};
+mod utils;
+use utils::*;
+
+fn run_with_storage(s: &str, storage: &dyn Storage) -> String {
+    let args: Vec<String> = s.split_whitespace().map(|s| s.to_string()).collect();
+    main(args, storage, today().and_time(noon()))
+}
+
+fn run_combined(s: &str, profiles: &[(String, &dyn Storage)]) -> String {
+    let args: Vec<String> = s.split_whitespace().map(|s| s.to_string()).collect();
+    combined(args, profiles, today())
+}
+
+#[rstest]
+fn combined_week_adds_up_profiles() {
+    let default = InMemory::new();
+    let acme = InMemory::new();
+    run_with_storage("2025-04-15 start 8 stop 12", &default);
+    run_with_storage("2025-04-15 start 13 stop 18", &acme);
+    let profiles: Vec<(String, &dyn Storage)> = vec![
+        ("default".to_string(), &default),
+        ("acme".to_string(), &acme),
+    ];
+
+    let output = run_combined("show week", &profiles);
+
+    let lines: Vec<&str> = output.lines().collect();
+    let row = |name: &str| {
+        lines
+            .iter()
+            .find(|line| line.starts_with(&format!("│ {}", name)))
+            .expect("row for profile")
+            .to_string()
+    };
+    assert!(row("default").contains("04:00"));
+    assert!(row("acme").contains("05:00"));
+    assert!(row("Total").contains("09:00"));
+}
+
+#[rstest]
+fn combined_last_week_is_empty() {
+    let default = InMemory::new();
+    run_with_storage("2025-04-15 start 8 stop 12", &default);
+    let profiles: Vec<(String, &dyn Storage)> = vec![("default".to_string(), &default)];
+
+    let output = run_combined("show last week", &profiles);
+
+    assert!(!output.contains("04:00"));
+}
+
+#[rstest]
+fn combined_needs_show_week() {
+    let default = InMemory::new();
+    let profiles: Vec<(String, &dyn Storage)> = vec![("default".to_string(), &default)];
+
+    assert_eq!(
+        run_combined("start 8", &profiles),
+        "Usage: t [DATE] show [last] week --all-profiles"
+    );
+}
@@ -91,3 +91,23 @@ fn subtract_project_time_from_default_project_time(temp_dir: TempDir) {
 
     assert!(output.contains("6:15"));
 }
+
+#[rstest]
+fn clear_project(temp_dir: TempDir) {
+    run("add p1", &temp_dir);
+    run("add p2", &temp_dir);
+    run("2025-04-15 project p1 1", &temp_dir);
+    run("2025-04-15 project p2 2", &temp_dir);
+
+    let output = run("2025-04-15 clear project 2", &temp_dir);
+
+    assert!(!output.contains("01:00"));
+    assert!(output.contains("02:00"));
+}
+
+#[rstest]
+fn clear_unknown_project(temp_dir: TempDir) {
+    let output = run("2025-04-15 clear project p1", &temp_dir);
+
+    assert!(output.contains("Unknown project 'p1'"));
+}
@@ -0,0 +1,102 @@
+use chrono::NaiveTime;
+use rstest::*;
+use tempfile::TempDir;
+mod utils;
+use utils::*;
+
+/// The style of the provisional values
+const GRAY: &str = "\u{1b}[90m";
+
+#[rstest]
+fn provisional_values_with_only_start(temp_dir: TempDir) {
+    let output = run_today("2025-04-16 start 0:00 lunch 30m", &temp_dir);
+
+    assert!(row(&output, "stop").expect("stop row").contains(GRAY));
+    assert!(row(&output, "Default project")
+        .expect("default project row")
+        .contains(GRAY));
+    assert!(row(&output, "Flex").expect("flex row").contains(GRAY));
+    // 00:00 + 00:30 lunch + 07:45 working time
+    assert!(row(&output, "Leave at")
+        .expect("leave at row")
+        .contains("08:15"));
+}
+
+#[rstest]
+fn provisional_values_are_not_stored(temp_dir: TempDir) {
+    run_today("2025-04-16 start 0:00 lunch 30m", &temp_dir);
+
+    assert_eq!(
+        read_config(&temp_dir)["days"][0]["stop"],
+        serde_json::Value::Null
+    );
+}
+
+#[rstest]
+fn leave_at_uses_implied_lunch(temp_dir: TempDir) {
+    run_today("config set lunch.wednesday 45m", &temp_dir);
+
+    let output = run_today("2025-04-16 start 8", &temp_dir);
+
+    assert!(row(&output, "Leave at")
+        .expect("leave at row")
+        .contains("16:30"));
+}
+
+#[rstest]
+fn provisional_stop_is_the_given_time(temp_dir: TempDir) {
+    let now = today().and_time(NaiveTime::from_hms_opt(10, 30, 0).expect(""));
+
+    let output = run_mock_time("2025-04-16 start 8", &temp_dir, now);
+
+    assert!(row(&output, "stop").expect("stop row").contains("10:30"));
+}
+
+#[rstest]
+fn leave_at_includes_required_break(temp_dir: TempDir) {
+    run_today("config set lunch.breaks 6:00 30m", &temp_dir);
+
+    let output = run_today("2025-04-16 start 8", &temp_dir);
+
+    // 08:00 + 07:45 working time + 00:30 break after 6 hours
+    assert!(row(&output, "Leave at")
+        .expect("leave at row")
+        .contains("16:15"));
+}
+
+#[rstest]
+fn leave_at_after_midnight(temp_dir: TempDir) {
+    let output = run_today("2025-04-16 start 0:00 overtime 20:00", &temp_dir);
+
+    assert!(row(&output, "Leave at")
+        .expect("leave at row")
+        .contains("after 24:00"));
+}
+
+#[rstest]
+fn no_provisional_values_with_stop(temp_dir: TempDir) {
+    let output = run_today("2025-04-16 start 0:00 stop 8 lunch 30m", &temp_dir);
+
+    assert!(row(&output, "Leave at").is_none());
+    assert!(!output.contains(GRAY));
+}
+
+#[rstest]
+fn no_provisional_values_on_other_days(temp_dir: TempDir) {
+    let output = run_today("2025-04-15 start 0:00 lunch 30m", &temp_dir);
+
+    assert!(row(&output, "Leave at").is_none());
+    assert!(!output.contains(GRAY));
+}
+
+#[rstest]
+fn flex_stays_last_row(temp_dir: TempDir) {
+    let output = run_today("2025-04-16 start 0:00", &temp_dir);
+
+    let rows: Vec<&str> = output
+        .lines()
+        .filter(|line| line.starts_with('│'))
+        .collect();
+    assert!(rows[rows.len() - 1].contains("Flex"));
+    assert!(rows[rows.len() - 2].contains("Leave at"));
+}
@@ -0,0 +1,73 @@
+use rstest::*;
+use tempfile::TempDir;
+mod utils;
+use utils::*;
+
+fn round_to_quarters(temp_dir: &TempDir) {
+    run_today("config set rounding.start down 15", temp_dir);
+    run_today("config set rounding.stop up 15", temp_dir);
+}
+
+#[rstest]
+fn rounded_in_report_but_stored_exactly(temp_dir: TempDir) {
+    round_to_quarters(&temp_dir);
+
+    let output = run_today("2025-04-15 start 8:03 stop 16:58 lunch 45m", &temp_dir);
+
+    assert!(row(&output, "start").expect("row exists").contains("08:00"));
+    assert!(row(&output, "stop").expect("row exists").contains("17:00"));
+    let days = read_config(&temp_dir)["days"].clone();
+    assert_eq!(days[0]["start"], "08:03:00");
+    assert_eq!(days[0]["stop"], "16:58:00");
+}
+
+#[rstest]
+fn rounded_on_entry(temp_dir: TempDir) {
+    round_to_quarters(&temp_dir);
+    run_today("config set rounding.apply entry", &temp_dir);
+
+    let output = run_today("2025-04-15 start 8:03 stop 16:58 lunch 45m", &temp_dir);
+
+    assert!(output.contains("Rounded start 08:03 to 08:00, stop 16:58 to 17:00"));
+    let days = read_config(&temp_dir)["days"].clone();
+    assert_eq!(days[0]["start"], "08:00:00");
+    assert_eq!(days[0]["stop"], "17:00:00");
+}
+
+#[rstest]
+fn rounded_project_time(temp_dir: TempDir) {
+    run_today("add ProjectA", &temp_dir);
+    run_today("config set rounding.project nearest 30", &temp_dir);
+
+    let output = run_today(
+        "2025-04-15 start 8 stop 17 lunch 1 project ProjectA 1:20",
+        &temp_dir,
+    );
+
+    assert!(row(&output, "ProjectA")
+        .expect("row exists")
+        .contains("01:30"));
+}
+
+#[rstest]
+fn check_shows_rounding_difference(temp_dir: TempDir) {
+    round_to_quarters(&temp_dir);
+    run_today("2025-04-15 start 8:03 stop 16:58 lunch 45m", &temp_dir);
+    run_today("2025-04-14 start 8:00 stop 16:45 lunch 45m", &temp_dir);
+
+    let output = run_today("check week", &temp_dir);
+
+    assert!(output.contains(
+        "Rounding:\n  2025-04-15 Tue: start 08:03 to 08:00, stop 16:58 to 17:00, +00:05 worked\n  Total: +00:05 worked"
+    ));
+}
+
+#[rstest]
+fn check_without_rounding_differences(temp_dir: TempDir) {
+    round_to_quarters(&temp_dir);
+    run_today("2025-04-15 start 8:00 stop 16:45 lunch 45m", &temp_dir);
+
+    let output = run_today("check week", &temp_dir);
+
+    assert!(!output.contains("Rounding"));
+}
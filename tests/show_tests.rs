@@ -77,3 +77,161 @@ fn show_month_last_day_of_month(temp_dir: TempDir) {
 
     assert!(output.contains(format!("{}-01-31", current_year).as_str()));
 }
+
+#[rstest]
+fn show_week_html_uses_configured_decimal_separator(temp_dir: TempDir) {
+    set_config_value(
+        &temp_dir,
+        "copy_format",
+        serde_json::json!({"decimal_separator": "."}),
+    );
+    run("start 10 stop 17 lunch 0", &temp_dir);
+    run("show week html", &temp_dir);
+    FILE_CONTENT.with(|content| {
+        let content = content.borrow();
+        assert!(content.contains("0.75"));
+    })
+}
+
+#[rstest]
+fn show_week_html_hhmm_copy_format(temp_dir: TempDir) {
+    set_config_value(
+        &temp_dir,
+        "copy_format",
+        serde_json::json!({"time_format": "hhmm"}),
+    );
+    run("start 8 stop 16 lunch 45m", &temp_dir);
+    run("show week html", &temp_dir);
+    FILE_CONTENT.with(|content| {
+        let content = content.borrow();
+        assert!(content.contains("07:15"));
+        assert!(!content.contains("7,25"));
+    })
+}
+
+#[rstest]
+fn show_week_html_column_layout(temp_dir: TempDir) {
+    set_config_value(
+        &temp_dir,
+        "copy_format",
+        serde_json::json!({"layout": "columns"}),
+    );
+    run("show week html", &temp_dir);
+    FILE_CONTENT.with(|content| {
+        let content = content.borrow();
+        assert!(content.contains(")\">Copy</button>"));
+        assert!(!content.contains(")\">1. Default project</button>"));
+    })
+}
+
+#[rstest]
+fn show_week_html_column_layout_copies_only_projects(temp_dir: TempDir) {
+    set_config_value(
+        &temp_dir,
+        "copy_format",
+        serde_json::json!({"layout": "columns", "time_format": "hhmm"}),
+    );
+    run_today("add myproject", &temp_dir);
+    run_today(
+        "2025-04-15 start 8 stop 18 lunch 45m project myproject 1 overtime 2",
+        &temp_dir,
+    );
+    run_mock_date("show week html", &temp_dir, today());
+    FILE_CONTENT.with(|content| {
+        let content = content.borrow();
+        // The default project and myproject, without the overtime and flex
+        assert!(content.contains("copyToClipboard('08:15\\n01:00')"));
+    })
+}
+
+#[rstest]
+fn show_week_ics_contains_work_event(temp_dir: TempDir) {
+    run("2025-04-15 start 8 stop 16:30 lunch 45m", &temp_dir);
+    let output = run_mock_date(
+        "show week ics",
+        &temp_dir,
+        NaiveDate::from_ymd_opt(2025, 4, 15).expect(""),
+    );
+
+    assert!(output.starts_with("BEGIN:VCALENDAR\r\n"));
+    assert!(output.ends_with("END:VCALENDAR\r\n"));
+    assert!(output.contains("UID:20250415-work-"));
+    assert!(output.contains("DTSTART:20250415T080000\r\n"));
+    assert!(output.contains("DTEND:20250415T163000\r\n"));
+    assert!(output.contains("DESCRIPTION:Lunch: 00:45\\n1. Default project: 07:45"));
+}
+
+#[rstest]
+fn show_week_ics_absence_is_all_day_event(temp_dir: TempDir) {
+    run("add Vacation", &temp_dir);
+    run("2025-04-16 project Vacation 7:45", &temp_dir);
+    let output = run_mock_date(
+        "show week ics",
+        &temp_dir,
+        NaiveDate::from_ymd_opt(2025, 4, 16).expect(""),
+    );
+
+    assert!(output.contains("UID:20250416-absence-"));
+    assert!(output.contains("DTSTART;VALUE=DATE:20250416\r\n"));
+    assert!(output.contains("DTEND;VALUE=DATE:20250417\r\n"));
+    assert!(output.contains("SUMMARY:Vacation\r\n"));
+}
+
+#[rstest]
+fn show_month_ics_has_only_the_days_of_the_month(temp_dir: TempDir) {
+    run("2025-03-31 start 8 stop 16", &temp_dir);
+    run("2025-04-01 start 8 stop 16", &temp_dir);
+
+    let output = run("show april 2025 ics", &temp_dir);
+
+    assert!(output.contains("DTSTART:20250401T080000\r\n"));
+    assert!(!output.contains("DTSTART:20250331T080000\r\n"));
+}
+
+#[rstest]
+fn ics_uids_differ_between_data_files(temp_dir: TempDir) {
+    let other_dir = tempfile::tempdir().expect("");
+    let uid = |temp_dir: &TempDir| {
+        run("2025-04-15 start 8 stop 16", temp_dir);
+        let output = run("show april 2025 ics", temp_dir);
+        output
+            .lines()
+            .find(|line| line.starts_with("UID:"))
+            .expect("an event")
+            .to_string()
+    };
+
+    assert_ne!(uid(&temp_dir), uid(&other_dir));
+    assert_eq!(uid(&temp_dir), uid(&temp_dir));
+}
+
+#[rstest]
+fn ics_uids_do_not_depend_on_how_the_path_is_given(temp_dir: TempDir) {
+    run("2025-04-15 start 8 stop 16", &temp_dir);
+    let path = data_path(&temp_dir);
+    let other_path = temp_dir
+        .path()
+        .join("subdirectory")
+        .join("..")
+        .join(path.file_name().expect("file name"));
+    std::fs::create_dir(temp_dir.path().join("subdirectory")).expect("");
+    let uid = |path: &std::path::Path| {
+        let output = timereport::main(
+            vec![
+                "show".to_string(),
+                "april".to_string(),
+                "2025".to_string(),
+                "ics".to_string(),
+            ],
+            timereport::storage::from_path(path).as_ref(),
+            today().and_time(noon()),
+        );
+        output
+            .lines()
+            .find(|line| line.starts_with("UID:"))
+            .expect("an event")
+            .to_string()
+    };
+
+    assert_eq!(uid(&other_path), uid(&path));
+}
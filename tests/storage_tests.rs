@@ -0,0 +1,75 @@
+use chrono::NaiveDate;
+use rstest::*;
+use timereport::main;
+use timereport::storage::{InMemory, JsonFile, ReadOnly, Storage};
+
+fn run_with_storage(s: &str, storage: &dyn Storage) -> String {
+    let args: Vec<String> = s.split_whitespace().map(|s| s.to_string()).collect();
+    main(
+        args,
+        storage,
+        NaiveDate::from_ymd_opt(2025, 4, 16)
+            .and_then(|date| date.and_hms_opt(12, 0, 0))
+            .expect(""),
+    )
+}
+
+#[rstest]
+fn in_memory_storage_keeps_data_between_commands() {
+    let storage = InMemory::new();
+    run_with_storage("2025-04-15 start 8", &storage);
+    run_with_storage("2025-04-15 stop 16", &storage);
+
+    let output = run_with_storage("2025-04-15 show week", &storage);
+
+    assert!(output.contains("08:00"));
+    assert!(output.contains("16:00"));
+}
+
+#[rstest]
+fn in_memory_storage_keeps_archives() {
+    let storage = InMemory::new();
+    run_with_storage("2024-12-18 start 8", &storage);
+
+    let output = run_with_storage("archive 2024", &storage);
+
+    assert_eq!(
+        output,
+        "Moved 1 entries for 2024 to the archive for 2024 in memory"
+    );
+    assert!(run_with_storage("show december 2024", &storage).contains("08:00"));
+}
+
+#[rstest]
+fn read_only_storage_does_not_save() {
+    let storage = InMemory::new();
+    run_with_storage("2025-04-15 start 8", &storage);
+    let read_only = ReadOnly(storage);
+
+    let output = run_with_storage("2025-04-15 stop 16", &read_only);
+
+    assert_eq!(output, "memory is read-only");
+    let output = run_with_storage("2025-04-15 show week", &read_only);
+    assert!(output.contains("08:00"));
+    assert!(!output.contains("16:00"));
+}
+
+#[rstest]
+fn json_file_is_not_changed_when_saving_fails() {
+    let temp_dir = tempfile::tempdir().expect("");
+    let path = temp_dir.path().join("timereport.json");
+    let settings_path = temp_dir.path().join("timereport.toml");
+    let storage = JsonFile::new(&path, &settings_path);
+    run_with_storage("2025-04-15 start 8", &storage);
+    let data = std::fs::read_to_string(&path).expect("");
+    let settings = std::fs::read_to_string(&settings_path).expect("");
+    // Writing the data fails when its temporary file cannot be created
+    std::fs::create_dir(temp_dir.path().join("timereport.json.tmp")).expect("");
+
+    let output = run_with_storage("add Sickness", &storage);
+
+    assert!(output.starts_with("Error writing to file"), "{}", output);
+    assert_eq!(std::fs::read_to_string(&path).expect(""), data);
+    assert_eq!(std::fs::read_to_string(&settings_path).expect(""), settings);
+    assert!(!temp_dir.path().join("timereport.toml.tmp").exists());
+}
@@ -0,0 +1,94 @@
+use chrono::NaiveDate;
+use rstest::*;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+mod utils;
+use utils::*;
+
+fn git(repository: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(repository)
+        .args(["-c", "user.name=Me", "-c", "user.email=me@example.com"])
+        .args(args)
+        .status()
+        .expect("git should be installed");
+    assert!(status.success());
+}
+
+fn commit_at(repository: &Path, time: &str) {
+    let date = format!("2025-04-15T{}:00", time);
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(repository)
+        .args(["-c", "user.name=Me", "-c", "user.email=me@example.com"])
+        .args(["commit", "-q", "--allow-empty", "-m", time, "--date", &date])
+        .env("GIT_COMMITTER_DATE", &date)
+        .status()
+        .expect("git should be installed");
+    assert!(status.success());
+}
+
+fn date() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2025, 4, 15).expect("")
+}
+
+/// Create a repository where main has commits at 10:00 and 12:00, and the
+/// branch feature/x has a commit at 15:00.
+fn create_repository(temp_dir: &TempDir) -> String {
+    let repository = temp_dir.path().join("repository");
+    std::fs::create_dir(&repository).expect("");
+    git(&repository, &["init", "-q", "-b", "main"]);
+    commit_at(&repository, "10:00");
+    commit_at(&repository, "12:00");
+    git(&repository, &["checkout", "-q", "-b", "feature/x"]);
+    commit_at(&repository, "15:00");
+    repository.to_string_lossy().to_string()
+}
+
+#[rstest]
+fn suggest_without_repositories(temp_dir: TempDir) {
+    let output = run("suggest", &temp_dir);
+
+    assert!(output.contains("No git repositories configured"));
+}
+
+#[rstest]
+fn suggest_without_start_and_stop(temp_dir: TempDir) {
+    let repository = create_repository(&temp_dir);
+    set_config_value(
+        &temp_dir,
+        "git_repositories",
+        serde_json::json!([{"path": repository, "project": "p1"}]),
+    );
+
+    let output = run_mock_date("suggest", &temp_dir, date());
+
+    assert!(output.contains("Start and stop must be reported for 2025-04-15 first"));
+}
+
+#[rstest]
+fn suggest_and_record(temp_dir: TempDir) {
+    let repository = create_repository(&temp_dir);
+    run("add p1", &temp_dir);
+    run("add p2", &temp_dir);
+    set_config_value(
+        &temp_dir,
+        "git_repositories",
+        serde_json::json!([{
+            "path": repository,
+            "project": "p1",
+            "branches": [{"pattern": "feature/*", "project": "p2"}]
+        }]),
+    );
+    run("2025-04-15 start 8 stop 16 lunch 1", &temp_dir);
+
+    let output = run_mock_date("suggest", &temp_dir, date());
+    assert!(output.contains("t 2025-04-15 project p1 04:00"));
+    assert!(output.contains("t 2025-04-15 project p2 03:00"));
+
+    let output = run_mock_date("suggest --record", &temp_dir, date());
+    assert!(output.contains("\x1b[1m04:00"));
+    assert!(output.contains("\x1b[1m03:00"));
+}
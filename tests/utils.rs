@@ -1,7 +1,14 @@
-use chrono::{Local, NaiveDate};
+// Each test file uses only some of the helpers
+#![allow(dead_code)]
+
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime};
 use rstest::*;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
 use tempfile::{tempdir, TempDir};
 use timereport::main;
+use timereport::storage::from_path;
 
 #[fixture]
 pub fn temp_dir() -> TempDir {
@@ -9,15 +16,88 @@ pub fn temp_dir() -> TempDir {
     //dir.path().join("timereport.json")
 }
 
+/// The date most tests run on, a Wednesday.
+pub fn today() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2025, 4, 16).expect("valid date")
+}
+
 pub fn run(s: &str, temp_dir: &TempDir) -> String {
-    run_mock_date(s, temp_dir, Local::now().date_naive())
+    run_mock_time(s, temp_dir, Local::now().naive_local())
 }
 
+/// Run at noon on the date.
 pub fn run_mock_date(s: &str, temp_dir: &TempDir, date: NaiveDate) -> String {
+    run_mock_time(s, temp_dir, date.and_time(noon()))
+}
+
+/// Run at noon on `today()`.
+pub fn run_today(s: &str, temp_dir: &TempDir) -> String {
+    run_mock_date(s, temp_dir, today())
+}
+
+pub fn run_mock_time(s: &str, temp_dir: &TempDir, now: NaiveDateTime) -> String {
     let args: Vec<String> = s.split_whitespace().map(|s| s.to_string()).collect();
-    main(
-        args,
-        &temp_dir.path().join("timereport.json").as_path(),
-        date,
-    )
+    main(args, from_path(&data_path(temp_dir)).as_ref(), now)
+}
+
+pub fn noon() -> NaiveTime {
+    NaiveTime::from_hms_opt(12, 0, 0).expect("valid time")
+}
+
+/// The row of the terminal table with the name. Rows that were changed are
+/// highlighted, so look for the name anywhere in the line.
+pub fn row<'a>(output: &'a str, name: &str) -> Option<&'a str> {
+    output
+        .lines()
+        .find(|line| line.starts_with('│') && line.contains(name))
+}
+
+/// The data file used by the tests. Set TIMEREPORT_TEST_BACKEND=sqlite to
+/// run the tests against the SQLite backend instead of the json file.
+pub fn data_path(temp_dir: &TempDir) -> PathBuf {
+    match env::var("TIMEREPORT_TEST_BACKEND").as_deref() {
+        Ok("sqlite") => temp_dir.path().join("timereport.sqlite"),
+        _ => temp_dir.path().join("timereport.json"),
+    }
+}
+
+/// The contents of the data file as json, whatever the backend.
+pub fn read_config(temp_dir: &TempDir) -> serde_json::Value {
+    let path = data_path(temp_dir);
+    let contents = if is_json(&path) {
+        fs::read_to_string(&path).expect("config file should exist")
+    } else {
+        let json_path = temp_dir.path().join("converted.json");
+        main(
+            vec![
+                "convert".to_string(),
+                json_path.to_string_lossy().to_string(),
+            ],
+            from_path(&path).as_ref(),
+            Local::now().naive_local(),
+        );
+        let contents = fs::read_to_string(&json_path).expect("converted file should exist");
+        fs::remove_file(&json_path).expect("converted file should exist");
+        contents
+    };
+    serde_json::from_str(&contents).expect("valid json")
+}
+
+/// Set a value in the settings file, creating the file if needed.
+pub fn set_config_value(temp_dir: &TempDir, key: &str, value: serde_json::Value) {
+    run("", temp_dir); // To create a config file
+    let path = data_path(temp_dir).with_extension("toml");
+    let contents = fs::read_to_string(&path).expect("settings file should exist");
+    let mut settings: toml::Table = toml::from_str(&contents).expect("valid toml");
+    settings.insert(
+        key.to_string(),
+        toml::Value::try_from(value).expect("valid toml value"),
+    );
+    fs::write(&path, toml::to_string(&settings).expect(""))
+        .expect("settings file should be writable");
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == "json")
 }